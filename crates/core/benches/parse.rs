@@ -0,0 +1,164 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use scrapbox_converter_core::{
+    batch::{convert_scrapbox_pages, BatchConfig},
+    parser::{
+        markdown::{self, MarkdownParserContext},
+        scrapbox::{self, ScrapboxParserContext},
+    },
+    visitor::{
+        markdown_printer::{MarkdownPrinter, MarkdownPrinterConfig},
+        scrapbox_printer::{ScrapboxPrinter, ScrapboxPrinterConfig},
+    },
+    Span,
+};
+
+const SIZES: &[usize] = &[100, 1000, 5000];
+
+/// A Scrapbox page of `lines` lines which uses most of the syntax.
+fn scrapbox_page(lines: usize) -> String {
+    let patterns = [
+        "plain text with a #tag and [a link] and `code`",
+        "[** heading] [/ italic] [- strike] [[bold]]",
+        "\t[Rust https://www.rust-lang.org/] https://example.com/a.png",
+        "\t\tnested list item #[multi word tag]",
+        "[$ \\frac{1}{2}] [N35.6812,E139.7671,Z14 Tokyo] abc#notatag",
+        "",
+    ];
+    let mut page = String::from("title\n");
+    for i in 0..lines {
+        page.push_str(patterns[i % patterns.len()]);
+        page.push('\n');
+    }
+    page
+}
+
+/// A Markdown page of `lines` lines which uses most of the syntax.
+fn markdown_page(lines: usize) -> String {
+    let patterns = [
+        "plain text with a #tag and [[a link]] and `code`",
+        "## heading *italic* ~~strike~~ **bold**",
+        "- [Rust](https://www.rust-lang.org/) ![](https://example.com/a.png)",
+        "  - nested list item",
+        "$\\frac{1}{2}$ abc#notatag",
+        "",
+    ];
+    let mut page = String::new();
+    for i in 0..lines {
+        page.push_str(patterns[i % patterns.len()]);
+        page.push('\n');
+    }
+    page
+}
+
+/// One line without any notation, which used to be quadratic.
+fn long_line(chars: usize) -> String {
+    let mut line = "abc ".repeat(chars / 4);
+    line.push('\n');
+    line
+}
+
+/// One line of "[" which isn't closed, with nodes between them
+fn unclosed_line(chars: usize) -> String {
+    let mut line = "[a #b ![c ".repeat(chars / 10);
+    line.push('\n');
+    line
+}
+
+fn parse_scrapbox(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse_scrapbox");
+    for &lines in SIZES {
+        let input = scrapbox_page(lines);
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(lines), &input, |b, input| {
+            b.iter(|| scrapbox::page(Span::new_extra(input, ScrapboxParserContext::default())))
+        });
+    }
+    group.finish();
+}
+
+fn parse_markdown(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse_markdown");
+    for &lines in SIZES {
+        let input = markdown_page(lines);
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(lines), &input, |b, input| {
+            b.iter(|| markdown::page(Span::new_extra(input, MarkdownParserContext::default())))
+        });
+    }
+    group.finish();
+}
+
+fn parse_long_line(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse_long_line");
+    for &chars in &[1000, 10000, 100000] {
+        let input = long_line(chars);
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_with_input(BenchmarkId::new("scrapbox", chars), &input, |b, input| {
+            b.iter(|| scrapbox::page(Span::new_extra(input, ScrapboxParserContext::default())))
+        });
+        group.bench_with_input(BenchmarkId::new("markdown", chars), &input, |b, input| {
+            b.iter(|| markdown::page(Span::new_extra(input, MarkdownParserContext::default())))
+        });
+        let input = unclosed_line(chars);
+        group.bench_with_input(
+            BenchmarkId::new("scrapbox_unclosed", chars),
+            &input,
+            |b, input| {
+                b.iter(|| scrapbox::page(Span::new_extra(input, ScrapboxParserContext::default())))
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("markdown_unclosed", chars),
+            &input,
+            |b, input| {
+                b.iter(|| markdown::page(Span::new_extra(input, MarkdownParserContext::default())))
+            },
+        );
+    }
+    group.finish();
+}
+
+fn convert(c: &mut Criterion) {
+    let mut group = c.benchmark_group("convert");
+    let input = scrapbox_page(1000);
+    group.bench_function("scrapbox_to_markdown", |b| {
+        b.iter(|| {
+            let (_, mut page) =
+                scrapbox::page(Span::new_extra(&input, ScrapboxParserContext::default())).unwrap();
+            MarkdownPrinter::new(MarkdownPrinterConfig::default()).generate(&mut page)
+        })
+    });
+    let input = markdown_page(1000);
+    group.bench_function("markdown_to_scrapbox", |b| {
+        b.iter(|| {
+            let (_, mut page) =
+                markdown::page(Span::new_extra(&input, MarkdownParserContext::default())).unwrap();
+            ScrapboxPrinter::new(ScrapboxPrinterConfig::default()).generate(&mut page)
+        })
+    });
+    group.finish();
+}
+
+/// Run with `--features parallel` to compare
+fn batch(c: &mut Criterion) {
+    let pages: Vec<(String, String)> = (0..2000)
+        .map(|i| (format!("page {}", i), scrapbox_page(50)))
+        .collect();
+    let config = BatchConfig {
+        backlinks: true,
+        ..Default::default()
+    };
+    c.bench_function("batch_2000_pages", |b| {
+        b.iter(|| convert_scrapbox_pages(pages.clone(), &config))
+    });
+}
+
+criterion_group!(
+    benches,
+    parse_scrapbox,
+    parse_markdown,
+    parse_long_line,
+    convert,
+    batch
+);
+criterion_main!(benches);
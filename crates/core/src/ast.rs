@@ -1,43 +1,80 @@
-use serde::Serialize;
-
-#[derive(Debug, Clone, PartialEq, Default, Serialize)]
-pub struct Page {
-    pub nodes: Vec<Node>,
-}
-
-#[derive(Debug, Clone, PartialEq, Serialize)]
-pub struct Node {
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// A page and its metadata.
+/// The metadata comes from front matter in Markdown, and from the first line
+/// (title) and the trailing hashtags (tags) in Scrapbox.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Page<'a> {
+    pub title: Option<Cow<'a, str>>,
+    pub aliases: Vec<Cow<'a, str>>,
+    /// Tags attached to the page itself (e.g. `tags:` in front matter),
+    /// as opposed to the inline `#tag`s in `nodes`.
+    pub tags: Vec<Cow<'a, str>>,
+    pub created: Option<Cow<'a, str>>,
+    pub updated: Option<Cow<'a, str>>,
+    /// Metadata which doesn't have a dedicated field
+    #[schemars(with = "BTreeMap<String, serde_json::Value>")]
+    pub extra: BTreeMap<String, serde_yaml::Value>,
+    pub nodes: Vec<Node<'a>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Node<'a> {
+    #[serde(default)]
     pub id: NodeId,
-    pub kind: NodeKind,
+    pub kind: NodeKind<'a>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Hash, Serialize)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Hash, Serialize, Deserialize, JsonSchema,
+)]
 pub struct NodeId(usize);
 
 /// When parsing the AST, NodeId is given this dummy Id.
 /// Then, during a later phase, it will be replaced.
 pub const DUMMY_NODE_ID: NodeId = NodeId(usize::MIN);
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
-pub enum NodeKind {
-    Paragraph(Paragraph),
-    List(List),
-    HashTag(HashTag),
-    InternalLink(InternalLink),
-    ExternalLink(ExternalLink),
-    Emphasis(Emphasis),
-    Heading(Heading),
-    BlockQuate(BlockQuate),
-    CodeBlock(CodeBlock),
-    Table(Table),
-    Image(Image),
-    Math(Math),
-    Text(Text),
+impl Default for NodeId {
+    fn default() -> Self {
+        DUMMY_NODE_ID
+    }
+}
+
+impl NodeId {
+    pub fn new(id: usize) -> Self {
+        Self(id)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub enum NodeKind<'a> {
+    Paragraph(Paragraph<'a>),
+    List(List<'a>),
+    HashTag(HashTag<'a>),
+    InternalLink(InternalLink<'a>),
+    ExternalLink(ExternalLink<'a>),
+    Emphasis(Emphasis<'a>),
+    Heading(Heading<'a>),
+    BlockQuate(BlockQuate<'a>),
+    CodeBlock(CodeBlock<'a>),
+    Table(Table<'a>),
+    Image(Image<'a>),
+    Embed(Embed<'a>),
+    Location(Location<'a>),
+    Math(Math<'a>),
+    Callout(Callout<'a>),
+    Comment(Comment<'a>),
+    Text(Text<'a>),
     Nop,
 }
 
-impl Node {
-    pub fn new(kind: NodeKind) -> Self {
+impl<'a> Node<'a> {
+    pub fn new(kind: NodeKind<'a>) -> Self {
         Self {
             id: DUMMY_NODE_ID,
             kind,
@@ -45,24 +82,24 @@ impl Node {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
-pub struct Paragraph {
-    pub children: Vec<Node>,
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Paragraph<'a> {
+    pub children: Vec<Node<'a>>,
 }
 
-impl Paragraph {
-    pub fn new(children: Vec<Node>) -> Self {
+impl<'a> Paragraph<'a> {
+    pub fn new(children: Vec<Node<'a>>) -> Self {
         Self { children }
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
-pub struct List {
-    pub children: Vec<ListItem>,
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct List<'a> {
+    pub children: Vec<ListItem<'a>>,
 }
 
-impl List {
-    pub fn new(children: Vec<ListItem>) -> Self {
+impl<'a> List<'a> {
+    pub fn new(children: Vec<ListItem<'a>>) -> Self {
         Self { children }
     }
 
@@ -76,104 +113,133 @@ impl List {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub enum ListKind {
     Disc,
     Decimal,
     Alphabet,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
-pub struct ListItem {
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ListItem<'a> {
     pub kind: ListKind,
+    /// 1 for the top level
     pub level: usize,
-    pub children: Vec<Node>,
+    pub children: Vec<Node<'a>>,
+    /// The indent as it was written like " \t", to print it back as it was.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub indent: Option<Cow<'a, str>>,
+    /// The number of `Decimal` as it was written like "3" of `3. abc`, to print it back as it was.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub number: Option<Cow<'a, str>>,
 }
 
-impl ListItem {
-    pub fn new(kind: ListKind, level: usize, children: Vec<Node>) -> Self {
+impl<'a> ListItem<'a> {
+    pub fn new(kind: ListKind, level: usize, children: Vec<Node<'a>>) -> Self {
         Self {
             kind,
             level,
             children,
+            indent: None,
+            number: None,
         }
     }
 
-    pub fn disc(level: usize, children: Vec<Node>) -> Self {
+    pub fn disc(level: usize, children: Vec<Node<'a>>) -> Self {
         Self {
             kind: ListKind::Disc,
             level,
             children,
+            indent: None,
+            number: None,
         }
     }
 
-    pub fn decimal(level: usize, children: Vec<Node>) -> Self {
+    pub fn decimal(level: usize, children: Vec<Node<'a>>) -> Self {
         Self {
             kind: ListKind::Decimal,
             level,
             children,
+            indent: None,
+            number: None,
         }
     }
 
-    pub fn alphabet(level: usize, children: Vec<Node>) -> Self {
+    pub fn alphabet(level: usize, children: Vec<Node<'a>>) -> Self {
         Self {
             kind: ListKind::Alphabet,
             level,
             children,
+            indent: None,
+            number: None,
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Default, Serialize)]
-pub struct HashTag {
-    pub value: String,
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema)]
+pub struct HashTag<'a> {
+    pub value: Cow<'a, str>,
+    /// true if written as `#[multi word tag]`
+    pub bracketed: bool,
 }
 
-impl HashTag {
-    pub fn new(value: &str) -> Self {
+impl<'a> HashTag<'a> {
+    pub fn new(value: impl Into<Cow<'a, str>>) -> Self {
         Self {
-            value: value.to_string(),
+            value: value.into(),
+            bracketed: false,
+        }
+    }
+
+    pub fn bracketed(value: impl Into<Cow<'a, str>>) -> Self {
+        Self {
+            value: value.into(),
+            bracketed: true,
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
-pub struct BlockQuate {
-    pub value: String,
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct BlockQuate<'a> {
+    pub value: Cow<'a, str>,
 }
 
-impl BlockQuate {
-    pub fn new(value: &str) -> Self {
+impl<'a> BlockQuate<'a> {
+    pub fn new(value: impl Into<Cow<'a, str>>) -> Self {
         Self {
-            value: value.to_string(),
+            value: value.into(),
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
-pub struct CodeBlock {
-    pub file_name: String,
-    pub children: Vec<String>,
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct CodeBlock<'a> {
+    pub file_name: Cow<'a, str>,
+    pub children: Vec<Cow<'a, str>>,
 }
 
-impl CodeBlock {
-    pub fn new(file_name: &str, children: Vec<&str>) -> Self {
+impl<'a> CodeBlock<'a> {
+    pub fn new(file_name: impl Into<Cow<'a, str>>, children: Vec<&'a str>) -> Self {
         Self {
-            file_name: file_name.to_string(),
-            children: children.iter().map(|s| s.to_string()).collect(),
+            file_name: file_name.into(),
+            children: children.into_iter().map(Cow::from).collect(),
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
-pub struct Table {
-    pub name: String,
-    pub header: Vec<String>,
-    pub rows: Vec<Vec<String>>,
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Table<'a> {
+    pub name: Cow<'a, str>,
+    pub header: Vec<Cow<'a, str>>,
+    pub rows: Vec<Vec<Cow<'a, str>>>,
 }
 
-impl Table {
-    pub fn new(name: &str, header: Vec<String>, rows: Vec<Vec<String>>) -> Self {
+impl<'a> Table<'a> {
+    pub fn new(
+        name: impl Into<Cow<'a, str>>,
+        header: Vec<Cow<'a, str>>,
+        rows: Vec<Vec<Cow<'a, str>>>,
+    ) -> Self {
         Self {
             name: name.into(),
             header,
@@ -182,153 +248,484 @@ impl Table {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Default, Serialize)]
-pub struct Text {
-    pub value: String,
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema)]
+pub struct Text<'a> {
+    pub value: Cow<'a, str>,
 }
 
-impl Text {
-    pub fn new(value: &str) -> Self {
+impl<'a> Text<'a> {
+    pub fn new(value: impl Into<Cow<'a, str>>) -> Self {
         Self {
-            value: value.to_string(),
+            value: value.into(),
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Default, Serialize)]
-pub struct InternalLink {
-    pub title: String,
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema)]
+pub struct InternalLink<'a> {
+    pub title: Cow<'a, str>,
+    /// "heading" of `[[title#heading]]` or "^block" of `[[title#^block]]`
+    pub anchor: Option<Cow<'a, str>>,
+    /// "label" of `[[title|label]]`
+    pub label: Option<Cow<'a, str>>,
+    /// true if the page is embedded like `![[title]]`
+    pub embed: bool,
 }
 
-impl InternalLink {
-    pub fn new(title: &str) -> Self {
+impl<'a> InternalLink<'a> {
+    pub fn new(title: impl Into<Cow<'a, str>>) -> Self {
+        Self {
+            title: title.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn embed(title: impl Into<Cow<'a, str>>) -> Self {
         Self {
-            title: title.to_string(),
+            title: title.into(),
+            embed: true,
+            ..Default::default()
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Default, Serialize)]
-pub struct ExternalLink {
-    pub title: Option<String>,
-    pub url: String,
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema)]
+pub struct ExternalLink<'a> {
+    pub title: Option<Cow<'a, str>>,
+    pub url: Cow<'a, str>,
+    /// true if written without brackets like `https://www.rust-lang.org/`
+    #[serde(default)]
+    pub plain: bool,
 }
 
-impl ExternalLink {
-    pub fn new(title: Option<&str>, url: &str) -> Self {
+impl<'a> ExternalLink<'a> {
+    pub fn new(title: Option<&'a str>, url: impl Into<Cow<'a, str>>) -> Self {
         Self {
-            title: title.map(String::from),
-            url: url.to_string(),
+            title: title.map(Cow::from),
+            url: url.into(),
+            plain: false,
+        }
+    }
+
+    pub fn plain(url: impl Into<Cow<'a, str>>) -> Self {
+        Self {
+            title: None,
+            url: url.into(),
+            plain: true,
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Default, Serialize)]
-pub struct Heading {
-    pub text: String,
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema)]
+pub struct Heading<'a> {
+    pub text: Cow<'a, str>,
     pub level: usize,
 }
 
 // TODO(tkat0): replace &str with Node to support "# `code`"
-impl Heading {
-    pub fn new(text: &str, level: usize) -> Self {
+impl<'a> Heading<'a> {
+    pub fn new(text: impl Into<Cow<'a, str>>, level: usize) -> Self {
         Self {
-            text: text.to_string(),
+            text: text.into(),
             level,
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Default, Serialize)]
-pub struct Emphasis {
-    pub text: String,
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Emphasis<'a> {
+    pub text: Cow<'a, str>,
     pub bold: usize,
     pub italic: usize,
     pub strikethrough: usize,
-}
-
-impl Emphasis {
-    pub fn new(text: &str, bold: usize, italic: usize, strikethrough: usize) -> Self {
+    /// `[# marker]` in Scrapbox, `==highlight==` in Obsidian
+    pub highlight: usize,
+    /// `[_ underline]`
+    pub underline: usize,
+    /// `[! emphasized]`
+    pub emphasized: usize,
+    /// `[~ wavy]`
+    pub wavy: usize,
+    /// `[% text]`, which some themes show as strikethrough
+    pub percent: usize,
+    /// `{`, `}`, `<` and `>` as written. They have no style without user CSS.
+    pub custom: Cow<'a, str>,
+    /// true if written as `[[bold]]` instead of `[* bold]`
+    pub double_bracket: bool,
+    /// The marks as written like "/*" of `[/* text]` if they aren't in the order of
+    /// the fields above, to print them back as they were. See `Emphasis::marks`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order: Option<Cow<'a, str>>,
+}
+
+impl<'a> Emphasis<'a> {
+    pub fn new(
+        text: impl Into<Cow<'a, str>>,
+        bold: usize,
+        italic: usize,
+        strikethrough: usize,
+    ) -> Self {
         Self {
-            text: text.to_string(),
+            text: text.into(),
             bold,
             italic,
             strikethrough,
+            ..Default::default()
         }
     }
 
-    pub fn bold(text: &str) -> Self {
+    pub fn bold(text: impl Into<Cow<'a, str>>) -> Self {
         Self {
-            text: text.to_string(),
+            text: text.into(),
             bold: 1,
             ..Default::default()
         }
     }
 
-    pub fn bold_level(text: &str, level: usize) -> Self {
+    pub fn bold_level(text: impl Into<Cow<'a, str>>, level: usize) -> Self {
         Self {
-            text: text.to_string(),
+            text: text.into(),
             bold: level,
             ..Default::default()
         }
     }
 
-    pub fn italic(text: &str) -> Self {
+    pub fn italic(text: impl Into<Cow<'a, str>>) -> Self {
         Self {
-            text: text.to_string(),
+            text: text.into(),
             italic: 1,
             ..Default::default()
         }
     }
 
-    pub fn strikethrough(text: &str) -> Self {
+    pub fn strikethrough(text: impl Into<Cow<'a, str>>) -> Self {
         Self {
-            text: text.to_string(),
+            text: text.into(),
             strikethrough: 1,
             ..Default::default()
         }
     }
+
+    pub fn highlight(text: impl Into<Cow<'a, str>>) -> Self {
+        Self {
+            text: text.into(),
+            highlight: 1,
+            ..Default::default()
+        }
+    }
+
+    pub fn underline(text: impl Into<Cow<'a, str>>) -> Self {
+        Self {
+            text: text.into(),
+            underline: 1,
+            ..Default::default()
+        }
+    }
+
+    /// The marks of Scrapbox like "*/" of `[*/ text]`.
+    /// They are in `order` if it has the same marks, or in the order of the fields.
+    pub fn marks(&self) -> String {
+        fn sorted(marks: &str) -> Vec<char> {
+            let mut chars: Vec<char> = marks.chars().collect();
+            chars.sort_unstable();
+            chars
+        }
+
+        let marks = format!(
+            "{}{}{}{}{}{}{}{}{}",
+            "*".repeat(self.bold),
+            "/".repeat(self.italic),
+            "-".repeat(self.strikethrough),
+            "_".repeat(self.underline),
+            "!".repeat(self.emphasized),
+            "#".repeat(self.highlight),
+            "~".repeat(self.wavy),
+            "%".repeat(self.percent),
+            self.custom,
+        );
+        match &self.order {
+            Some(order) if sorted(order) == sorted(&marks) => order.to_string(),
+            _ => marks,
+        }
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, Default, Serialize)]
-pub struct Image {
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema)]
+pub struct Image<'a> {
     // TODO(tkat0): title
-    pub uri: String,
+    pub uri: Cow<'a, str>,
+    /// `[image_url link_url]`
+    pub link: Option<Cow<'a, str>>,
 }
 
-impl Image {
-    pub fn new(uri: &str) -> Self {
-        Self { uri: uri.into() }
+impl<'a> Image<'a> {
+    pub fn new(uri: impl Into<Cow<'a, str>>) -> Self {
+        Self {
+            uri: uri.into(),
+            link: None,
+        }
+    }
+
+    pub fn with_link(uri: impl Into<Cow<'a, str>>, link: impl Into<Cow<'a, str>>) -> Self {
+        Self {
+            uri: uri.into(),
+            link: Some(link.into()),
+        }
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Default, Serialize)]
-pub struct Math {
-    pub value: String,
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum EmbedProvider {
+    YouTube,
+    Twitter,
+    Vimeo,
+    /// `.mp4` and so on
+    Video,
+    /// `.mp3` and so on
+    Audio,
+}
+
+/// [https://www.youtube.com/watch?v=xxxxxxxxxxx]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Embed<'a> {
+    pub provider: EmbedProvider,
+    pub url: Cow<'a, str>,
 }
 
-impl Math {
-    pub fn new(value: &str) -> Self {
+impl<'a> Embed<'a> {
+    pub fn new(provider: EmbedProvider, url: impl Into<Cow<'a, str>>) -> Self {
+        Self {
+            provider,
+            url: url.into(),
+        }
+    }
+}
+
+/// [N35.6812,E139.7671,Z14 Tokyo Station]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema)]
+pub struct Location<'a> {
+    /// negative for `S`
+    pub latitude: f64,
+    /// negative for `W`
+    pub longitude: f64,
+    pub zoom: u32,
+    pub label: Option<Cow<'a, str>>,
+}
+
+impl<'a> Location<'a> {
+    pub fn new(latitude: f64, longitude: f64, zoom: u32, label: Option<&'a str>) -> Self {
+        Self {
+            latitude,
+            longitude,
+            zoom,
+            label: label.map(Cow::from),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema)]
+pub struct Math<'a> {
+    pub value: Cow<'a, str>,
+}
+
+impl<'a> Math<'a> {
+    pub fn new(value: impl Into<Cow<'a, str>>) -> Self {
+        Self {
+            value: value.into(),
+        }
+    }
+}
+
+/// > [!info] title
+/// > body
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema)]
+pub struct Callout<'a> {
+    /// "info" of `[!info]`
+    pub kind: Cow<'a, str>,
+    pub title: Option<Cow<'a, str>>,
+    /// a Paragraph per line of the body
+    pub children: Vec<Node<'a>>,
+}
+
+impl<'a> Callout<'a> {
+    pub fn new(
+        kind: impl Into<Cow<'a, str>>,
+        title: Option<&'a str>,
+        children: Vec<Node<'a>>,
+    ) -> Self {
+        Self {
+            kind: kind.into(),
+            title: title.map(Cow::from),
+            children,
+        }
+    }
+}
+
+/// %%comment%%
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema)]
+pub struct Comment<'a> {
+    pub value: Cow<'a, str>,
+}
+
+impl<'a> Comment<'a> {
+    pub fn new(value: impl Into<Cow<'a, str>>) -> Self {
         Self {
             value: value.into(),
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Default, Serialize)]
-pub struct HtmlTag {
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema)]
+pub struct HtmlTag<'a> {
     /// "<tag>", "<tag />", "</tag>"
-    pub value: String,
+    pub value: Cow<'a, str>,
 }
 
-impl HtmlTag {
-    pub fn new(value: &str) -> Self {
+impl<'a> HtmlTag<'a> {
+    pub fn new(value: impl Into<Cow<'a, str>>) -> Self {
         Self {
             value: value.into(),
         }
     }
 }
 
+fn owned(value: Cow<'_, str>) -> Cow<'static, str> {
+    Cow::Owned(value.into_owned())
+}
+
+fn owned_vec(values: Vec<Cow<'_, str>>) -> Vec<Cow<'static, str>> {
+    values.into_iter().map(owned).collect()
+}
+
+fn owned_nodes(nodes: Vec<Node<'_>>) -> Vec<Node<'static>> {
+    nodes.into_iter().map(Node::into_owned).collect()
+}
+
+impl<'a> Page<'a> {
+    /// The parsers borrow the text from the input instead of copying it.
+    /// Copy the borrowed text so that the page outlives the input.
+    pub fn into_owned(self) -> Page<'static> {
+        Page {
+            title: self.title.map(owned),
+            aliases: owned_vec(self.aliases),
+            tags: owned_vec(self.tags),
+            created: self.created.map(owned),
+            updated: self.updated.map(owned),
+            extra: self.extra,
+            nodes: owned_nodes(self.nodes),
+        }
+    }
+}
+
+impl<'a> Node<'a> {
+    pub fn into_owned(self) -> Node<'static> {
+        Node {
+            id: self.id,
+            kind: self.kind.into_owned(),
+        }
+    }
+}
+
+impl<'a> NodeKind<'a> {
+    pub fn into_owned(self) -> NodeKind<'static> {
+        match self {
+            NodeKind::Paragraph(v) => NodeKind::Paragraph(Paragraph {
+                children: owned_nodes(v.children),
+            }),
+            NodeKind::List(v) => NodeKind::List(List {
+                children: v.children.into_iter().map(ListItem::into_owned).collect(),
+            }),
+            NodeKind::HashTag(v) => NodeKind::HashTag(HashTag {
+                value: owned(v.value),
+                bracketed: v.bracketed,
+            }),
+            NodeKind::InternalLink(v) => NodeKind::InternalLink(InternalLink {
+                title: owned(v.title),
+                anchor: v.anchor.map(owned),
+                label: v.label.map(owned),
+                embed: v.embed,
+            }),
+            NodeKind::ExternalLink(v) => NodeKind::ExternalLink(ExternalLink {
+                title: v.title.map(owned),
+                url: owned(v.url),
+                plain: v.plain,
+            }),
+            NodeKind::Emphasis(v) => NodeKind::Emphasis(Emphasis {
+                text: owned(v.text),
+                bold: v.bold,
+                italic: v.italic,
+                strikethrough: v.strikethrough,
+                highlight: v.highlight,
+                underline: v.underline,
+                emphasized: v.emphasized,
+                wavy: v.wavy,
+                percent: v.percent,
+                custom: owned(v.custom),
+                double_bracket: v.double_bracket,
+                order: v.order.map(owned),
+            }),
+            NodeKind::Heading(v) => NodeKind::Heading(Heading {
+                text: owned(v.text),
+                level: v.level,
+            }),
+            NodeKind::BlockQuate(v) => NodeKind::BlockQuate(BlockQuate {
+                value: owned(v.value),
+            }),
+            NodeKind::CodeBlock(v) => NodeKind::CodeBlock(CodeBlock {
+                file_name: owned(v.file_name),
+                children: owned_vec(v.children),
+            }),
+            NodeKind::Table(v) => NodeKind::Table(Table {
+                name: owned(v.name),
+                header: owned_vec(v.header),
+                rows: v.rows.into_iter().map(owned_vec).collect(),
+            }),
+            NodeKind::Image(v) => NodeKind::Image(Image {
+                uri: owned(v.uri),
+                link: v.link.map(owned),
+            }),
+            NodeKind::Embed(v) => NodeKind::Embed(Embed {
+                provider: v.provider,
+                url: owned(v.url),
+            }),
+            NodeKind::Location(v) => NodeKind::Location(Location {
+                latitude: v.latitude,
+                longitude: v.longitude,
+                zoom: v.zoom,
+                label: v.label.map(owned),
+            }),
+            NodeKind::Math(v) => NodeKind::Math(Math {
+                value: owned(v.value),
+            }),
+            NodeKind::Callout(v) => NodeKind::Callout(Callout {
+                kind: owned(v.kind),
+                title: v.title.map(owned),
+                children: owned_nodes(v.children),
+            }),
+            NodeKind::Comment(v) => NodeKind::Comment(Comment {
+                value: owned(v.value),
+            }),
+            NodeKind::Text(v) => NodeKind::Text(Text {
+                value: owned(v.value),
+            }),
+            NodeKind::Nop => NodeKind::Nop,
+        }
+    }
+}
+
+impl<'a> ListItem<'a> {
+    pub fn into_owned(self) -> ListItem<'static> {
+        ListItem {
+            kind: self.kind,
+            level: self.level,
+            children: owned_nodes(self.children),
+            indent: self.indent.map(owned),
+            number: self.number.map(owned),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -352,8 +749,27 @@ mod test {
                 )]))),
                 Node::new(NodeKind::BlockQuate(BlockQuate::new("git"))),
             ],
+            ..Default::default()
         };
 
         dbg!(page);
     }
+
+    #[test]
+    fn into_owned_test() {
+        let input = String::from("abc");
+        let page = Page {
+            title: Some(Cow::Borrowed(&input)),
+            nodes: vec![Node::new(NodeKind::Paragraph(Paragraph::new(vec![
+                Node::new(NodeKind::Text(Text::new(input.as_str()))),
+                Node::new(NodeKind::Emphasis(Emphasis::bold(input.as_str()))),
+            ])))],
+            ..Default::default()
+        };
+
+        let owned: Page<'static> = page.clone().into_owned();
+        assert_eq!(owned, page);
+        drop(input);
+        assert_eq!(owned.title.as_deref(), Some("abc"));
+    }
 }
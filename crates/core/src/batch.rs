@@ -0,0 +1,317 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+
+use serde::Serialize;
+
+use crate::ast::Page;
+use crate::graph::LinkGraph;
+use crate::link::{normalize_title, LinkResolver, PathLinkResolver, WikiLinkResolver};
+use crate::parser::scrapbox::{self, ScrapboxParserConfig, ScrapboxParserContext};
+use crate::parser::Span;
+use crate::visitor::markdown_printer::{MarkdownPass, MarkdownPrinter, MarkdownPrinterConfig};
+use crate::visitor::Visitor;
+
+/// How to convert the pages of a Scrapbox project to Markdown.
+#[derive(Clone, Default)]
+pub struct BatchConfig {
+    pub parser: ScrapboxParserConfig,
+    pub pass: MarkdownPass,
+    pub printer: MarkdownPrinterConfig,
+    /// If set, `[link]` is written as a path to the page like `[link](pages/link.md)`.
+    /// If not, it is written as `[[link]]`.
+    pub path_links: Option<PathLinks>,
+    /// If true, `## Backlinks` is appended to each page.
+    pub backlinks: bool,
+}
+
+/// See `PathLinkResolver`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathLinks {
+    /// e.g. "pages/"
+    pub prefix: String,
+    /// e.g. ".md"
+    pub extension: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BatchError {
+    /// The text of the page couldn't be parsed.
+    Parse(String),
+    /// Another page earlier in the batch has the same title after `normalize_title`.
+    DuplicateTitle(String),
+    /// The converter panicked. It is a bug of the converter.
+    Panic(String),
+}
+
+impl fmt::Display for BatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BatchError::Parse(message) => write!(f, "{}", message),
+            BatchError::DuplicateTitle(title) => write!(f, "duplicate title: {}", title),
+            BatchError::Panic(message) => write!(f, "converter panicked: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for BatchError {}
+
+#[derive(Debug)]
+pub struct PageOutput {
+    pub title: String,
+    /// Markdown of the page
+    pub result: Result<String, BatchError>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct BatchStats {
+    pub pages: usize,
+    pub converted: usize,
+    pub failed: usize,
+    pub input_bytes: usize,
+    pub output_bytes: usize,
+    /// Link targets whose page doesn't exist (see `LinkGraph::red_links`)
+    pub red_links: usize,
+    /// Pages with neither links nor backlinks (see `LinkGraph::orphans`)
+    pub orphans: usize,
+}
+
+#[derive(Debug)]
+pub struct BatchOutput {
+    /// In the same order as the input
+    pub pages: Vec<PageOutput>,
+    /// Links between the pages which were parsed
+    pub graph: LinkGraph,
+    pub stats: BatchStats,
+}
+
+/// Convert the pages of a Scrapbox project, given as `(title, text)`, to Markdown.
+///
+/// With the `parallel` feature, the pages are parsed and printed on all cores.
+/// The output doesn't depend on the number of threads: the link graph is built
+/// in the order of the input, and the first page wins when titles conflict.
+/// A page which fails doesn't stop the others.
+pub fn convert_scrapbox_pages<I>(pages: I, config: &BatchConfig) -> BatchOutput
+where
+    I: IntoIterator<Item = (String, String)>,
+{
+    let inputs: Vec<(String, String)> = pages.into_iter().collect();
+
+    let mut seen = HashSet::new();
+    let duplicated: Vec<bool> = inputs
+        .iter()
+        .map(|(title, _)| !seen.insert(normalize_title(title)))
+        .collect();
+
+    let parsed = map(
+        inputs.iter().zip(duplicated).collect(),
+        |((title, text), duplicated)| {
+            if duplicated {
+                return Err(BatchError::DuplicateTitle(title.clone()));
+            }
+            catch_panic(|| parse(title, text, config))
+        },
+    );
+
+    let graph = LinkGraph::from_pages(parsed.iter().filter_map(|page| page.as_ref().ok()));
+    let resolver = config.path_links.as_ref().map(|links| {
+        let titles = parsed
+            .iter()
+            .filter_map(|page| page.as_ref().ok())
+            .filter_map(|page| page.title.as_deref());
+        PathLinkResolver::new(&links.prefix, &links.extension, titles)
+    });
+
+    let results = map(parsed, |page| {
+        let mut page = page?;
+        catch_panic(|| {
+            let mut printer_config = config.printer.clone();
+            if config.backlinks {
+                if let Some(title) = &page.title {
+                    printer_config.backlinks = graph.backlinks(title);
+                }
+            }
+            let link_resolver: Box<dyn LinkResolver> = match &resolver {
+                Some(resolver) => Box::new(resolver.clone()),
+                None => Box::new(WikiLinkResolver),
+            };
+            let mut printer = MarkdownPrinter::with_link_resolver(printer_config, link_resolver);
+            Ok(printer.generate(&mut page))
+        })
+    });
+
+    let mut stats = BatchStats {
+        pages: inputs.len(),
+        input_bytes: inputs.iter().map(|(_, text)| text.len()).sum(),
+        red_links: graph.red_links().len(),
+        orphans: graph.orphans().len(),
+        ..Default::default()
+    };
+    for result in results.iter() {
+        match result {
+            Ok(markdown) => {
+                stats.converted += 1;
+                stats.output_bytes += markdown.len();
+            }
+            Err(_) => stats.failed += 1,
+        }
+    }
+
+    let pages = inputs
+        .into_iter()
+        .zip(results)
+        .map(|((title, _), result)| PageOutput { title, result })
+        .collect();
+
+    BatchOutput {
+        pages,
+        graph,
+        stats,
+    }
+}
+
+/// The title of the page is always `title`, even if `ScrapboxParserConfig::title` is set.
+fn parse<'a>(title: &'a str, text: &'a str, config: &BatchConfig) -> Result<Page<'a>, BatchError> {
+    let context = ScrapboxParserContext {
+        config: config.parser.clone(),
+        ..Default::default()
+    };
+    let mut page = match scrapbox::page(Span::new_extra(text, context)) {
+        Ok((rest, _)) if !rest.is_empty() => {
+            return Err(BatchError::Parse(format!(
+                "{}:{}: parse error: unexpected input",
+                rest.location_line(),
+                rest.location_offset()
+            )))
+        }
+        Ok((_, page)) => page,
+        Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => {
+            return Err(BatchError::Parse(e.message()))
+        }
+        Err(nom::Err::Incomplete(_)) => {
+            return Err(BatchError::Parse("parse error: incomplete input".into()))
+        }
+    };
+    page.title = Some(title.into());
+    let mut pass = config.pass;
+    pass.visit(&mut page);
+    Ok(page)
+}
+
+fn catch_panic<T>(f: impl FnOnce() -> Result<T, BatchError>) -> Result<T, BatchError> {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_default();
+        Err(BatchError::Panic(message))
+    })
+}
+
+/// `Iterator::map` which keeps the order, on all cores with the `parallel` feature.
+#[cfg(feature = "parallel")]
+fn map<T: Send, U: Send>(items: Vec<T>, f: impl Fn(T) -> U + Sync + Send) -> Vec<U> {
+    use rayon::prelude::*;
+    items.into_par_iter().map(f).collect()
+}
+
+#[cfg(not(feature = "parallel"))]
+fn map<T, U>(items: Vec<T>, f: impl Fn(T) -> U) -> Vec<U> {
+    items.into_iter().map(f).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use indoc::indoc;
+
+    fn pages() -> Vec<(String, String)> {
+        [
+            ("A", "link to [B] and #tag\n"),
+            ("B", "[** heading]\nlink to [A] and [missing]\n"),
+            ("C", "no link\n"),
+            ("a", "same title as A\n"),
+        ]
+        .into_iter()
+        .map(|(title, text)| (title.to_string(), text.to_string()))
+        .collect()
+    }
+
+    #[test]
+    fn convert_scrapbox_pages_test() {
+        let config = BatchConfig {
+            path_links: Some(PathLinks {
+                prefix: "pages/".into(),
+                extension: ".md".into(),
+            }),
+            backlinks: true,
+            ..Default::default()
+        };
+        let output = convert_scrapbox_pages(pages(), &config);
+
+        let titles: Vec<&str> = output.pages.iter().map(|p| p.title.as_str()).collect();
+        assert_eq!(titles, vec!["A", "B", "C", "a"]);
+        assert_eq!(
+            output.pages[1].result,
+            Ok(indoc! {"
+                ---
+                title: B
+                ---
+                ## heading
+                link to [A](pages/A.md) and [missing](pages/missing.md)
+
+                ## Backlinks
+
+                * [A](pages/A.md)
+            "}
+            .to_string())
+        );
+        assert_eq!(
+            output.pages[3].result,
+            Err(BatchError::DuplicateTitle("a".into()))
+        );
+        assert_eq!(output.graph.backlinks("A"), vec!["B"]);
+        assert_eq!(
+            output.stats,
+            BatchStats {
+                pages: 4,
+                converted: 3,
+                failed: 1,
+                input_bytes: pages().iter().map(|(_, text)| text.len()).sum(),
+                output_bytes: output
+                    .pages
+                    .iter()
+                    .filter_map(|p| p.result.as_ref().ok())
+                    .map(|markdown| markdown.len())
+                    .sum(),
+                red_links: 2,
+                orphans: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn convert_scrapbox_pages_deterministic_test() {
+        let pages: Vec<(String, String)> = (0..200)
+            .map(|i| {
+                (
+                    format!("page {}", i),
+                    format!("[page {}] #tag{}\n", (i + 1) % 200, i % 7),
+                )
+            })
+            .collect();
+        let config = BatchConfig {
+            backlinks: true,
+            ..Default::default()
+        };
+        let first = convert_scrapbox_pages(pages.clone(), &config);
+        for _ in 0..3 {
+            let output = convert_scrapbox_pages(pages.clone(), &config);
+            let results =
+                |o: &BatchOutput| -> Vec<_> { o.pages.iter().map(|p| p.result.clone()).collect() };
+            assert_eq!(results(&output), results(&first));
+            assert_eq!(output.graph.to_json(), first.graph.to_json());
+        }
+    }
+}
@@ -0,0 +1,434 @@
+use std::borrow::Cow;
+use std::ops::Range;
+
+use crate::ast::*;
+use crate::parser::scrapbox::{
+    self, take_trailing_tags, ScrapboxParserConfig, ScrapboxParserContext,
+};
+use crate::parser::Span;
+
+/// A Scrapbox page which is parsed again only around the edited lines, for editors.
+///
+/// The text is kept as blocks: the source range of each top-level node like
+/// a list or a paragraph with a `code:` block. An edit parses the blocks from
+/// the one before the edit until a block starts at the same place as before,
+/// which gives the same nodes as parsing the whole page.
+/// The top-level nodes are given a `NodeId` unique in the document.
+#[derive(Debug, Clone)]
+pub struct Document {
+    text: String,
+    config: ScrapboxParserConfig,
+    title: Option<String>,
+    /// The end of the title line, or 0 without a title
+    title_end: usize,
+    blocks: Vec<Block>,
+    next_id: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+    /// Byte range in the text, including the last newline
+    pub range: Range<usize>,
+    pub node: Node<'static>,
+}
+
+/// Replaces `range` (in bytes) of the text with `text`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    pub range: Range<usize>,
+    pub text: String,
+}
+
+/// What an edit changed in the AST
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Changes {
+    /// Blocks which were parsed again, as indices after the edit
+    pub blocks: Range<usize>,
+    /// Top-level nodes which were removed or replaced
+    pub removed: Vec<NodeId>,
+    /// Top-level nodes which were added or replaced
+    pub inserted: Vec<NodeId>,
+    /// If true, `Document::title` changed.
+    pub title: bool,
+}
+
+impl Document {
+    pub fn new(text: impl Into<String>, config: ScrapboxParserConfig) -> Self {
+        let mut document = Self {
+            text: String::new(),
+            config,
+            title: None,
+            title_end: 0,
+            blocks: vec![],
+            next_id: 1,
+        };
+        document.apply(TextEdit {
+            range: 0..0,
+            text: text.into(),
+        });
+        document
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn config(&self) -> &ScrapboxParserConfig {
+        &self.config
+    }
+
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    pub fn blocks(&self) -> &[Block] {
+        &self.blocks
+    }
+
+    /// The same page as `scrapbox::page` gives for the text, except `Node::id`.
+    pub fn page(&self) -> Page<'static> {
+        let mut nodes: Vec<Node<'static>> =
+            self.blocks.iter().map(|block| block.node.clone()).collect();
+        let tags = if self.config.tags {
+            take_trailing_tags(&mut nodes)
+        } else {
+            vec![]
+        };
+        Page {
+            title: self.title.clone().map(Cow::Owned),
+            tags,
+            nodes,
+            ..Default::default()
+        }
+    }
+
+    /// Replace the whole text, and parse again only the blocks which differ.
+    /// It is for a text editor which doesn't tell what was edited, like `<textarea>`.
+    pub fn set_text(&mut self, text: &str) -> Changes {
+        let prefix = common_prefix(&self.text, text);
+        let suffix = common_suffix(&self.text[prefix..], &text[prefix..]);
+        self.apply(TextEdit {
+            range: prefix..self.text.len() - suffix,
+            text: text[prefix..text.len() - suffix].to_string(),
+        })
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the range is out of the text, or not on a char boundary.
+    pub fn apply(&mut self, edit: TextEdit) -> Changes {
+        let TextEdit { range, text } = edit;
+        let first_line_end = self.text.find('\n').map_or(self.text.len(), |i| i + 1);
+        self.text.replace_range(range.clone(), &text);
+        let edit_end = range.start + text.len();
+        let shift = |offset: usize| offset + text.len() - range.len();
+
+        let title = self.title.clone();
+        let first = if self.config.title && range.start <= first_line_end {
+            self.parse_title();
+            0
+        } else {
+            // The block which ends at the edit is parsed again too,
+            // because a list grows with the indented lines inserted after it.
+            self.blocks
+                .iter()
+                .position(|block| block.range.end >= range.start)
+                .unwrap_or(self.blocks.len())
+        };
+        let mut offset = match (self.blocks.get(first), first.checked_sub(1)) {
+            (Some(block), _) if first > 0 => block.range.start,
+            (_, Some(prev)) => self.blocks[prev].range.end,
+            _ => self.title_end,
+        };
+
+        let mut blocks = vec![];
+        let last = loop {
+            if offset >= edit_end {
+                let old_offset = offset + range.len() - text.len();
+                if let Ok(i) = self.blocks[first..]
+                    .binary_search_by_key(&old_offset, |block| block.range.start)
+                {
+                    break first + i;
+                }
+            }
+            match self.parse_block(offset) {
+                Some(block) => {
+                    offset = block.range.end;
+                    blocks.push(block);
+                }
+                // The rest isn't parsed like `scrapbox::page`.
+                None => break self.blocks.len(),
+            }
+        };
+
+        // Keep the ids of the blocks which didn't change.
+        let old: Vec<Block> = self.blocks.drain(first..last).collect();
+        let mut kept_head = 0;
+        while kept_head < old.len().min(blocks.len())
+            && old[kept_head].range == blocks[kept_head].range
+            && old[kept_head].node.kind == blocks[kept_head].node.kind
+        {
+            blocks[kept_head].node.id = old[kept_head].node.id;
+            kept_head += 1;
+        }
+        let mut kept_tail = 0;
+        while kept_tail < (old.len() - kept_head).min(blocks.len() - kept_head) {
+            let a = &old[old.len() - 1 - kept_tail];
+            let len = blocks.len();
+            let b = &mut blocks[len - 1 - kept_tail];
+            if a.range.start < range.end
+                || shift(a.range.start) != b.range.start
+                || shift(a.range.end) != b.range.end
+                || a.node.kind != b.node.kind
+            {
+                break;
+            }
+            b.node.id = a.node.id;
+            kept_tail += 1;
+        }
+
+        let ids = |blocks: &[Block]| -> Vec<NodeId> { blocks.iter().map(|b| b.node.id).collect() };
+        let changes = Changes {
+            blocks: first + kept_head..first + blocks.len() - kept_tail,
+            removed: ids(&old[kept_head..old.len() - kept_tail]),
+            inserted: ids(&blocks[kept_head..blocks.len() - kept_tail]),
+            title: self.title != title,
+        };
+
+        for block in self.blocks[first..].iter_mut() {
+            block.range = shift(block.range.start)..shift(block.range.end);
+        }
+        self.blocks.splice(first..first, blocks);
+        changes
+    }
+
+    fn context(&self) -> ScrapboxParserContext {
+        ScrapboxParserContext {
+            config: self.config.clone(),
+            ..Default::default()
+        }
+    }
+
+    fn parse_title(&mut self) {
+        let input = Span::new_extra(self.text.as_str(), self.context());
+        match scrapbox::title(input) {
+            Ok((rest, title)) => {
+                self.title = Some(title.into_owned());
+                self.title_end = rest.location_offset();
+            }
+            Err(_) => {
+                self.title = None;
+                self.title_end = 0;
+            }
+        }
+    }
+
+    fn parse_block(&mut self, offset: usize) -> Option<Block> {
+        if offset >= self.text.len() {
+            return None;
+        }
+        let input = Span::new_extra(&self.text[offset..], self.context());
+        let (rest, node) = scrapbox::block(input).ok()?;
+        let mut node = node.into_owned();
+        node.id = NodeId::new(self.next_id);
+        self.next_id += 1;
+        Some(Block {
+            range: offset..offset + rest.location_offset(),
+            node,
+        })
+    }
+}
+
+/// Length in bytes, on a char boundary
+fn common_prefix(a: &str, b: &str) -> usize {
+    a.char_indices()
+        .zip(b.chars())
+        .find(|((_, a), b)| a != b)
+        .map_or(a.len().min(b.len()), |((i, _), _)| i)
+}
+
+/// Length in bytes, on a char boundary
+fn common_suffix(a: &str, b: &str) -> usize {
+    a.chars()
+        .rev()
+        .zip(b.chars().rev())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a.len_utf8())
+        .sum()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use indoc::indoc;
+    use proptest::prelude::*;
+
+    fn parse(text: &str, config: ScrapboxParserConfig) -> Page<'static> {
+        let context = ScrapboxParserContext {
+            config,
+            ..Default::default()
+        };
+        let (_, page) = scrapbox::page(Span::new_extra(text, context)).unwrap();
+        page.into_owned()
+    }
+
+    fn without_ids(mut page: Page<'static>) -> Page<'static> {
+        for node in page.nodes.iter_mut() {
+            node.id = DUMMY_NODE_ID;
+        }
+        page
+    }
+
+    fn edit(range: Range<usize>, text: &str) -> TextEdit {
+        TextEdit {
+            range,
+            text: text.into(),
+        }
+    }
+
+    #[test]
+    fn blocks_test() {
+        let text = indoc! {"
+            title
+            abc
+             list
+              code:a.js
+               let a = 1;
+
+            [link]"};
+        let document = Document::new(text, ScrapboxParserConfig::default());
+        let ranges: Vec<&str> = document
+            .blocks()
+            .iter()
+            .map(|block| &text[block.range.clone()])
+            .collect();
+        assert_eq!(
+            ranges,
+            vec![
+                "title\n",
+                "abc\n",
+                " list\n  code:a.js\n   let a = 1;\n",
+                "\n",
+                "[link]"
+            ]
+        );
+        assert_eq!(
+            without_ids(document.page()),
+            parse(text, ScrapboxParserConfig::default())
+        );
+    }
+
+    #[test]
+    fn apply_test() {
+        let mut document = Document::new("a\nb\n c\nd\n", ScrapboxParserConfig::default());
+        let ids: Vec<NodeId> = document.page().nodes.iter().map(|node| node.id).collect();
+        assert_eq!(ids.len(), 4);
+
+        // the list of "c" grows
+        let changes = document.apply(edit(7..7, " e\n"));
+        assert_eq!(document.text(), "a\nb\n c\n e\nd\n");
+        assert_eq!(
+            changes,
+            Changes {
+                blocks: 2..3,
+                removed: vec![ids[2]],
+                inserted: vec![NodeId::new(5)],
+                title: false,
+            }
+        );
+        let new_ids: Vec<NodeId> = document.page().nodes.iter().map(|node| node.id).collect();
+        assert_eq!(new_ids, vec![ids[0], ids[1], NodeId::new(5), ids[3]]);
+        assert_eq!(document.blocks()[3].range, 10..12);
+
+        // nothing changes in the AST
+        let changes = document.apply(edit(0..1, "a"));
+        assert_eq!(changes.removed, vec![]);
+        assert_eq!(changes.inserted, vec![]);
+    }
+
+    #[test]
+    fn apply_title_test() {
+        let config = ScrapboxParserConfig {
+            title: true,
+            tags: true,
+            ..Default::default()
+        };
+        let mut document = Document::new("title\nabc\n#tag\n", config);
+        assert_eq!(document.title(), Some("title"));
+        assert_eq!(document.page().tags, vec!["tag"]);
+
+        let changes = document.apply(edit(0..5, "new title"));
+        assert!(changes.title);
+        assert_eq!(document.title(), Some("new title"));
+
+        document.set_text("");
+        assert_eq!(document.title(), None);
+        assert_eq!(document.blocks(), &[]);
+    }
+
+    #[test]
+    fn set_text_test() {
+        let mut document = Document::new("あいう\nえお\n", ScrapboxParserConfig::default());
+        let changes = document.set_text("あいう\nえかお\n");
+        assert_eq!(changes.blocks, 1..2);
+        assert_eq!(
+            without_ids(document.page()),
+            parse("あいう\nえかお\n", ScrapboxParserConfig::default())
+        );
+    }
+
+    fn line() -> impl Strategy<Value = String> {
+        prop::sample::select(vec![
+            "abc",
+            "[link]",
+            "#tag",
+            "code:a.js",
+            "table:t",
+            "a\tb",
+            "`code`",
+            "`",
+            "[* bold",
+            "]",
+            "1. x",
+            "",
+        ])
+        .prop_map(String::from)
+    }
+
+    fn text() -> impl Strategy<Value = String> {
+        prop::collection::vec((0..3usize, line()), 0..12).prop_map(|lines| {
+            lines
+                .into_iter()
+                .map(|(indent, line)| format!("{}{}\n", " ".repeat(indent), line))
+                .collect()
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn apply_same_as_parse_test(
+            text in text(),
+            edits in prop::collection::vec(
+                (any::<prop::sample::Index>(), any::<prop::sample::Index>(), text()),
+                1..4,
+            ),
+            title in any::<bool>(),
+            tags in any::<bool>(),
+        ) {
+            let config = ScrapboxParserConfig { title, tags, ..Default::default() };
+            let mut document = Document::new(text.clone(), config.clone());
+            let mut expected = text;
+            for (start, len, inserted) in edits {
+                // a part of lines
+                let inserted = &inserted[..inserted.len() / 2];
+                let start = start.index(expected.len() + 1);
+                let end = start + len.index(expected.len() - start + 1);
+                document.apply(edit(start..end, inserted));
+                expected.replace_range(start..end, inserted);
+
+                prop_assert_eq!(document.text(), expected.as_str());
+                prop_assert_eq!(without_ids(document.page()), parse(&expected, config.clone()));
+            }
+        }
+    }
+}
@@ -0,0 +1,262 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::ast::Page;
+use crate::parser::scrapbox::{self, ScrapboxParserConfig, ScrapboxParserContext};
+use crate::parser::Span;
+use crate::visitor::scrapbox_printer::{
+    BoldStyle, ScrapboxFormatPass, ScrapboxPrinter, ScrapboxPrinterConfig,
+};
+use crate::visitor::Visitor;
+
+/// The indent of lists. A character is a level.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum IndentChar {
+    #[default]
+    Tab,
+    Space,
+    /// `　`
+    FullWidthSpace,
+}
+
+impl IndentChar {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IndentChar::Tab => "\t",
+            IndentChar::Space => " ",
+            IndentChar::FullWidthSpace => "　",
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FormatConfig {
+    /// If true, the first line is the title of the page.
+    pub title: bool,
+    pub indent: IndentChar,
+    pub bold: BoldStyle,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormatError {
+    /// The text of the page couldn't be parsed.
+    Parse(String),
+    /// The formatted page is parsed to another AST. It is a bug of the formatter.
+    AstChanged,
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatError::Parse(message) => write!(f, "{}", message),
+            FormatError::AstChanged => write!(f, "the formatted page has another AST"),
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+/// Rewrite a Scrapbox page in one style: the indent of lists, the spaces
+/// in brackets like `[*  text　]` and `[[bold]]` or `[* bold]`.
+///
+/// The output is parsed again and returned only if it gives the same AST
+/// as `input`, except for how the notations are written (see `ScrapboxFormatPass`).
+/// A page which `ScrapboxPrinter` can't write back, like a table with an empty row,
+/// is `FormatError::AstChanged`.
+pub fn format_scrapbox(input: &str, config: &FormatConfig) -> Result<String, FormatError> {
+    let page = parse(input, config)?;
+
+    let mut formatted = page.clone();
+    ScrapboxFormatPass { bold: config.bold }.visit(&mut formatted);
+    let output = ScrapboxPrinter::new(ScrapboxPrinterConfig {
+        indent: config.indent.as_str().into(),
+        ..Default::default()
+    })
+    .generate(&mut formatted);
+
+    if normalize(parse(&output, config)?) != normalize(page) {
+        return Err(FormatError::AstChanged);
+    }
+    Ok(output)
+}
+
+fn parse<'a>(input: &'a str, config: &FormatConfig) -> Result<Page<'a>, FormatError> {
+    let context = ScrapboxParserContext {
+        config: ScrapboxParserConfig {
+            title: config.title,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    match scrapbox::page(Span::new_extra(input, context)) {
+        // the rest would be lost
+        Ok((rest, _)) if !rest.is_empty() => Err(FormatError::Parse(format!(
+            "{}:{}: parse error: unexpected input",
+            rest.location_line(),
+            rest.location_offset()
+        ))),
+        Ok((_, page)) => Ok(page),
+        Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => Err(FormatError::Parse(e.message())),
+        Err(nom::Err::Incomplete(_)) => {
+            Err(FormatError::Parse("parse error: incomplete input".into()))
+        }
+    }
+}
+
+/// Drop how the notations are written
+fn normalize(mut page: Page<'_>) -> Page<'_> {
+    ScrapboxFormatPass {
+        bold: BoldStyle::Asterisk,
+    }
+    .visit(&mut page);
+    page
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use indoc::indoc;
+    use proptest::prelude::*;
+    use rstest::rstest;
+
+    const INPUT: &str = indoc! {"
+        title
+        [*  bold　] and [[ bold ]] and [**  heading]
+         1. one
+         2. two
+        \tlist
+        \t 　[[nested]]
+        \t 　code:a.js
+            let a;
+    "};
+
+    #[rstest(indent, bold, expected,
+        case(IndentChar::Tab, BoldStyle::Asterisk, indoc! {"
+            title
+            [* bold] and [* bold] and [** heading]
+            \t1. one
+            \t2. two
+            \tlist
+            \t\t\t[* nested]
+            \t\t\tcode:a.js
+                let a;
+        "}),
+        case(IndentChar::Space, BoldStyle::DoubleBracket, indoc! {"
+            title
+            [[bold]] and [[bold]] and [** heading]
+             1. one
+             2. two
+             list
+               [[nested]]
+               code:a.js
+                let a;
+        "}),
+        case(IndentChar::FullWidthSpace, BoldStyle::Keep, indoc! {"
+            title
+            [* bold] and [[bold]] and [** heading]
+            　1. one
+            　2. two
+            　list
+            　　　[[nested]]
+            　　　code:a.js
+                let a;
+        "}),
+    )]
+    fn format_scrapbox_test(indent: IndentChar, bold: BoldStyle, expected: &str) {
+        let config = FormatConfig {
+            title: true,
+            indent,
+            bold,
+        };
+        let output = format_scrapbox(INPUT, &config).unwrap();
+        assert_eq!(output, expected);
+        assert_eq!(
+            normalize(parse(&output, &config).unwrap()),
+            normalize(parse(INPUT, &config).unwrap())
+        );
+        // formatted twice
+        assert_eq!(format_scrapbox(&output, &config).unwrap(), output);
+    }
+
+    #[test]
+    fn format_scrapbox_code_in_list_test() {
+        let input = " code:a.js\n  let a;\n table:t\n  a\tb\nc\n";
+        let config = FormatConfig {
+            indent: IndentChar::Space,
+            ..Default::default()
+        };
+        assert_eq!(format_scrapbox(input, &config).unwrap(), input);
+    }
+
+    #[test]
+    fn format_scrapbox_numbers_test() {
+        let input = " 3. c\n 4. d\n  10. e\n";
+        assert_eq!(
+            format_scrapbox(input, &FormatConfig::default()).unwrap(),
+            "\t3. c\n\t4. d\n\t\t10. e\n"
+        );
+    }
+
+    #[rstest(
+        input,
+        case(include_str!("../../../src/__test__/help-jp/Scrapboxの使い方_input.txt")),
+        case(include_str!("../../../src/__test__/help-jp/コードブロック_input.txt"))
+    )]
+    fn format_scrapbox_fixture_test(input: &str) {
+        let config = FormatConfig::default();
+        let output = format_scrapbox(input, &config).unwrap();
+        assert_eq!(
+            normalize(parse(&output, &config).unwrap()),
+            normalize(parse(input, &config).unwrap())
+        );
+    }
+
+    fn line() -> impl Strategy<Value = String> {
+        prop::sample::select(vec![
+            "abc",
+            "[link]",
+            "#tag",
+            "code:a.js",
+            "table:t",
+            "a\tb",
+            "`code`",
+            "[*  bold　]",
+            "[[ bold]]",
+            "[*/ both]",
+            "[/* both]",
+            "[",
+            "1. x",
+            "3. y",
+            "",
+        ])
+        .prop_map(String::from)
+    }
+
+    fn indent() -> impl Strategy<Value = String> {
+        prop::collection::vec(prop::sample::select(vec![' ', '\t', '　']), 0..3)
+            .prop_map(|chars| chars.into_iter().collect())
+    }
+
+    proptest! {
+        #[test]
+        fn format_scrapbox_keeps_ast_test(
+            lines in prop::collection::vec((indent(), line()), 0..12),
+            indent in prop::sample::select(vec![IndentChar::Tab, IndentChar::Space, IndentChar::FullWidthSpace]),
+            bold in prop::sample::select(vec![BoldStyle::Keep, BoldStyle::Asterisk, BoldStyle::DoubleBracket]),
+        ) {
+            let input: String = lines
+                .into_iter()
+                // an indented blank line is an empty row of a table
+                .map(|(indent, line)| match line.as_str() {
+                    "" => "\n".to_string(),
+                    line => format!("{}{}\n", indent, line),
+                })
+                .collect();
+            let config = FormatConfig { title: false, indent, bold };
+            let output = format_scrapbox(&input, &config);
+            prop_assert!(output.is_ok(), "{:?}", output);
+        }
+    }
+}
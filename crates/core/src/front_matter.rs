@@ -0,0 +1,225 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_yaml::Value;
+
+use crate::ast::Page;
+
+/// YAML front matter at the top of a Markdown document.
+///
+/// ---
+/// title: title
+/// aliases: [alias]
+/// tags: [tag1, tag2]
+/// created: 2022-07-01
+/// updated: 2022-07-02
+/// ---
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct FrontMatter {
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "scalar"
+    )]
+    pub title: Option<String>,
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        deserialize_with = "string_or_list"
+    )]
+    pub aliases: Vec<String>,
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        deserialize_with = "tags"
+    )]
+    pub tags: Vec<String>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "scalar"
+    )]
+    pub created: Option<String>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "scalar"
+    )]
+    pub updated: Option<String>,
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, Value>,
+}
+
+impl FrontMatter {
+    pub fn from_page(page: &Page<'_>) -> Self {
+        Self {
+            title: page.title.as_deref().map(String::from),
+            aliases: to_strings(&page.aliases),
+            tags: to_strings(&page.tags),
+            created: page.created.as_deref().map(String::from),
+            updated: page.updated.as_deref().map(String::from),
+            extra: page.extra.clone(),
+        }
+    }
+
+    pub fn from_yaml(yaml: &str) -> Result<Self, serde_yaml::Error> {
+        if yaml.trim().is_empty() {
+            return Ok(Self::default());
+        }
+        serde_yaml::from_str(yaml)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Move the metadata into the page.
+    pub fn apply(self, page: &mut Page<'_>) {
+        page.title = self.title.map(Cow::from);
+        page.aliases = self.aliases.into_iter().map(Cow::from).collect();
+        page.tags = self.tags.into_iter().map(Cow::from).collect();
+        page.created = self.created.map(Cow::from);
+        page.updated = self.updated.map(Cow::from);
+        page.extra = self.extra;
+    }
+
+    /// Render with the `---` delimiters.
+    pub fn to_yaml(&self) -> String {
+        let yaml = serde_yaml::to_string(self).expect("front matter is always serializable");
+        format!("---\n{}---\n", yaml)
+    }
+}
+
+fn to_strings(values: &[Cow<'_, str>]) -> Vec<String> {
+    values.iter().map(|s| s.to_string()).collect()
+}
+
+/// `2022-07-01` and `2022` are read as a string.
+fn scalar<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Option::<Value>::deserialize(deserializer)? {
+        Some(Value::String(s)) => Ok(Some(s)),
+        Some(Value::Number(n)) => Ok(Some(n.to_string())),
+        Some(Value::Bool(b)) => Ok(Some(b.to_string())),
+        Some(Value::Null) | None => Ok(None),
+        Some(_) => Err(serde::de::Error::custom("expected a scalar value")),
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StringOrList {
+    String(String),
+    List(Vec<String>),
+}
+
+/// Obsidian accepts `aliases: [a, b]` and `aliases: a, b`.
+fn string_or_list<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let values = match Option::<StringOrList>::deserialize(deserializer)? {
+        Some(StringOrList::String(s)) => s.split(',').map(String::from).collect(),
+        Some(StringOrList::List(list)) => list,
+        None => vec![],
+    };
+
+    Ok(values
+        .iter()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+/// Obsidian accepts `tags: [a, b]`, `tags: a, b` and `tags: a b`.
+fn tags<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let values = match Option::<StringOrList>::deserialize(deserializer)? {
+        Some(StringOrList::String(s)) => s
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .map(String::from)
+            .collect(),
+        Some(StringOrList::List(list)) => list,
+        None => vec![],
+    };
+
+    Ok(values
+        .iter()
+        .map(|s| s.trim().trim_start_matches('#'))
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use indoc::indoc;
+    use rstest::rstest;
+
+    fn tags(tags: &[&str]) -> FrontMatter {
+        FrontMatter {
+            tags: tags.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[rstest(input, expected,
+        case("", FrontMatter::default()),
+        case("tags: [a, b]", tags(&["a", "b"])),
+        case("tags:\n  - a\n  - '#b'\n", tags(&["a", "b"])),
+        case("tags: a, b", tags(&["a", "b"])),
+        case("tags: a b", tags(&["a", "b"])),
+        case("tags:", FrontMatter::default()),
+        case("aliases: a b, c", FrontMatter { aliases: vec!["a b".into(), "c".into()], ..Default::default() }),
+        case("created: 2022-07-01\nupdated: 2022", FrontMatter { created: Some("2022-07-01".into()), updated: Some("2022".into()), ..Default::default() }),
+        case("title: title\nauthor: tkat0", FrontMatter {
+            title: Some("title".into()),
+            extra: BTreeMap::from([("author".into(), Value::String("tkat0".into()))]),
+            ..Default::default()
+        }),
+    )]
+    fn from_yaml_valid_test(input: &str, expected: FrontMatter) {
+        assert_eq!(FrontMatter::from_yaml(input).unwrap(), expected);
+    }
+
+    #[rstest(input, case("title: [a]"), case("tags: {a: b}"))]
+    fn from_yaml_invalid_test(input: &str) {
+        if let Ok(ok) = FrontMatter::from_yaml(input) {
+            panic!("{:?}", ok)
+        }
+    }
+
+    #[test]
+    fn to_yaml_test() {
+        let front_matter = FrontMatter {
+            title: Some("title".into()),
+            aliases: vec!["alias".into()],
+            tags: vec!["a".into(), "b".into()],
+            created: Some("2022-07-01".into()),
+            updated: None,
+            extra: BTreeMap::from([("author".into(), Value::String("tkat0".into()))]),
+        };
+
+        let expected = indoc! {"
+            ---
+            title: title
+            aliases:
+            - alias
+            tags:
+            - a
+            - b
+            created: 2022-07-01
+            author: tkat0
+            ---
+        "};
+
+        assert_eq!(front_matter.to_yaml(), expected);
+    }
+}
@@ -0,0 +1,331 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::Serialize;
+
+use crate::ast::*;
+use crate::link::normalize_title;
+
+/// Links between pages of a project, like the "Links" panel of Scrapbox.
+///
+/// `InternalLink` and `HashTag` are both edges to the page of the title.
+/// Titles are compared after `normalize_title`.
+#[derive(Debug, Default)]
+pub struct LinkGraph {
+    /// normalized title -> title of the pages which exist
+    pages: BTreeMap<String, String>,
+    /// normalized title -> title of the link targets which don't exist (yet)
+    targets: BTreeMap<String, String>,
+    /// normalized title -> normalized titles it links to
+    links: BTreeMap<String, BTreeSet<String>>,
+    /// normalized title -> normalized titles which link to it
+    backlinks: BTreeMap<String, BTreeSet<String>>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct GraphNode<'a> {
+    title: &'a str,
+    exists: bool,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct GraphEdge<'a> {
+    source: &'a str,
+    target: &'a str,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct GraphJson<'a> {
+    nodes: Vec<GraphNode<'a>>,
+    edges: Vec<GraphEdge<'a>>,
+}
+
+impl LinkGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_pages<'a, 'b: 'a, I>(pages: I) -> Self
+    where
+        I: IntoIterator<Item = &'a Page<'b>>,
+    {
+        let mut graph = Self::new();
+        for page in pages {
+            graph.add_page(page);
+        }
+        graph
+    }
+
+    /// Pages without `Page::title` are skipped because nothing can link to them.
+    pub fn add_page(&mut self, page: &Page<'_>) {
+        let title = match &page.title {
+            Some(title) => title,
+            None => return,
+        };
+        let source = normalize_title(title);
+        self.targets.remove(&source);
+        self.pages.insert(source.clone(), title.to_string());
+
+        let mut titles = vec![];
+        collect_links(&page.nodes, &mut titles);
+        let tags = page.tags.iter().map(|tag| tag.to_string());
+        for target_title in titles.into_iter().chain(tags) {
+            let target = normalize_title(&target_title);
+            if target.is_empty() || target == source {
+                continue;
+            }
+            if !self.pages.contains_key(&target) {
+                self.targets
+                    .entry(target.clone())
+                    .or_insert_with(|| target_title.clone());
+            }
+            self.links
+                .entry(source.clone())
+                .or_default()
+                .insert(target.clone());
+            self.backlinks
+                .entry(target)
+                .or_default()
+                .insert(source.clone());
+        }
+    }
+
+    /// Titles the page links to.
+    pub fn links(&self, title: &str) -> Vec<String> {
+        self.titles(self.links.get(&normalize_title(title)))
+    }
+
+    /// Titles of the pages which link to the page.
+    pub fn backlinks(&self, title: &str) -> Vec<String> {
+        self.titles(self.backlinks.get(&normalize_title(title)))
+    }
+
+    /// Pages which link to the same page as the page, grouped by the shared link.
+    /// The page itself and its direct links are excluded.
+    pub fn two_hop_links(&self, title: &str) -> Vec<(String, Vec<String>)> {
+        let source = normalize_title(title);
+        let direct = self.links.get(&source).cloned().unwrap_or_default();
+        let backlinks = self.backlinks.get(&source).cloned().unwrap_or_default();
+
+        let mut result = vec![];
+        for via in direct.iter() {
+            let pages: BTreeSet<String> = self.backlinks[via]
+                .iter()
+                .filter(|t| **t != source && !direct.contains(*t) && !backlinks.contains(*t))
+                .cloned()
+                .collect();
+            if !pages.is_empty() {
+                result.push((self.title(via).to_string(), self.titles(Some(&pages))));
+            }
+        }
+        result
+    }
+
+    /// Pages with neither links nor backlinks.
+    pub fn orphans(&self) -> Vec<String> {
+        self.pages
+            .iter()
+            .filter(|(key, _)| !self.links.contains_key(*key) && !self.backlinks.contains_key(*key))
+            .map(|(_, title)| title.clone())
+            .collect()
+    }
+
+    /// Link targets whose page doesn't exist.
+    pub fn red_links(&self) -> Vec<String> {
+        self.targets.values().cloned().collect()
+    }
+
+    pub fn to_json(&self) -> String {
+        let json = GraphJson {
+            nodes: self
+                .pages
+                .values()
+                .map(|title| GraphNode {
+                    title,
+                    exists: true,
+                })
+                .chain(self.targets.values().map(|title| GraphNode {
+                    title,
+                    exists: false,
+                }))
+                .collect(),
+            edges: self.edges().collect(),
+        };
+        serde_json::to_string_pretty(&json).expect("graph is always serializable")
+    }
+
+    /// GraphViz DOT. Red links are dashed nodes.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph {\n");
+        for title in self.pages.values() {
+            dot.push_str(&format!("  {};\n", quote(title)));
+        }
+        for title in self.targets.values() {
+            dot.push_str(&format!("  {} [style=dashed];\n", quote(title)));
+        }
+        for edge in self.edges() {
+            dot.push_str(&format!(
+                "  {} -> {};\n",
+                quote(edge.source),
+                quote(edge.target)
+            ));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    fn edges(&self) -> impl Iterator<Item = GraphEdge<'_>> {
+        self.links.iter().flat_map(move |(source, targets)| {
+            targets.iter().map(move |target| GraphEdge {
+                source: self.title(source),
+                target: self.title(target),
+            })
+        })
+    }
+
+    fn title<'a>(&'a self, key: &'a str) -> &'a str {
+        self.pages
+            .get(key)
+            .or_else(|| self.targets.get(key))
+            .map(|title| title.as_str())
+            .unwrap_or(key)
+    }
+
+    fn titles(&self, keys: Option<&BTreeSet<String>>) -> Vec<String> {
+        keys.map(|keys| keys.iter().map(|key| self.title(key).to_string()).collect())
+            .unwrap_or_default()
+    }
+}
+
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// The titles of the links and hashtags in `nodes`.
+/// It walks the same nodes as a `Visitor` without the need of `&mut Page`.
+fn collect_links(nodes: &[Node<'_>], titles: &mut Vec<String>) {
+    for node in nodes {
+        match &node.kind {
+            NodeKind::Paragraph(v) => collect_links(&v.children, titles),
+            NodeKind::List(v) => {
+                for item in v.children.iter() {
+                    collect_links(&item.children, titles);
+                }
+            }
+            NodeKind::Callout(v) => collect_links(&v.children, titles),
+            NodeKind::HashTag(v) => titles.push(v.value.to_string()),
+            NodeKind::InternalLink(v) => titles.push(v.title.to_string()),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use indoc::indoc;
+
+    fn page(title: &'static str, links: &[&'static str]) -> Page<'static> {
+        let children = links
+            .iter()
+            .map(|link| match link.strip_prefix('#') {
+                Some(tag) => Node::new(NodeKind::HashTag(HashTag::new(tag))),
+                None => Node::new(NodeKind::InternalLink(InternalLink::new(*link))),
+            })
+            .collect();
+        Page {
+            title: Some(title.into()),
+            nodes: vec![Node::new(NodeKind::List(List::new(vec![ListItem::new(
+                ListKind::Disc,
+                1,
+                vec![Node::new(NodeKind::Paragraph(Paragraph::new(children)))],
+            )])))],
+            ..Default::default()
+        }
+    }
+
+    fn graph() -> LinkGraph {
+        LinkGraph::from_pages(&[
+            page("A", &["B", "#tag"]),
+            page("B", &["c_page"]),
+            page("C page", &["A", "C page"]),
+            page("D", &["#Tag", "missing"]),
+            page("E", &[]),
+        ])
+    }
+
+    #[test]
+    fn links_test() {
+        let graph = graph();
+        assert_eq!(graph.links("a"), vec!["B", "tag"]);
+        assert_eq!(graph.links("C_page"), vec!["A"]);
+        assert_eq!(graph.backlinks("A"), vec!["C page"]);
+        assert_eq!(graph.backlinks("tag"), vec!["A", "D"]);
+        assert_eq!(graph.backlinks("E"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn two_hop_links_test() {
+        let graph = graph();
+        assert_eq!(
+            graph.two_hop_links("A"),
+            vec![("tag".to_string(), vec!["D".to_string()])]
+        );
+        assert_eq!(
+            graph.two_hop_links("D"),
+            vec![("tag".to_string(), vec!["A".to_string()])]
+        );
+    }
+
+    #[test]
+    fn orphans_and_red_links_test() {
+        let graph = graph();
+        assert_eq!(graph.orphans(), vec!["E"]);
+        assert_eq!(graph.red_links(), vec!["missing", "tag"]);
+    }
+
+    #[test]
+    fn red_link_resolved_by_later_page_test() {
+        let graph = LinkGraph::from_pages(&[page("A", &["b"]), page("B", &[])]);
+        assert_eq!(graph.red_links(), Vec::<String>::new());
+        assert_eq!(graph.links("A"), vec!["B"]);
+    }
+
+    #[test]
+    fn to_dot_test() {
+        let graph = LinkGraph::from_pages(&[page("A", &["B \"b\""]), page("C", &[])]);
+        let expected = indoc! {r#"
+            digraph {
+              "A";
+              "C";
+              "B \"b\"" [style=dashed];
+              "A" -> "B \"b\"";
+            }
+        "#};
+        assert_eq!(graph.to_dot(), expected);
+    }
+
+    #[test]
+    fn to_json_test() {
+        let graph = LinkGraph::from_pages(&[page("A", &["B"])]);
+        let expected = indoc! {r#"
+            {
+              "nodes": [
+                {
+                  "title": "A",
+                  "exists": true
+                },
+                {
+                  "title": "B",
+                  "exists": false
+                }
+              ],
+              "edges": [
+                {
+                  "source": "A",
+                  "target": "B"
+                }
+              ]
+            }"#};
+        assert_eq!(graph.to_json(), expected);
+    }
+}
@@ -1,11 +1,28 @@
 use serde::{Deserialize, Serialize};
 
 pub mod ast;
+pub mod batch;
+pub mod document;
+pub mod format;
+pub mod front_matter;
+pub mod graph;
+pub mod line_index;
+pub mod link;
+pub mod lint;
+pub mod media;
+pub mod outline;
 pub mod parser;
+pub mod schema;
+pub mod stream;
 pub mod visitor;
 
-use parser::markdown::IndentKind;
+use media::MediaConfig;
+use parser::markdown::{IndentKind, MarkdownFlavor};
 pub use parser::Span;
+use schema::AstFormat;
+use visitor::markdown_printer::{
+    DecorationFallback, EmbedStyle, LocationStyle, TagNormalization, TitleStyle,
+};
 
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -13,4 +30,32 @@ pub struct Config {
     pub heading1_mapping: usize,
     pub bold_to_heading: bool,
     pub indent: IndentKind,
+    #[serde(default)]
+    pub markdown_flavor: MarkdownFlavor,
+    /// If set, the first line of Scrapbox is the title of the page.
+    #[serde(default)]
+    pub title: Option<TitleStyle>,
+    /// If true, the lines of hashtags at the end of Scrapbox are the tags of the page.
+    #[serde(default)]
+    pub tags: bool,
+    #[serde(default)]
+    pub tag_normalization: TagNormalization,
+    #[serde(default)]
+    pub embed_style: EmbedStyle,
+    #[serde(default)]
+    pub location_style: LocationStyle,
+    #[serde(default)]
+    pub decoration_fallback: DecorationFallback,
+    #[serde(default)]
+    pub ast_format: AstFormat,
+    #[serde(default)]
+    pub media: MediaConfig,
+}
+
+/// The language of an input
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SourceFormat {
+    Scrapbox,
+    Markdown,
+    Org,
 }
@@ -0,0 +1,53 @@
+/// The start of each line of a text, to find the line of a byte offset by binary search.
+pub struct LineIndex<'a> {
+    text: &'a str,
+    /// byte offset of the start of each line
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(text: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self { text, line_starts }
+    }
+
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    /// 0 for the first line. An offset after the end is in the last line.
+    pub fn line(&self, offset: usize) -> usize {
+        self.line_starts
+            .partition_point(|&start| start <= offset)
+            .saturating_sub(1)
+    }
+
+    /// Byte offset of the start of `line`, or `None` after the last line
+    pub fn line_start(&self, line: usize) -> Option<usize> {
+        self.line_starts.get(line).copied()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest(
+        offset,
+        expected,
+        case(0, 0),
+        case(3, 0),
+        case(4, 1),
+        case(8, 2),
+        case(100, 2)
+    )]
+    fn line_test(offset: usize, expected: usize) {
+        let index = LineIndex::new("abc\nde\n");
+        assert_eq!(index.line(offset), expected);
+        assert_eq!(index.line_start(expected), Some([0, 4, 7][expected]));
+        assert_eq!(index.line_start(3), None);
+    }
+}
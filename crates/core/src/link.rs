@@ -0,0 +1,189 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+
+use crate::ast::InternalLink;
+
+/// Characters which break a Markdown link or a file path
+const PATH: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'(')
+    .add(b')')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'[')
+    .add(b'\\')
+    .add(b']')
+    .add(b'^')
+    .add(b'`')
+    .add(b'{')
+    .add(b'|')
+    .add(b'}');
+
+/// Scrapbox treats `_` and ` ` as the same, and ignores case in a page title.
+/// "Page_Title" -> "page title"
+pub fn normalize_title(title: &str) -> String {
+    title.trim().replace('_', " ").to_lowercase()
+}
+
+pub fn percent_encode(value: &str) -> String {
+    utf8_percent_encode(value, PATH).to_string()
+}
+
+/// Decides where `InternalLink` points to.
+pub trait LinkResolver {
+    /// Returns the destination of the link like `pages/title.md`,
+    /// or None to write it as a wiki link `[[title]]`.
+    fn resolve(&mut self, link: &InternalLink) -> Option<String>;
+
+    /// Titles of the links resolved so far which point to a page that doesn't exist.
+    fn missing_links(&self) -> Vec<String> {
+        vec![]
+    }
+}
+
+/// `[[title]]` for wiki-style tools like Obsidian
+#[derive(Debug, Default)]
+pub struct WikiLinkResolver;
+
+impl LinkResolver for WikiLinkResolver {
+    fn resolve(&mut self, _link: &InternalLink) -> Option<String> {
+        None
+    }
+}
+
+/// `[title](pages/title.md)` for MkDocs, Hugo, GitHub, Zola and so on
+///
+/// Clones share the titles, so cloning one for each page is cheap.
+#[derive(Debug, Clone)]
+pub struct PathLinkResolver {
+    /// e.g. "pages/"
+    pub prefix: String,
+    /// e.g. ".md"
+    pub extension: String,
+    /// normalized title -> title
+    pages: Arc<HashMap<String, String>>,
+    /// normalized title -> the title first linked
+    missing: BTreeMap<String, String>,
+}
+
+impl PathLinkResolver {
+    pub fn new<I, S>(prefix: &str, extension: &str, titles: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let pages = titles
+            .into_iter()
+            .map(|title| (normalize_title(title.as_ref()), title.as_ref().to_string()))
+            .collect::<HashMap<_, _>>();
+        Self {
+            prefix: prefix.into(),
+            extension: extension.into(),
+            pages: Arc::new(pages),
+            missing: BTreeMap::new(),
+        }
+    }
+}
+
+impl LinkResolver for PathLinkResolver {
+    fn resolve(&mut self, link: &InternalLink) -> Option<String> {
+        let anchor = link
+            .anchor
+            .as_ref()
+            .map(|anchor| format!("#{}", percent_encode(anchor)))
+            .unwrap_or_default();
+
+        // [[#heading]] is a link in the same page
+        if link.title.is_empty() {
+            return Some(anchor);
+        }
+
+        let normalized = normalize_title(&link.title);
+        let title = match self.pages.get(&normalized) {
+            Some(title) => title.clone(),
+            // the same file for "Foo Bar" and "foo_bar"
+            None => {
+                self.missing
+                    .entry(normalized.clone())
+                    .or_insert_with(|| link.title.to_string());
+                normalized
+            }
+        };
+
+        Some(format!(
+            "{}{}{}{}",
+            self.prefix,
+            percent_encode(&title),
+            self.extension,
+            anchor
+        ))
+    }
+
+    fn missing_links(&self) -> Vec<String> {
+        self.missing.values().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest(
+        input,
+        expected,
+        case("title", "title"),
+        case("Page_Title", "page title"),
+        case(" Page Title ", "page title"),
+        case("ページ", "ページ")
+    )]
+    fn normalize_title_test(input: &str, expected: &str) {
+        assert_eq!(normalize_title(input), expected);
+    }
+
+    #[rstest(input, expected,
+        case(InternalLink::new("Page Title"), Some("pages/Page%20Title.md".into())),
+        case(InternalLink::new("page_title"), Some("pages/Page%20Title.md".into())),
+        case(InternalLink::new("a/b (c)"), Some("pages/a%2Fb%20%28c%29.md".into())),
+        case(InternalLink::new("ページ"), Some("pages/%E3%83%9A%E3%83%BC%E3%82%B8.md".into())),
+        case(InternalLink { title: "Page Title".into(), anchor: Some("heading 1".into()), ..Default::default() }, Some("pages/Page%20Title.md#heading%201".into())),
+        case(InternalLink { title: "".into(), anchor: Some("heading".into()), ..Default::default() }, Some("#heading".into())),
+    )]
+    fn path_link_resolver_test(input: InternalLink, expected: Option<String>) {
+        let mut resolver =
+            PathLinkResolver::new("pages/", ".md", ["Page Title", "a/b (c)", "ページ"]);
+        assert_eq!(resolver.resolve(&input), expected);
+        assert_eq!(resolver.missing_links(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn path_link_resolver_missing_test() {
+        let mut resolver = PathLinkResolver::new("", ".md", ["a"]);
+        assert_eq!(
+            resolver.resolve(&InternalLink::new("b")),
+            Some("b.md".into())
+        );
+        resolver.resolve(&InternalLink::new("A"));
+        resolver.resolve(&InternalLink::new("c"));
+        resolver.resolve(&InternalLink::new("b"));
+        assert_eq!(resolver.missing_links(), vec!["b", "c"]);
+
+        let mut resolver = PathLinkResolver::new("", ".md", ["a"]);
+        assert_eq!(
+            resolver.resolve(&InternalLink::new("Foo Bar")),
+            Some("foo%20bar.md".into())
+        );
+        assert_eq!(
+            resolver.resolve(&InternalLink::new("foo_bar")),
+            Some("foo%20bar.md".into())
+        );
+        assert_eq!(resolver.missing_links(), vec!["Foo Bar"]);
+    }
+}
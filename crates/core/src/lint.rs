@@ -0,0 +1,454 @@
+use serde::Serialize;
+
+use crate::ast::*;
+use crate::line_index::LineIndex;
+use crate::parser::scrapbox::{self, ScrapboxParserConfig, ScrapboxParserContext};
+use crate::parser::{source_offset, Span};
+use crate::visitor::{walk_list, walk_list_item, TransformCommand, Visitor};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Rule {
+    /// `[` without `]` in the line, which is read as a text
+    UnbalancedBracket,
+    /// A line after `code:` which is indented but isn't a line of the code,
+    /// because the indent is not the spaces of the code block
+    CodeBlockIndent,
+    /// A row of `table:` with another number of cells than the header
+    RaggedTable,
+    /// Extra spaces around the URL in `[https://…]`, like `[ https://…]` or `[title  https://…]`
+    LinkSpaces,
+    /// `[` or `]` in a hashtag like `#tag]`
+    HashtagBracket,
+    /// The text from here couldn't be parsed, and the converters drop it
+    ParseError,
+}
+
+impl Rule {
+    pub fn id(&self) -> &'static str {
+        match self {
+            Rule::UnbalancedBracket => "unbalanced-bracket",
+            Rule::CodeBlockIndent => "code-block-indent",
+            Rule::RaggedTable => "ragged-table",
+            Rule::LinkSpaces => "link-spaces",
+            Rule::HashtagBracket => "hashtag-bracket",
+            Rule::ParseError => "parse-error",
+        }
+    }
+
+    pub fn severity(&self) -> Severity {
+        match self {
+            // the rest of the code is written as a text
+            Rule::CodeBlockIndent | Rule::ParseError => Severity::Error,
+            _ => Severity::Warning,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+pub struct Position {
+    /// Byte offset in the input
+    pub offset: usize,
+    /// 1 for the first line
+    pub line: usize,
+    /// 1 for the first character of the line, counted in chars
+    pub column: usize,
+}
+
+impl Position {
+    fn new(index: &LineIndex, offset: usize) -> Self {
+        let line = index.line(offset);
+        let line_start = index.line_start(line).unwrap_or_default();
+        Self {
+            offset,
+            line: line + 1,
+            column: index.text()[line_start..offset].chars().count() + 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    pub rule: Rule,
+    pub severity: Severity,
+    pub message: String,
+    pub start: Position,
+    pub end: Position,
+}
+
+/// Find the notations in a Scrapbox page which are likely to be broken,
+/// and the text the parser couldn't read. The diagnostics are sorted by the position.
+pub fn lint_scrapbox(input: &str, config: ScrapboxParserConfig) -> Vec<Diagnostic> {
+    let context = ScrapboxParserContext {
+        config,
+        ..Default::default()
+    };
+    let index = LineIndex::new(input);
+    let (rest, mut page) = match scrapbox::page(Span::new_extra(input, context)) {
+        Ok(ok) => ok,
+        Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => {
+            return vec![parse_error(&index, e.offset())]
+        }
+        Err(nom::Err::Incomplete(_)) => return vec![parse_error(&index, 0)],
+    };
+
+    let mut linter = Linter {
+        input,
+        index: &index,
+        level: 0,
+        diagnostics: vec![],
+    };
+    linter.visit(&mut page);
+
+    let mut diagnostics = linter.diagnostics;
+    if !rest.is_empty() {
+        diagnostics.push(parse_error(&index, rest.location_offset()));
+    }
+    diagnostics.sort_by_key(|d| d.start.offset);
+    diagnostics
+}
+
+/// The text from `offset` to the end, which the parser couldn't read
+fn parse_error(index: &LineIndex, offset: usize) -> Diagnostic {
+    let rule = Rule::ParseError;
+    Diagnostic {
+        rule,
+        severity: rule.severity(),
+        message: "parse error: unexpected input".into(),
+        start: Position::new(index, offset),
+        end: Position::new(index, index.text().len()),
+    }
+}
+
+pub fn to_json(diagnostics: &[Diagnostic]) -> String {
+    serde_json::to_string_pretty(diagnostics).expect("diagnostics are always serializable")
+}
+
+/// The position of a node is found by `source_offset` of its text.
+/// A node whose text isn't borrowed from `input` has no position and no diagnostic.
+struct Linter<'i> {
+    input: &'i str,
+    index: &'i LineIndex<'i>,
+    /// level of the list item being visited
+    level: usize,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Linter<'_> {
+    fn offset(&self, s: &str) -> Option<usize> {
+        source_offset(self.input, s)
+    }
+
+    /// `[` and `]` in the line around `start..end`
+    fn brackets_around(&self, start: usize, end: usize) -> Option<(usize, usize)> {
+        let line_start = self.input[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = self.input[end..]
+            .find('\n')
+            .map_or(self.input.len(), |i| end + i);
+        let open = self.input[line_start..start].rfind('[')?;
+        let close = self.input[end..line_end].find(']')?;
+        Some((line_start + open, end + close))
+    }
+
+    fn push(&mut self, rule: Rule, start: usize, end: usize, message: String) {
+        self.diagnostics.push(Diagnostic {
+            rule,
+            severity: rule.severity(),
+            message,
+            start: Position::new(self.index, start),
+            end: Position::new(self.index, end),
+        });
+    }
+}
+
+fn is_space(c: char) -> bool {
+    c == ' ' || c == '　'
+}
+
+fn leading_spaces(s: &str) -> usize {
+    s.chars().take_while(|&c| is_space(c)).count()
+}
+
+fn trailing_spaces(s: &str) -> usize {
+    s.chars().rev().take_while(|&c| is_space(c)).count()
+}
+
+impl Visitor for Linter<'_> {
+    fn visit_list<'a>(&mut self, value: &mut List<'a>) -> Option<TransformCommand<'a>> {
+        walk_list(self, value)
+    }
+
+    fn visit_list_item(&mut self, value: &mut ListItem<'_>) {
+        self.level = value.level;
+        walk_list_item(self, value);
+        self.level = 0;
+    }
+
+    fn visit_hashtag<'a>(&mut self, value: &HashTag<'a>) -> Option<TransformCommand<'a>> {
+        if !value.value.contains(['[', ']']) {
+            return None;
+        }
+        if let Some(offset) = self.offset(&value.value) {
+            // `#` or `#[`, and `]`
+            let (start, end) = match value.bracketed {
+                true => (offset - 2, offset + value.value.len() + 1),
+                false => (offset - 1, offset + value.value.len()),
+            };
+            self.push(
+                Rule::HashtagBracket,
+                start,
+                end,
+                format!("hashtag `{}` contains a bracket", value.value),
+            );
+        }
+        None
+    }
+
+    fn visit_external_link<'a>(
+        &mut self,
+        value: &ExternalLink<'a>,
+    ) -> Option<TransformCommand<'a>> {
+        if value.plain {
+            return None;
+        }
+        let url_start = self.offset(&value.url)?;
+        let url_end = url_start + value.url.len();
+        let (open, close) = self.brackets_around(url_start, url_end)?;
+
+        // `[title https://…]` or `[https://… title]` with a space between them
+        let before = &self.input[open + 1..url_start];
+        let after = &self.input[url_end..close];
+        let stray = leading_spaces(before) > 0
+            || trailing_spaces(before) != !before.is_empty() as usize
+            || leading_spaces(after) != !after.is_empty() as usize
+            || trailing_spaces(after) > 0;
+        if stray {
+            self.push(
+                Rule::LinkSpaces,
+                open,
+                close + 1,
+                format!("extra spaces around the URL `{}`", value.url),
+            );
+        }
+        None
+    }
+
+    fn visit_emphasis<'a>(&mut self, value: &Emphasis<'a>) -> Option<TransformCommand<'a>> {
+        // `[ https://…]` is read as a text without decorations
+        let plain = Emphasis {
+            text: value.text.clone(),
+            ..Default::default()
+        };
+        if *value != plain
+            || !(value.text.starts_with("https://") || value.text.starts_with("http://"))
+        {
+            return None;
+        }
+        let start = self.offset(&value.text)?;
+        if let Some((open, close)) = self.brackets_around(start, start + value.text.len()) {
+            self.push(
+                Rule::LinkSpaces,
+                open,
+                close + 1,
+                format!("extra spaces around the URL `{}`", value.text),
+            );
+        }
+        None
+    }
+
+    fn visit_code_block<'a>(&mut self, value: &CodeBlock<'a>) -> Option<TransformCommand<'a>> {
+        let last = value.children.last().unwrap_or(&value.file_name);
+        let end = self.offset(last)? + last.len();
+        let next = self.input[end..].strip_prefix('\n')?;
+        let line = next.split('\n').next().unwrap_or_default();
+        let indent = line
+            .chars()
+            .take_while(|&c| matches!(c, ' ' | '\t' | '　'))
+            .count();
+
+        // a less indented line is the next list item
+        if indent > self.level && !line.trim().is_empty() {
+            let start = end + 1;
+            self.push(
+                Rule::CodeBlockIndent,
+                start,
+                start + line.len(),
+                format!(
+                    "`code:{}` ends here: the lines of the code are indented by {} spaces",
+                    value.file_name,
+                    self.level + 1
+                ),
+            );
+        }
+        None
+    }
+
+    fn visit_table<'a>(&mut self, value: &Table<'a>) -> Option<TransformCommand<'a>> {
+        for row in value.rows.iter() {
+            if row.len() == value.header.len() {
+                continue;
+            }
+            let (first, last) = match (row.first(), row.last()) {
+                (Some(first), Some(last)) => (first, last),
+                _ => continue,
+            };
+            if let (Some(start), Some(end)) = (self.offset(first), self.offset(last)) {
+                self.push(
+                    Rule::RaggedTable,
+                    start,
+                    end + last.len(),
+                    format!(
+                        "the row of `table:{}` has {} cells, but the header has {}",
+                        value.name,
+                        row.len(),
+                        value.header.len()
+                    ),
+                );
+            }
+        }
+        None
+    }
+
+    fn visit_text<'a>(&mut self, value: &Text<'a>) -> Option<TransformCommand<'a>> {
+        if value.value == "[" {
+            if let Some(offset) = self.offset(&value.value) {
+                self.push(
+                    Rule::UnbalancedBracket,
+                    offset,
+                    offset + 1,
+                    "`[` is not closed in the line".into(),
+                );
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use indoc::indoc;
+    use rstest::rstest;
+
+    fn rules(input: &str) -> Vec<(Rule, usize, usize)> {
+        lint_scrapbox(input, ScrapboxParserConfig::default())
+            .into_iter()
+            .map(|d| (d.rule, d.start.line, d.start.column))
+            .collect()
+    }
+
+    #[rstest(input, expected,
+        case("[link] and [abc\n", vec![(Rule::UnbalancedBracket, 1, 12)]),
+        case("abc\n [abc and [", vec![(Rule::UnbalancedBracket, 2, 2), (Rule::UnbalancedBracket, 2, 11)]),
+        case("code:a.js\n let a;\n\tlet b;\n", vec![(Rule::CodeBlockIndent, 3, 1)]),
+        case(" list\n code:a.js\n  let a;\n \tlet b;\n next\n", vec![(Rule::CodeBlockIndent, 4, 1)]),
+        case(" code:a.js\n  let a;\n next\n", vec![]),
+        case("code:a.js\n let a;\n\n", vec![]),
+        case("table:t\n a\tb\n c\n d\te\tf\n g\th\n", vec![(Rule::RaggedTable, 3, 2), (Rule::RaggedTable, 4, 2)]),
+        case("[ https://example.com]", vec![(Rule::LinkSpaces, 1, 1)]),
+        case("[https://example.com ]", vec![(Rule::LinkSpaces, 1, 1)]),
+        case("a [title  https://example.com]", vec![(Rule::LinkSpaces, 1, 3)]),
+        case("[https://example.com  title]", vec![(Rule::LinkSpaces, 1, 1)]),
+        case("[title https://example.com] [https://example.com title] [https://example.com]", vec![]),
+        case("あ #tag] #[a[b] #ok", vec![(Rule::HashtagBracket, 1, 3), (Rule::HashtagBracket, 1, 9)]),
+        case("#[tag] [* bold] `[`", vec![]),
+    )]
+    fn lint_scrapbox_test(input: &str, expected: Vec<(Rule, usize, usize)>) {
+        assert_eq!(rules(input), expected);
+    }
+
+    #[test]
+    fn parse_error_test() {
+        // `scrapbox::page` reads any text so far, so the diagnostic is made by hand
+        let d = parse_error(&LineIndex::new("abc\ndef\n"), 5);
+        assert_eq!(
+            (d.rule, d.severity, d.start, d.end),
+            (
+                Rule::ParseError,
+                Severity::Error,
+                Position {
+                    offset: 5,
+                    line: 2,
+                    column: 2
+                },
+                Position {
+                    offset: 8,
+                    line: 3,
+                    column: 1
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn owned_text_test() {
+        let input = "#tag] [abc\n";
+        let (_, page) =
+            scrapbox::page(Span::new_extra(input, ScrapboxParserContext::default())).unwrap();
+        let index = LineIndex::new(input);
+        let lint = |mut page: Page| {
+            let mut linter = Linter {
+                input,
+                index: &index,
+                level: 0,
+                diagnostics: vec![],
+            };
+            linter.visit(&mut page);
+            linter
+                .diagnostics
+                .into_iter()
+                .map(|d| d.rule)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            lint(page.clone()),
+            vec![Rule::HashtagBracket, Rule::UnbalancedBracket]
+        );
+        // the copied text isn't in the input, so the nodes have no position
+        assert_eq!(lint(page.into_owned()), vec![]);
+    }
+
+    #[test]
+    fn to_json_test() {
+        let diagnostics = lint_scrapbox("title\n#tag]\n", ScrapboxParserConfig::default());
+        let expected = indoc! {r#"
+            [
+              {
+                "rule": "hashtag-bracket",
+                "severity": "warning",
+                "message": "hashtag `tag]` contains a bracket",
+                "start": {
+                  "offset": 6,
+                  "line": 2,
+                  "column": 1
+                },
+                "end": {
+                  "offset": 11,
+                  "line": 2,
+                  "column": 6
+                }
+              }
+            ]"#};
+        assert_eq!(to_json(&diagnostics), expected);
+    }
+
+    #[rstest(
+        input,
+        case(include_str!("../../../src/__test__/help-jp/Scrapboxの使い方_input.txt")),
+        case(include_str!("../../../src/__test__/help-jp/コードブロック_input.txt"))
+    )]
+    fn lint_scrapbox_fixture_test(input: &str) {
+        // the positions are in the input
+        for d in lint_scrapbox(input, ScrapboxParserConfig::default()) {
+            assert!(d.start.offset <= d.end.offset && d.end.offset <= input.len());
+        }
+    }
+}
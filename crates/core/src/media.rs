@@ -0,0 +1,259 @@
+use serde::{Deserialize, Serialize};
+
+use crate::ast::EmbedProvider;
+
+const IMAGE_EXTENSIONS: &[&str] = &["svg", "jpg", "jpeg", "png", "gif", "webp", "avif"];
+
+const IMAGE_HOSTS: &[&str] = &[
+    "https://gyazo.com/",
+    "https://i.gyazo.com/",
+    "https://scrapbox.io/files/",
+];
+
+const VIDEO_EXTENSIONS: &[&str] = &["mp4", "webm", "mov", "ogv"];
+
+const AUDIO_EXTENSIONS: &[&str] = &["mp3", "wav", "m4a", "ogg", "flac"];
+
+/// Rules to recognize a URL or a file name as an image or an embedded media.
+/// The parsers and the printers share it with `Arc`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct MediaConfig {
+    /// e.g. `["png", "jpg"]`. Compared case-insensitively.
+    pub image_extensions: Vec<String>,
+    /// URL prefixes which always point to an image even without an extension.
+    /// e.g. `["https://gyazo.com/"]`
+    pub image_hosts: Vec<String>,
+    pub video_extensions: Vec<String>,
+    pub audio_extensions: Vec<String>,
+}
+
+impl Default for MediaConfig {
+    fn default() -> Self {
+        fn strings(values: &[&str]) -> Vec<String> {
+            values.iter().map(|v| v.to_string()).collect()
+        }
+        Self {
+            image_extensions: strings(IMAGE_EXTENSIONS),
+            image_hosts: strings(IMAGE_HOSTS),
+            video_extensions: strings(VIDEO_EXTENSIONS),
+            audio_extensions: strings(AUDIO_EXTENSIONS),
+        }
+    }
+}
+
+impl MediaConfig {
+    pub fn is_image(&self, url: &str) -> bool {
+        let path = strip_query(url);
+
+        if self.image_hosts.iter().any(|host| path.starts_with(host)) {
+            return true;
+        }
+
+        has_extension(path, &self.image_extensions)
+    }
+
+    /// Scrapbox shows these URLs as a player instead of a link.
+    pub fn embed_provider(&self, url: &str) -> Option<EmbedProvider> {
+        let path = strip_query(url);
+
+        if youtube_id(url).is_some() {
+            Some(EmbedProvider::YouTube)
+        } else if vimeo_id(url).is_some() {
+            Some(EmbedProvider::Vimeo)
+        } else if is_tweet(path) {
+            Some(EmbedProvider::Twitter)
+        } else if has_extension(path, &self.video_extensions) {
+            Some(EmbedProvider::Video)
+        } else if has_extension(path, &self.audio_extensions) {
+            Some(EmbedProvider::Audio)
+        } else {
+            None
+        }
+    }
+}
+
+fn has_extension(path: &str, extensions: &[String]) -> bool {
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    match file_name.rsplit_once('.') {
+        Some((_, ext)) => extensions.iter().any(|e| e.eq_ignore_ascii_case(ext)),
+        None => false,
+    }
+}
+
+fn strip_scheme(url: &str) -> Option<&str> {
+    let url = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))?;
+    Some(url.strip_prefix("www.").unwrap_or(url))
+}
+
+fn is_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// - https://www.youtube.com/watch?v=<id>
+/// - https://youtu.be/<id>
+/// - https://www.youtube.com/shorts/<id>
+pub fn youtube_id(url: &str) -> Option<&str> {
+    let url = strip_scheme(url)?;
+    let id = if let Some(query) = url.strip_prefix("youtube.com/watch?") {
+        query
+            .split('&')
+            .find_map(|param| param.strip_prefix("v="))?
+    } else {
+        url.strip_prefix("youtu.be/")
+            .or_else(|| url.strip_prefix("youtube.com/shorts/"))?
+    };
+    let id = strip_query(id);
+    is_id(id).then_some(id)
+}
+
+/// https://vimeo.com/<id>
+pub fn vimeo_id(url: &str) -> Option<&str> {
+    let id = strip_query(strip_scheme(url)?.strip_prefix("vimeo.com/")?);
+    (!id.is_empty() && id.chars().all(|c| c.is_ascii_digit())).then_some(id)
+}
+
+/// https://twitter.com/<user>/status/<id>
+fn is_tweet(path: &str) -> bool {
+    let path = match strip_scheme(path) {
+        Some(path) => path,
+        None => return false,
+    };
+    let path = match path
+        .strip_prefix("twitter.com/")
+        .or_else(|| path.strip_prefix("x.com/"))
+    {
+        Some(path) => path,
+        None => return false,
+    };
+    matches!(
+        path.split('/').collect::<Vec<_>>().as_slice(),
+        [user, "status", id] if is_id(user) && !id.is_empty() && id.chars().all(|c| c.is_ascii_digit())
+    )
+}
+
+/// "https://example.com/a.png?w=100#top" -> "https://example.com/a.png"
+pub fn strip_query(url: &str) -> &str {
+    url.split(['?', '#']).next().unwrap_or(url)
+}
+
+/// URL which can be put in `<img src>`.
+/// A Gyazo page like `https://gyazo.com/<id>` is a HTML page, not an image.
+pub fn image_source(url: &str) -> String {
+    match url.strip_prefix("https://gyazo.com/") {
+        Some(id) if !id.contains('/') => format!("{}/max_size/400", url),
+        _ => url.into(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest(
+        input,
+        expected,
+        case("https://www.rust-lang.org/static/images/rust-logo-blk.svg", true),
+        case("https://example.com/image.PNG", true),
+        case("https://example.com/image.webp?width=100", true),
+        case("https://example.com/image.avif#top", true),
+        case("test.png", true),
+        case("https://gyazo.com/5f93e65a3b979ae5333aca4f32600611", true),
+        case("https://i.gyazo.com/da78df293f9e83a74b5402411e2f2e01.png", true),
+        case("https://scrapbox.io/files/62c8e4e2d6a6a1001d8a0f4b", true),
+        case("https://www.rust-lang.org/", false),
+        case("https://example.com/image.png.html", false),
+        case("https://example.com/?file=image.png", false),
+        case("https://example.png/", false),
+        case("png", false)
+    )]
+    fn is_image_test(input: &str, expected: bool) {
+        assert_eq!(MediaConfig::default().is_image(input), expected);
+    }
+
+    #[test]
+    fn is_image_custom_test() {
+        let config = MediaConfig {
+            image_extensions: vec!["bmp".into()],
+            image_hosts: vec![],
+            ..Default::default()
+        };
+        assert!(config.is_image("a.bmp"));
+        assert!(!config.is_image("a.png"));
+        assert!(!config.is_image("https://gyazo.com/5f93e65a3b979ae5333aca4f32600611"));
+    }
+
+    #[test]
+    fn deserialize_test() {
+        let config: MediaConfig = serde_json::from_str(r#"{"imageExtensions": ["bmp"]}"#).unwrap();
+        assert!(config.is_image("a.bmp"));
+        assert!(!config.is_image("a.png"));
+        assert_eq!(
+            config.video_extensions,
+            MediaConfig::default().video_extensions
+        );
+    }
+
+    #[rstest(
+        input,
+        expected,
+        case(
+            "https://gyazo.com/5f93e65a3b979ae5333aca4f32600611",
+            "https://gyazo.com/5f93e65a3b979ae5333aca4f32600611/max_size/400"
+        ),
+        case(
+            "https://gyazo.com/5f93e65a3b979ae5333aca4f32600611/max_size/1000",
+            "https://gyazo.com/5f93e65a3b979ae5333aca4f32600611/max_size/1000"
+        ),
+        case(
+            "https://i.gyazo.com/da78df293f9e83a74b5402411e2f2e01.png",
+            "https://i.gyazo.com/da78df293f9e83a74b5402411e2f2e01.png"
+        )
+    )]
+    fn image_source_test(input: &str, expected: &str) {
+        assert_eq!(image_source(input), expected);
+    }
+
+    #[rstest(
+        input,
+        expected,
+        case(
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+            Some(EmbedProvider::YouTube)
+        ),
+        case(
+            "https://youtube.com/watch?t=10&v=dQw4w9WgXcQ",
+            Some(EmbedProvider::YouTube)
+        ),
+        case("https://youtu.be/dQw4w9WgXcQ?t=10", Some(EmbedProvider::YouTube)),
+        case(
+            "https://www.youtube.com/shorts/dQw4w9WgXcQ",
+            Some(EmbedProvider::YouTube)
+        ),
+        case("https://vimeo.com/76979871", Some(EmbedProvider::Vimeo)),
+        case(
+            "https://twitter.com/rustlang/status/1549787419512807424",
+            Some(EmbedProvider::Twitter)
+        ),
+        case(
+            "https://x.com/rustlang/status/1549787419512807424?s=20",
+            Some(EmbedProvider::Twitter)
+        ),
+        case("https://example.com/movie.mp4", Some(EmbedProvider::Video)),
+        case("https://example.com/music.MP3?dl=1", Some(EmbedProvider::Audio)),
+        case("https://www.youtube.com/", None),
+        case("https://www.youtube.com/watch?list=abc", None),
+        case("https://vimeo.com/channels", None),
+        case("https://twitter.com/rustlang", None),
+        case("https://www.rust-lang.org/", None)
+    )]
+    fn embed_provider_test(input: &str, expected: Option<EmbedProvider>) {
+        assert_eq!(MediaConfig::default().embed_provider(input), expected);
+    }
+}
@@ -0,0 +1,311 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::ast::*;
+use crate::line_index::LineIndex;
+use crate::parser::scrapbox::{self, ScrapboxParserConfig, ScrapboxParserContext};
+use crate::parser::{source_offset, Span};
+use crate::visitor::markdown_printer::MarkdownPass;
+use crate::visitor::{TransformCommand, Visitor};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OutlineItem {
+    /// Level of the Markdown heading, 1 for `#`
+    pub level: usize,
+    pub text: String,
+    /// 1 for the first line of the input, or 0 if the heading is not in the input
+    pub line: usize,
+    /// Slug of the heading, unique in the page
+    pub anchor: String,
+    /// The headings after this one with a larger level
+    pub children: Vec<OutlineItem>,
+}
+
+/// The outline of a Scrapbox page, with the headings `MarkdownPass` makes of `[** text]`.
+pub fn scrapbox_outline(
+    input: &str,
+    config: ScrapboxParserConfig,
+    mut pass: MarkdownPass,
+) -> Vec<OutlineItem> {
+    let context = ScrapboxParserContext {
+        config,
+        ..Default::default()
+    };
+    let mut page = match scrapbox::page(Span::new_extra(input, context)) {
+        Ok((_, page)) => page,
+        Err(_) => return vec![],
+    };
+    pass.visit(&mut page);
+    outline(input, &mut page)
+}
+
+/// The outline of the headings of a page which is parsed from `input`.
+/// Only the lines of a heading are in the outline. A heading in a list or after a text
+/// is not a heading in Markdown.
+pub fn outline(input: &str, page: &mut Page<'_>) -> Vec<OutlineItem> {
+    let index = LineIndex::new(input);
+    let mut collector = HeadingCollector {
+        input,
+        index: &index,
+        slugs: HashMap::new(),
+        items: vec![],
+    };
+    collector.visit(page);
+
+    // the open items and their parents
+    let mut stack: Vec<OutlineItem> = vec![];
+    let mut roots = vec![];
+    fn close(stack: &mut Vec<OutlineItem>, roots: &mut Vec<OutlineItem>) {
+        if let Some(item) = stack.pop() {
+            match stack.last_mut() {
+                Some(parent) => parent.children.push(item),
+                None => roots.push(item),
+            }
+        }
+    }
+    for item in collector.items {
+        while matches!(stack.last(), Some(open) if open.level >= item.level) {
+            close(&mut stack, &mut roots);
+        }
+        stack.push(item);
+    }
+    while !stack.is_empty() {
+        close(&mut stack, &mut roots);
+    }
+    roots
+}
+
+pub fn to_json(outline: &[OutlineItem]) -> String {
+    serde_json::to_string_pretty(outline).expect("outline is always serializable")
+}
+
+/// A nested list of the links to the headings, like `* [text](#anchor)`
+pub fn to_markdown(outline: &[OutlineItem], indent: &str) -> String {
+    fn write(document: &mut String, items: &[OutlineItem], indent: &str, depth: usize) {
+        for item in items {
+            document.push_str(&format!(
+                "{}* [{}](#{})\n",
+                indent.repeat(depth),
+                escape_link_text(&item.text),
+                item.anchor
+            ));
+            write(document, &item.children, indent, depth + 1);
+        }
+    }
+    let mut document = String::new();
+    write(&mut document, outline, indent, 0);
+    document
+}
+
+/// `[`, `]` and `\` end or break the text of a link
+fn escape_link_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '[' | ']' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// The anchor of a heading in GitHub: lowercase, without punctuation, and `-` for spaces.
+pub fn slugify(text: &str) -> String {
+    text.trim()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            '-' | '_' => Some(c),
+            c if c.is_alphanumeric() => Some(c),
+            _ => None,
+        })
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+struct HeadingCollector<'i> {
+    input: &'i str,
+    index: &'i LineIndex<'i>,
+    /// the number of the headings with the slug, to make it unique like `slug-1`
+    slugs: HashMap<String, usize>,
+    items: Vec<OutlineItem>,
+}
+
+impl Visitor for HeadingCollector<'_> {
+    fn visit_paragraph<'a>(&mut self, value: &mut Paragraph<'a>) -> Option<TransformCommand<'a>> {
+        let mut nodes = value.children.iter().filter(|node| match &node.kind {
+            NodeKind::Text(text) => !text.value.trim().is_empty(),
+            _ => true,
+        });
+        if let (Some(node), None) = (nodes.next(), nodes.next()) {
+            if let NodeKind::Heading(heading) = &node.kind {
+                self.visit_heading(heading);
+            }
+        }
+        None
+    }
+
+    fn visit_heading<'a>(&mut self, value: &Heading<'a>) -> Option<TransformCommand<'a>> {
+        let line =
+            source_offset(self.input, &value.text).map_or(0, |offset| self.index.line(offset) + 1);
+        let slug = slugify(&value.text);
+        let count = self.slugs.entry(slug.clone()).or_default();
+        let anchor = match *count {
+            0 => slug,
+            n => format!("{}-{}", slug, n),
+        };
+        *count += 1;
+
+        self.items.push(OutlineItem {
+            level: value.level,
+            text: value.text.to_string(),
+            line,
+            anchor,
+            children: vec![],
+        });
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use indoc::indoc;
+    use rstest::rstest;
+
+    /// (depth, level, text, line, anchor)
+    fn flatten(outline: &[OutlineItem]) -> Vec<(usize, usize, &str, usize, &str)> {
+        fn walk<'o>(
+            items: &'o [OutlineItem],
+            depth: usize,
+            v: &mut Vec<(usize, usize, &'o str, usize, &'o str)>,
+        ) {
+            for item in items {
+                v.push((depth, item.level, &item.text, item.line, &item.anchor));
+                walk(&item.children, depth + 1, v);
+            }
+        }
+        let mut v = vec![];
+        walk(outline, 0, &mut v);
+        v
+    }
+
+    #[test]
+    fn scrapbox_outline_test() {
+        let input = indoc! {"
+            [*** Title]
+            [** First Section]
+            text [** inline]
+             [** in a list]
+            [* bold]
+            [** Second, section!]
+            [*** Another Title]
+            [** First Section]
+        "};
+        let outline = scrapbox_outline(
+            input,
+            ScrapboxParserConfig::default(),
+            MarkdownPass::default(),
+        );
+        assert_eq!(
+            flatten(&outline),
+            vec![
+                (0, 1, "Title", 1, "title"),
+                (1, 2, "First Section", 2, "first-section"),
+                (1, 2, "Second, section!", 6, "second-section"),
+                (0, 1, "Another Title", 7, "another-title"),
+                (1, 2, "First Section", 8, "first-section-1"),
+            ]
+        );
+    }
+
+    #[test]
+    fn scrapbox_outline_bold_to_h_test() {
+        let input = "[** a]\n[* b]\n[*** c]\n";
+        let pass = MarkdownPass {
+            h1_level: 2,
+            bold_to_h: true,
+        };
+        let outline = scrapbox_outline(input, ScrapboxParserConfig::default(), pass);
+        // `[*** c]` is larger than h1, so it's not a heading
+        assert_eq!(
+            flatten(&outline),
+            vec![(0, 1, "a", 1, "a"), (1, 2, "b", 2, "b")]
+        );
+    }
+
+    #[rstest(
+        input,
+        expected,
+        case("Hello World", "hello-world"),
+        case(" foo_bar-baz ", "foo_bar-baz"),
+        case("What's new? (v1.0)", "whats-new-v10"),
+        case("日本語の見出し", "日本語の見出し"),
+        case("Ünïcode", "ünïcode")
+    )]
+    fn slugify_test(input: &str, expected: &str) {
+        assert_eq!(slugify(input), expected);
+    }
+
+    #[test]
+    fn to_markdown_test() {
+        let input = "[*** a]\n[** b]\n[** c]\n[*** d]\n";
+        let outline = scrapbox_outline(
+            input,
+            ScrapboxParserConfig::default(),
+            MarkdownPass::default(),
+        );
+        let expected = indoc! {"
+            * [a](#a)
+              * [b](#b)
+              * [c](#c)
+            * [d](#d)
+        "};
+        assert_eq!(to_markdown(&outline, "  "), expected);
+    }
+
+    #[test]
+    fn to_markdown_escape_test() {
+        let item = |text: &str, anchor: &str| OutlineItem {
+            level: 1,
+            text: text.into(),
+            line: 0,
+            anchor: anchor.into(),
+            children: vec![],
+        };
+        let outline = [item("see [x]", "see-x"), item(r"a\b]", "ab")];
+        assert_eq!(
+            to_markdown(&outline, "  "),
+            "* [see \\[x\\]](#see-x)\n* [a\\\\b\\]](#ab)\n"
+        );
+    }
+
+    #[test]
+    fn to_json_test() {
+        let outline = scrapbox_outline(
+            "[*** a]\n[** b]\n",
+            ScrapboxParserConfig::default(),
+            MarkdownPass::default(),
+        );
+        let expected = indoc! {r#"
+            [
+              {
+                "level": 1,
+                "text": "a",
+                "line": 1,
+                "anchor": "a",
+                "children": [
+                  {
+                    "level": 2,
+                    "text": "b",
+                    "line": 2,
+                    "anchor": "b",
+                    "children": []
+                  }
+                ]
+              }
+            ]"#};
+        assert_eq!(to_json(&outline), expected);
+    }
+}
@@ -19,7 +19,7 @@ impl<'a, X> ParseError<'a, X> {
     }
 
     #[inline]
-    pub fn span(&self) -> &Span<X> {
+    pub fn span(&self) -> &Span<'_, X> {
         &self.span
     }
 
@@ -51,7 +51,7 @@ impl<'a, X> nom::error::ParseError<Span<'a, X>> for ParseError<'a, X> {
         Self::new(input, format!("parse error: {:?}", kind))
     }
 
-    fn append(input: Span<X>, kind: nom::error::ErrorKind, other: Self) -> Self {
+    fn append(_input: Span<X>, _kind: nom::error::ErrorKind, other: Self) -> Self {
         other
     }
 
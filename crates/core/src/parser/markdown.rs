@@ -1,26 +1,39 @@
 use nom::{
     branch::alt,
-    bytes::complete::{tag, take_until, take_while},
+    bytes::complete::{tag, take_until, take_while, take_while1},
     character::complete::{char, digit1},
-    combinator::map,
+    combinator::{eof, map, opt, peek, verify},
     multi::{many0, many1},
     sequence::delimited,
     sequence::terminated,
-    Err,
+    sequence::tuple,
+    Err, InputTake,
 };
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::fmt;
+use std::sync::Arc;
 
 use super::utils::*;
 use super::{error, ParseError};
 use crate::ast::*;
+use crate::front_matter::FrontMatter;
+use crate::media::MediaConfig;
 
 pub type Span<'a> = error::Span<'a, MarkdownParserContext>;
 pub type IResult<'a, O> = error::IResult<'a, O, MarkdownParserContext>;
 
-#[derive(Default, Debug, Copy, Clone, PartialEq)]
+#[derive(Default, Debug, Clone, PartialEq)]
 pub struct MarkdownParserContext {
     pub config: MarkdownParserConfig,
     pub indent: Option<IndentKind>,
+    pub brackets: LineBrackets,
+}
+
+impl AsMut<LineBrackets> for MarkdownParserContext {
+    fn as_mut(&mut self) -> &mut LineBrackets {
+        &mut self.brackets
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
@@ -30,43 +43,105 @@ pub enum IndentKind {
     Space { size: usize },
 }
 
-impl IndentKind {
-    pub fn to_string(&self) -> String {
+impl fmt::Display for IndentKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            IndentKind::Space { size } => " ".repeat(*size),
-            IndentKind::Tab => "\t".into(),
+            IndentKind::Space { size } => write!(f, "{}", " ".repeat(*size)),
+            IndentKind::Tab => write!(f, "\t"),
         }
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq)]
-pub struct MarkdownParserConfig {}
+/// Dialect of Markdown to read and write.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum MarkdownFlavor {
+    #[default]
+    Generic,
+    /// Obsidian flavored Markdown
+    /// https://help.obsidian.md/Editing+and+formatting/Obsidian+Flavored+Markdown
+    Obsidian,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarkdownParserConfig {
+    pub flavor: MarkdownFlavor,
+    /// If true, YAML front matter is read into the metadata of `Page`.
+    /// If false, it is parsed as a text.
+    pub front_matter: bool,
+    /// a pointer to keep `Span` small
+    pub media: Arc<MediaConfig>,
+}
 
 impl Default for MarkdownParserConfig {
     fn default() -> Self {
-        Self {}
+        Self {
+            flavor: MarkdownFlavor::Generic,
+            front_matter: true,
+            media: Arc::default(),
+        }
     }
 }
 
 pub fn page(input: Span) -> IResult<Page> {
+    let (input, front_matter) = if input.extra.config.front_matter {
+        opt(front_matter)(input)?
+    } else {
+        (input, None)
+    };
     let (input, nodes) = many0(alt((
         // parser for multiline block
         map(code_block, |s| Node::new(NodeKind::CodeBlock(s))),
         map(table, |s| Node::new(NodeKind::Table(s))),
         map(list, |s| Node::new(NodeKind::List(s))),
+        map(callout, |s| Node::new(NodeKind::Callout(s))),
+        map(comment_block, |s| {
+            Node::new(NodeKind::Paragraph(Paragraph::new(vec![Node::new(
+                NodeKind::Comment(s),
+            )])))
+        }),
         map(paragraph, |s| Node::new(NodeKind::Paragraph(s))),
         // workaround for no-newline like "hoge"
         // map(text, |s| Node::new(NodeKind::Text(s))),
         node,
     )))(input)?;
-    Ok((input, Page { nodes }))
+
+    let mut page = Page {
+        nodes,
+        ..Default::default()
+    };
+    if let Some(front_matter) = front_matter {
+        front_matter.apply(&mut page);
+    }
+    Ok((input, page))
+}
+
+fn is_obsidian(input: &Span) -> bool {
+    input.extra.config.flavor == MarkdownFlavor::Obsidian
+}
+
+/// ---
+/// tags: [tag1, tag2]
+/// ---
+fn front_matter(input: Span) -> IResult<FrontMatter> {
+    let (input, _) = tag("---\n")(input)?;
+    let (input, yaml) = alt((
+        peek(tag("---")),
+        terminated(take_until("\n---"), char('\n')),
+    ))(input)?;
+    let (input, _) = tag("---")(input)?;
+    let (input, _) = alt((tag("\n"), eof))(input)?;
+
+    match FrontMatter::from_yaml(*yaml) {
+        Ok(front_matter) => Ok((input, front_matter)),
+        Err(e) => Err(Err::Error(ParseError::new(yaml, e.to_string()))),
+    }
 }
 
 // "hoge\n"
 fn paragraph(input: Span) -> IResult<Paragraph> {
     let (input, p) = take_until_eol(input)?;
     let (input, _) = char('\n')(input)?;
-    let (rest, p) = map(many0(node), |children| Paragraph::new(children))(p)?;
+    let (rest, p) = map(many0(node), Paragraph::new)(p)?;
     assert!(rest.is_empty());
     Ok((input, p))
 }
@@ -84,39 +159,109 @@ fn list(input: Span) -> IResult<List> {
     // children.append(&mut items);
 
     // Ok((input, List::new(children)))
-    let (mut input, list) = map(many1(list_item), |children| List::new(children))(input)?;
+    let (mut input, list) = map(many1(list_item), List::new)(input)?;
     input.extra.indent = None; // reset
     Ok((input, list))
 }
 
 fn node(input: Span) -> IResult<Node> {
+    let (input, unclosed) = unclosed_bracket(input)?;
+    if let Some(text) = unclosed {
+        return Ok((input, Node::new(NodeKind::Text(text))));
+    }
+
     alt((
         // parser for single line
         map(heading, |c| Node::new(NodeKind::Heading(c))),
+        map(hash_before_link, |s| Node::new(NodeKind::Text(s))),
         map(hashtag, |s| Node::new(NodeKind::HashTag(s))),
         map(block_quate, |s| Node::new(NodeKind::BlockQuate(s))),
+        map(linked_image, |s| Node::new(NodeKind::Image(s))),
         map(image, |s| Node::new(NodeKind::Image(s))),
+        map(embed, |s| Node::new(NodeKind::Embed(s))),
         map(emphasis, |c| Node::new(NodeKind::Emphasis(c))),
         map(external_link, |c| Node::new(NodeKind::ExternalLink(c))),
         map(math, |c| Node::new(NodeKind::Math(c))),
+        map(comment, |c| Node::new(NodeKind::Comment(c))),
         // NOTE(tkat0): keep internal_link at the bottom of parsing bracket node
         previewable_wikilink,
         map(wikilink, |c| Node::new(NodeKind::InternalLink(c))),
         map(external_link_plain, |s| {
             Node::new(NodeKind::ExternalLink(s))
         }),
-        map(text, |s| Node::new(NodeKind::Text(s))),
+        map(inline_text, |s| Node::new(NodeKind::Text(s))),
     ))(input)
 }
 
-// [[wikilink]]
+/// "#" of "#[text](url)" is not a hashtag but a text before a link
+fn hash_before_link(input: Span) -> IResult<Text> {
+    let (input, hash) = terminated(
+        tag("#"),
+        peek(tuple((
+            char('['),
+            take_while(|c| c != ']' && c != '\n'),
+            tag("]("),
+        ))),
+    )(input)?;
+    Ok((input, Text::new(*hash)))
+}
+
+/// `utils::text` which also stops before inline notation unknown to Scrapbox.
+/// "abc ==highlight==" -> ("==highlight==", "abc ")
+fn inline_text(input: Span) -> IResult<Text> {
+    let (_, text) = text(input.clone())?;
+
+    let markers: &[&str] = if is_obsidian(&input) {
+        &["![", "==", "%%"]
+    } else {
+        &["!["]
+    };
+
+    // skip the first char not to stop at a marker which failed to be parsed as a node
+    let first = text.value.chars().next().map_or(0, char::len_utf8);
+    // search the text and the next char, which may be the end of "!["
+    let end = input[text.value.len()..]
+        .chars()
+        .next()
+        .map_or(input.len(), |c| text.value.len() + c.len_utf8());
+    let len = markers
+        .iter()
+        .filter_map(|m| input[first..end].find(m))
+        .map(|i| first + i)
+        .fold(text.value.len(), usize::min);
+
+    let (input, value) = input.take_split(len);
+    Ok((input, Text::new(*value)))
+}
+
+/// [[title]]
+/// [[title|label]]
+/// [[title#heading]]
+/// [[title#^block]]
 fn wikilink(input: Span) -> IResult<InternalLink> {
     map(
         delimited(tag("[["), take_while(|c| c != ']'), tag("]]")),
-        |s: Span| InternalLink::new(*s),
+        |s: Span| wikilink_target(*s),
     )(input)
 }
 
+fn wikilink_target(value: &str) -> InternalLink<'_> {
+    let (target, label) = match value.split_once('|') {
+        Some((target, label)) => (target, Some(label.into())),
+        None => (value, None),
+    };
+    let (title, anchor) = match target.split_once('#') {
+        Some((title, anchor)) => (title, Some(anchor.into())),
+        None => (target, None),
+    };
+    InternalLink {
+        title: title.into(),
+        anchor,
+        label,
+        embed: false,
+    }
+}
+
 /// [Rust](https://www.rust-lang.org/)
 fn external_link(input: Span) -> IResult<ExternalLink> {
     let (input, title) = brackets(input)?;
@@ -131,31 +276,26 @@ fn external_link(input: Span) -> IResult<ExternalLink> {
 fn previewable_wikilink(input: Span) -> IResult<Node> {
     let (input, url) = delimited(tag("![["), take_while(|c| c != ']'), tag("]]"))(input)?;
 
-    let ext = ["svg", "jpg", "jpeg", "png", "gif"];
-    let is_image = |url: &str| ext.iter().any(|e| url.ends_with(e));
-
-    if is_image(*url) {
+    if input.extra.config.media.is_image(*url) {
         Ok((input, Node::new(NodeKind::Image(Image::new(*url)))))
     } else {
-        Ok((
-            input,
-            Node::new(NodeKind::InternalLink(InternalLink::new(*url))),
-        ))
+        let link = InternalLink {
+            embed: true,
+            ..wikilink_target(*url)
+        };
+        Ok((input, Node::new(NodeKind::InternalLink(link))))
     }
 }
 
 /// ![](https://i.gyazo.com/da78df293f9e83a74b5402411e2f2e01.png)
 /// ![image](https://i.gyazo.com/da78df293f9e83a74b5402411e2f2e01.png)
 fn image(input: Span) -> IResult<Image> {
-    let ext = ["svg", "jpg", "jpeg", "png", "gif"];
     let (input, _) = char('!')(input)?;
-    let (input, title) = brackets(input)?;
+    let (input, _title) = brackets(input)?;
     let (input, url) = parentheses(input)?;
 
-    let is_image = |url: &str| ext.iter().any(|e| url.ends_with(e));
-
-    if is_image(*url) {
-        return Ok((input, Image::new(*url)));
+    if input.extra.config.media.is_image(*url) {
+        Ok((input, Image::new(*url)))
     } else {
         Err(Err::Error(ParseError::new(
             input,
@@ -164,6 +304,35 @@ fn image(input: Span) -> IResult<Image> {
     }
 }
 
+/// Obsidian embeds these like an image
+/// ![](https://www.youtube.com/watch?v=xxxxxxxxxxx)
+fn embed(input: Span) -> IResult<Embed> {
+    let (input, _) = char('!')(input)?;
+    let (input, _title) = brackets(input)?;
+    let (input, url) = parentheses(input)?;
+
+    match input.extra.config.media.embed_provider(*url) {
+        Some(provider) => Ok((input, Embed::new(provider, *url))),
+        None => Err(Err::Error(ParseError::new(
+            input,
+            "URL is not embeddable".into(),
+        ))),
+    }
+}
+
+/// [![](https://i.gyazo.com/da78df293f9e83a74b5402411e2f2e01.png)](http://cutedog.com)
+fn linked_image(input: Span) -> IResult<Image> {
+    let (input, image) = delimited(char('['), image, char(']'))(input)?;
+    let (input, link) = parentheses(input)?;
+    Ok((
+        input,
+        Image {
+            link: Some((*link).into()),
+            ..image
+        },
+    ))
+}
+
 fn heading(input: Span) -> IResult<Heading> {
     let (input, hash) = many1(tag("#"))(input)?;
     let level = hash.len();
@@ -173,7 +342,7 @@ fn heading(input: Span) -> IResult<Heading> {
 
 // TODO(tkat0): mix is not supported yet
 fn emphasis(input: Span) -> IResult<Emphasis> {
-    alt((bold, italic, strikethrough))(input)
+    alt((bold, italic, strikethrough, highlight))(input)
 }
 
 /// **bold**
@@ -200,6 +369,71 @@ fn strikethrough(input: Span) -> IResult<Emphasis> {
     )(input)
 }
 
+/// ==highlight== (Obsidian)
+fn highlight(input: Span) -> IResult<Emphasis> {
+    if !is_obsidian(&input) {
+        return Err(Err::Error(ParseError::new(input, "not obsidian".into())));
+    }
+    map(
+        delimited(
+            tag("=="),
+            verify(take_until("=="), |s: &Span| !s.is_empty()),
+            tag("=="),
+        ),
+        |s: Span| Emphasis::highlight(*s),
+    )(input)
+}
+
+/// %%comment%% (Obsidian)
+fn comment(input: Span) -> IResult<Comment> {
+    if !is_obsidian(&input) {
+        return Err(Err::Error(ParseError::new(input, "not obsidian".into())));
+    }
+    map(
+        delimited(tag("%%"), take_until("%%"), tag("%%")),
+        |s: Span| Comment::new(*s),
+    )(input)
+}
+
+/// %%
+/// multiline comment
+/// %%
+fn comment_block(input: Span) -> IResult<Comment> {
+    terminated(comment, alt((tag("\n"), eof)))(input)
+}
+
+/// Callout (Obsidian)
+/// > [!info] title
+/// > body
+fn callout(input: Span) -> IResult<Callout> {
+    if !is_obsidian(&input) {
+        return Err(Err::Error(ParseError::new(input, "not obsidian".into())));
+    }
+    let (input, _) = tag("> [!")(input)?;
+    let (input, kind) = take_while1(|c| c != ']' && c != '\n')(input)?;
+    let (input, _) = char(']')(input)?;
+    // foldable callout "[!info]-" or "[!info]+"
+    let (input, _) = opt(alt((char('-'), char('+'))))(input)?;
+    let (input, title) = take_until_eol(input)?;
+    let (input, _) = alt((tag("\n"), eof))(input)?;
+
+    fn line(input: Span) -> IResult<Node> {
+        let (input, _) = char('>')(input)?;
+        let (input, _) = opt(char(' '))(input)?;
+        let (input, body) = take_until_eol(input)?;
+        let (input, _) = alt((tag("\n"), eof))(input)?;
+        let (rest, p) = map(many0(node), Paragraph::new)(body)?;
+        assert!(rest.is_empty());
+        Ok((input, Node::new(NodeKind::Paragraph(p))))
+    }
+
+    let (input, children) = many0(line)(input)?;
+
+    let title = title.trim();
+    let title = if title.is_empty() { None } else { Some(title) };
+    Ok((input, Callout::new(*kind, title, children)))
+}
+
 /// $$ Tex here $$
 fn math(input: Span) -> IResult<Math> {
     map(
@@ -234,13 +468,16 @@ fn code_block(input: Span) -> IResult<CodeBlock> {
 /// | --- | --- | --- |
 /// | d | e | f |
 fn table(input: Span) -> IResult<Table> {
-    fn row(input: Span) -> IResult<Vec<String>> {
+    fn row(input: Span) -> IResult<Vec<Cow<str>>> {
         let (rest, input) = take_until_eol(input)?;
         let (rest, _) = char('\n')(rest)?;
         let (input, _) = char('|')(input)?;
         let (input, row) = many1(terminated(take_until("|"), tag("|")))(input)?;
         assert!(input.is_empty());
-        let row = row.into_iter().map(|s| s.trim().to_string()).collect();
+        let row = row
+            .into_iter()
+            .map(|s| s.fragment().trim().into())
+            .collect();
         Ok((rest, row))
     }
 
@@ -290,8 +527,9 @@ fn list_item(input: Span) -> IResult<ListItem> {
         Ok((input, (ListKind::Disc, children)))
     }
 
+    // `level` counts the indent, and the top level is 1 as Scrapbox
     map(alt((decimal, disc)), move |(kind, children)| {
-        ListItem::new(kind, level, children)
+        ListItem::new(kind, level + 1, children)
     })(input)
 }
 
@@ -302,11 +540,11 @@ mod test {
     use rstest::rstest;
 
     #[rstest(input, expected,
-        case("* 123abc\n  * 123abc\n    * 123abc\n", ("", List::new(vec![ListItem::new(ListKind::Disc, 0, vec![Node::new(NodeKind::Text(Text::new("123abc")))]), ListItem::new(ListKind::Disc, 1, vec![Node::new(NodeKind::Text(Text::new("123abc")))]), ListItem::new(ListKind::Disc, 2, vec![Node::new(NodeKind::Text(Text::new("123abc")))])]))),
-        case("* 123abc\n    * 123abc\n        * 123abc\n", ("", List::new(vec![ListItem::new(ListKind::Disc, 0, vec![Node::new(NodeKind::Text(Text::new("123abc")))]), ListItem::new(ListKind::Disc, 1, vec![Node::new(NodeKind::Text(Text::new("123abc")))]), ListItem::new(ListKind::Disc, 2, vec![Node::new(NodeKind::Text(Text::new("123abc")))])]))),
-        case("123. abc\n",("", List::new(vec![ListItem::new(ListKind::Decimal, 0, vec![Node::new(NodeKind::Text(Text::new("abc")))])]))),
-        case("* 123abc\n123. abc\n",("", List::new(vec![ListItem::new(ListKind::Disc, 0, vec![Node::new(NodeKind::Text(Text::new("123abc")))]), ListItem::new(ListKind::Decimal, 0, vec![Node::new(NodeKind::Text(Text::new("abc")))])]))),
-        case("* 123abc\n\t* 123abc\n", ("", List::new(vec![ListItem::new(ListKind::Disc, 0, vec![Node::new(NodeKind::Text(Text::new("123abc")))]), ListItem::new(ListKind::Disc, 1, vec![Node::new(NodeKind::Text(Text::new("123abc")))])]))),
+        case("* 123abc\n  * 123abc\n    * 123abc\n", ("", List::new(vec![ListItem::new(ListKind::Disc, 1, vec![Node::new(NodeKind::Text(Text::new("123abc")))]), ListItem::new(ListKind::Disc, 2, vec![Node::new(NodeKind::Text(Text::new("123abc")))]), ListItem::new(ListKind::Disc, 3, vec![Node::new(NodeKind::Text(Text::new("123abc")))])]))),
+        case("* 123abc\n    * 123abc\n        * 123abc\n", ("", List::new(vec![ListItem::new(ListKind::Disc, 1, vec![Node::new(NodeKind::Text(Text::new("123abc")))]), ListItem::new(ListKind::Disc, 2, vec![Node::new(NodeKind::Text(Text::new("123abc")))]), ListItem::new(ListKind::Disc, 3, vec![Node::new(NodeKind::Text(Text::new("123abc")))])]))),
+        case("123. abc\n",("", List::new(vec![ListItem::new(ListKind::Decimal, 1, vec![Node::new(NodeKind::Text(Text::new("abc")))])]))),
+        case("* 123abc\n123. abc\n",("", List::new(vec![ListItem::new(ListKind::Disc, 1, vec![Node::new(NodeKind::Text(Text::new("123abc")))]), ListItem::new(ListKind::Decimal, 1, vec![Node::new(NodeKind::Text(Text::new("abc")))])]))),
+        case("* 123abc\n\t* 123abc\n", ("", List::new(vec![ListItem::new(ListKind::Disc, 1, vec![Node::new(NodeKind::Text(Text::new("123abc")))]), ListItem::new(ListKind::Disc, 2, vec![Node::new(NodeKind::Text(Text::new("123abc")))])]))),
     )]
     fn list_valid_test(input: &str, expected: (&str, List)) {
         assert_eq!(
@@ -342,6 +580,8 @@ mod test {
     #[rstest(input, expected,
         case("![](https://www.rust-lang.org/static/images/rust-logo-blk.svg)", ("", Image::new("https://www.rust-lang.org/static/images/rust-logo-blk.svg"))),
         case("![title](https://www.rust-lang.org/static/images/rust-logo-blk.svg)", ("", Image::new("https://www.rust-lang.org/static/images/rust-logo-blk.svg"))),
+        case("![](https://example.com/image.webp?width=100)", ("", Image::new("https://example.com/image.webp?width=100"))),
+        case("![](https://gyazo.com/5f93e65a3b979ae5333aca4f32600611)", ("", Image::new("https://gyazo.com/5f93e65a3b979ae5333aca4f32600611"))),
     )]
     fn image_valid_test(input: &str, expected: (&str, Image)) {
         assert_eq!(
@@ -351,6 +591,29 @@ mod test {
         );
     }
 
+    #[rstest(input, expected,
+        case("![](https://www.youtube.com/watch?v=dQw4w9WgXcQ)", ("", Embed::new(EmbedProvider::YouTube, "https://www.youtube.com/watch?v=dQw4w9WgXcQ"))),
+        case("![video](https://example.com/movie.mp4)", ("", Embed::new(EmbedProvider::Video, "https://example.com/movie.mp4"))),
+    )]
+    fn embed_valid_test(input: &str, expected: (&str, Embed)) {
+        assert_eq!(
+            embed(Span::new_extra(input, MarkdownParserContext::default()))
+                .map(|(input, ret)| (*input, ret)),
+            Ok(expected)
+        );
+    }
+
+    #[rstest(input, expected,
+        case("[![](https://www.rust-lang.org/static/images/rust-logo-blk.svg)](https://www.rust-lang.org/)", ("", Image::with_link("https://www.rust-lang.org/static/images/rust-logo-blk.svg", "https://www.rust-lang.org/"))),
+    )]
+    fn linked_image_valid_test(input: &str, expected: (&str, Image)) {
+        assert_eq!(
+            linked_image(Span::new_extra(input, MarkdownParserContext::default()))
+                .map(|(input, ret)| (*input, ret)),
+            Ok(expected)
+        );
+    }
+
     #[rstest(input, expected,
         case("# heading", ("", Heading::new("heading", 1))),
         case("# ヘッダ", ("", Heading::new("ヘッダ", 1))),
@@ -380,6 +643,10 @@ mod test {
 
     #[rstest(input, expected,
         case("[[title]]", ("", InternalLink::new("title"))),
+        case("[[title|label]]", ("", InternalLink { title: "title".into(), label: Some("label".into()), ..Default::default() })),
+        case("[[title#heading]]", ("", InternalLink { title: "title".into(), anchor: Some("heading".into()), ..Default::default() })),
+        case("[[title#^block|label]]", ("", InternalLink { title: "title".into(), anchor: Some("^block".into()), label: Some("label".into()), ..Default::default() })),
+        case("[[#heading]]", ("", InternalLink { title: "".into(), anchor: Some("heading".into()), ..Default::default() })),
     )]
     fn internal_link_valid_test(input: &str, expected: (&str, InternalLink)) {
         assert_eq!(
@@ -391,7 +658,8 @@ mod test {
 
     #[rstest(input, expected,
         case("![[test.png]]", ("", Node::new(NodeKind::Image(Image::new("test.png"))))),
-        case("![[wikilink]]", ("", Node::new(NodeKind::InternalLink(InternalLink::new("wikilink"))))),
+        case("![[wikilink]]", ("", Node::new(NodeKind::InternalLink(InternalLink::embed("wikilink"))))),
+        case("![[wikilink#heading|label]]", ("", Node::new(NodeKind::InternalLink(InternalLink { title: "wikilink".into(), anchor: Some("heading".into()), label: Some("label".into()), embed: true })))),
     )]
     fn previewable_wikilink_valid_test(input: &str, expected: (&str, Node)) {
         assert_eq!(
@@ -401,6 +669,79 @@ mod test {
         );
     }
 
+    fn obsidian_context() -> MarkdownParserContext {
+        MarkdownParserContext {
+            config: MarkdownParserConfig {
+                flavor: MarkdownFlavor::Obsidian,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[rstest(input, expected,
+        case("==text==", ("", Node::new(NodeKind::Emphasis(Emphasis::highlight("text"))))),
+        case("%%comment%%", ("", Node::new(NodeKind::Comment(Comment::new("comment"))))),
+        case("abc ==text==", ("==text==", Node::new(NodeKind::Text(Text::new("abc "))))),
+        case("abc %%comment%%", ("%%comment%%", Node::new(NodeKind::Text(Text::new("abc "))))),
+        case("== abc", ("", Node::new(NodeKind::Text(Text::new("== abc"))))),
+    )]
+    fn obsidian_node_valid_test(input: &str, expected: (&str, Node)) {
+        assert_eq!(
+            node(Span::new_extra(input, obsidian_context())).map(|(input, ret)| (*input, ret)),
+            Ok(expected)
+        );
+    }
+
+    #[rstest(input, expected,
+        case("==text==", ("", Node::new(NodeKind::Text(Text::new("==text=="))))),
+        case("%%comment%%", ("", Node::new(NodeKind::Text(Text::new("%%comment%%"))))),
+        case("abc ![[page]]", ("![[page]]", Node::new(NodeKind::Text(Text::new("abc "))))),
+    )]
+    fn generic_node_valid_test(input: &str, expected: (&str, Node)) {
+        assert_eq!(
+            node(Span::new_extra(input, MarkdownParserContext::default()))
+                .map(|(input, ret)| (*input, ret)),
+            Ok(expected)
+        );
+    }
+
+    #[rstest(input, expected,
+        case("> [!info]\n", ("", Callout::new("info", None, vec![]))),
+        case("> [!tip]- Title\n> body\n>\nabc\n", ("abc\n", Callout::new("tip", Some("Title"), vec![
+            Node::new(NodeKind::Paragraph(Paragraph::new(vec![Node::new(NodeKind::Text(Text::new("body")))]))),
+            Node::new(NodeKind::Paragraph(Paragraph::new(vec![]))),
+        ]))),
+    )]
+    fn callout_valid_test(input: &str, expected: (&str, Callout)) {
+        assert_eq!(
+            callout(Span::new_extra(input, obsidian_context())).map(|(input, ret)| (*input, ret)),
+            Ok(expected)
+        );
+    }
+
+    #[test]
+    fn generic_callout_test() {
+        // an ordinary blockquote in Markdown
+        assert!(callout(Span::new_extra(
+            "> [!info]\n",
+            MarkdownParserContext::default()
+        ))
+        .is_err());
+    }
+
+    #[rstest(input, expected,
+        case("---\n---\n", ("", FrontMatter::default())),
+        case("---\ntags: [a, b]\n---\nabc\n", ("abc\n", FrontMatter { tags: vec!["a".into(), "b".into()], ..Default::default() })),
+    )]
+    fn front_matter_valid_test(input: &str, expected: (&str, FrontMatter)) {
+        assert_eq!(
+            front_matter(Span::new_extra(input, MarkdownParserContext::default()))
+                .map(|(input, ret)| (*input, ret)),
+            Ok(expected)
+        );
+    }
+
     #[rstest(input, expected,
         case("[Rust](https://www.rust-lang.org/)", ("", ExternalLink::new(Some("Rust"), "https://www.rust-lang.org/"))),
         case("[Rustプログラミング言語](https://www.rust-lang.org/)", ("", ExternalLink::new(Some("Rustプログラミング言語"), "https://www.rust-lang.org/"))),
@@ -428,6 +769,8 @@ mod test {
         case("abc #tag ", ("#tag ", Node::new(NodeKind::Text(Text::new("abc "))))),
         case("[[title]]abc", ("abc", Node::new(NodeKind::InternalLink(InternalLink::new("title"))))),
         case("[", ("", Node::new(NodeKind::Text(Text::new("["))))),
+        case("#[Rust](https://www.rust-lang.org/)", ("[Rust](https://www.rust-lang.org/)", Node::new(NodeKind::Text(Text::new("#"))))),
+        case("#[tag] (abc)", (" (abc)", Node::new(NodeKind::HashTag(HashTag::bracketed("tag"))))),
         case(r#"$$ \frac{-b \pm \sqrt{b^2-4ac}}{2a} $$"#, ("", Node::new(NodeKind::Math(Math::new(r#" \frac{-b \pm \sqrt{b^2-4ac}}{2a} "#))))),
     )]
     fn node_valid_test(input: &str, expected: (&str, Node)) {
@@ -445,6 +788,26 @@ mod test {
         }
     }
 
+    #[test]
+    fn hash_before_link_test() {
+        let (_, page) = page(Span::new_extra(
+            "abc #[Rust](https://www.rust-lang.org/)\n",
+            MarkdownParserContext::default(),
+        ))
+        .unwrap();
+        assert_eq!(
+            page.nodes,
+            vec![Node::new(NodeKind::Paragraph(Paragraph::new(vec![
+                Node::new(NodeKind::Text(Text::new("abc "))),
+                Node::new(NodeKind::Text(Text::new("#"))),
+                Node::new(NodeKind::ExternalLink(ExternalLink::new(
+                    Some("Rust"),
+                    "https://www.rust-lang.org/"
+                ))),
+            ])))]
+        );
+    }
+
     /* TODO: fix
     #[rstest(input, expected,
         case(" ", ("", Paragraph::new( vec![Node::new(NodeKind::Text(Text::new(" ")))]))),
@@ -458,6 +821,18 @@ mod test {
                 Node::new(NodeKind::InternalLink(InternalLink::new("internal link")))
                 ]
             ))),
+        case("[[a]] ![b [c #d", ("", Paragraph::new(
+            vec![
+                Node::new(NodeKind::InternalLink(InternalLink::new("a"))),
+                Node::new(NodeKind::Text(Text::new(" "))),
+                Node::new(NodeKind::Text(Text::new("!"))),
+                Node::new(NodeKind::Text(Text::new("["))),
+                Node::new(NodeKind::Text(Text::new("b "))),
+                Node::new(NodeKind::Text(Text::new("["))),
+                Node::new(NodeKind::Text(Text::new("c "))),
+                Node::new(NodeKind::HashTag(HashTag::new("d"))),
+                ]
+            ))),
     )]
     fn paragraph_valid_test(input: &str, expected: (&str, Paragraph)) {
         assert_eq!(
@@ -479,9 +854,7 @@ mod test {
                     }))
                 ]))),
                 Node::new(NodeKind::Paragraph(Paragraph::new(vec![
-                        Node::new(NodeKind::HashTag(HashTag {
-                            value: "efg".into(),
-                        })),
+                        Node::new(NodeKind::HashTag(HashTag::new("efg"))),
                         Node::new(NodeKind::Text(Text { value: " ".into() })),
                         Node::new(NodeKind::InternalLink(InternalLink::new("internal link"))),
                         Node::new(NodeKind::ExternalLink(ExternalLink::new(
@@ -489,7 +862,8 @@ mod test {
                             "https://www.rust-lang.org/",
                         ))),
                 ]))),
-            ]
+            ],
+            ..Default::default()
         })),
         case(indoc! {"
             a
@@ -508,7 +882,8 @@ mod test {
                         value: "b".into(),
                     }))
                 ]))),
-            ]
+            ],
+            ..Default::default()
         }))
     )]
     fn page_valid_test(input: &str, expected: (&str, Page)) {
@@ -518,4 +893,84 @@ mod test {
             Ok(expected)
         );
     }
+
+    #[test]
+    fn obsidian_page_valid_test() {
+        let input = indoc! {"
+            ---
+            tags: [tag1, tag2]
+            ---
+            %%
+            comment
+            %%
+            > [!note] ==note==
+        "};
+
+        assert_eq!(
+            page(Span::new_extra(input, obsidian_context())).map(|(input, ret)| (*input, ret)),
+            Ok((
+                "",
+                Page {
+                    tags: vec!["tag1".into(), "tag2".into()],
+                    nodes: vec![
+                        Node::new(NodeKind::Paragraph(Paragraph::new(vec![Node::new(
+                            NodeKind::Comment(Comment::new("\ncomment\n"))
+                        )]))),
+                        Node::new(NodeKind::Callout(Callout::new(
+                            "note",
+                            Some("==note=="),
+                            vec![]
+                        ))),
+                    ],
+                    ..Default::default()
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn front_matter_page_valid_test() {
+        let input = indoc! {"
+            ---
+            title: title
+            aliases: alias
+            created: 2022-07-01
+            author: tkat0
+            ---
+            abc
+        "};
+
+        assert_eq!(
+            page(Span::new_extra(input, MarkdownParserContext::default()))
+                .map(|(input, ret)| (*input, ret)),
+            Ok((
+                "",
+                Page {
+                    title: Some("title".into()),
+                    aliases: vec!["alias".into()],
+                    created: Some("2022-07-01".into()),
+                    extra: [("author".into(), "tkat0".into())].into(),
+                    nodes: vec![Node::new(NodeKind::Paragraph(Paragraph::new(vec![
+                        Node::new(NodeKind::Text(Text::new("abc")))
+                    ])))],
+                    ..Default::default()
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn front_matter_disabled_page_valid_test() {
+        let context = MarkdownParserContext {
+            config: MarkdownParserConfig {
+                front_matter: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let (_, page) = page(Span::new_extra("---\ntitle: title\n---\n", context)).unwrap();
+        assert_eq!(page.title, None);
+        assert_eq!(page.nodes.len(), 3);
+    }
 }
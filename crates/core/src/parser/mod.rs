@@ -1,5 +1,14 @@
 mod error;
 pub mod markdown;
+pub mod org;
 pub mod scrapbox;
 mod utils;
 pub use error::*;
+
+/// The byte offset of `text` in `input`, if the parser borrowed `text` from `input`.
+/// The AST doesn't have positions, but the borrowed text tells where a node is.
+pub fn source_offset(input: &str, text: &str) -> Option<usize> {
+    let start = input.as_ptr() as usize;
+    let ptr = text.as_ptr() as usize;
+    (start <= ptr && ptr + text.len() <= start + input.len()).then(|| ptr - start)
+}
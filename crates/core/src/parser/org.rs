@@ -0,0 +1,674 @@
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, tag_no_case, take_while, take_while1},
+    character::complete::{char, digit1, satisfy},
+    combinator::{eof, map, opt, value},
+    multi::{many0, many1},
+    sequence::{delimited, terminated},
+    Err,
+};
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use super::utils::*;
+use super::{error, ParseError};
+use crate::ast::*;
+use crate::media::MediaConfig;
+
+pub type Span<'a> = error::Span<'a, OrgParserContext>;
+pub type IResult<'a, O> = error::IResult<'a, O, OrgParserContext>;
+
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct OrgParserContext {
+    pub config: OrgParserConfig,
+    pub brackets: LineBrackets,
+}
+
+impl AsMut<LineBrackets> for OrgParserContext {
+    fn as_mut(&mut self) -> &mut LineBrackets {
+        &mut self.brackets
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct OrgParserConfig {
+    /// a pointer to keep `Span` small
+    pub media: Arc<MediaConfig>,
+}
+
+/// A line of the top level
+enum Block<'a> {
+    /// `#+KEY: value`
+    Keyword(&'a str, &'a str),
+    Node(Node<'a>),
+}
+
+pub fn page(input: Span) -> IResult<Page> {
+    let (input, blocks) = many0(alt((
+        // parser for multiline block
+        map(block, Block::Node),
+        map(table, |s| Block::Node(Node::new(NodeKind::Table(s)))),
+        map(keyword, |(key, value)| Block::Keyword(key, value)),
+        map(list, |s| Block::Node(Node::new(NodeKind::List(s)))),
+        map(headline, |s| Block::Node(Node::new(NodeKind::Paragraph(s)))),
+        map(comment_line, |s| {
+            Block::Node(Node::new(NodeKind::Paragraph(Paragraph::new(vec![
+                Node::new(NodeKind::Comment(s)),
+            ]))))
+        }),
+        map(paragraph, |s| {
+            Block::Node(Node::new(NodeKind::Paragraph(s)))
+        }),
+    )))(input)?;
+
+    let mut page = Page::default();
+    for block in blocks {
+        match block {
+            Block::Keyword(key, value) if key.eq_ignore_ascii_case("TITLE") => {
+                page.title = Some(value.into());
+            }
+            Block::Keyword(key, value) if key.eq_ignore_ascii_case("FILETAGS") => {
+                page.tags = value
+                    .split(':')
+                    .map(str::trim)
+                    .filter(|tag| !tag.is_empty())
+                    .map(Cow::from)
+                    .collect();
+            }
+            Block::Keyword(..) => {}
+            Block::Node(node) => page.nodes.push(node),
+        }
+    }
+    Ok((input, page))
+}
+
+fn eol(input: Span) -> IResult<Span> {
+    alt((tag("\n"), eof))(input)
+}
+
+/// #+TITLE: title
+fn keyword<'a>(input: Span<'a>) -> IResult<'a, (&'a str, &'a str)> {
+    let (input, _) = tag("#+")(input)?;
+    let (input, key) =
+        take_while1(|c: char| c.is_ascii_alphanumeric() || c == '_' || c == '-')(input)?;
+    let (input, _) = char(':')(input)?;
+    let (input, value) = take_until_eol(input)?;
+    let (input, _) = eol(input)?;
+    Ok((input, (*key, value.trim())))
+}
+
+/// # comment
+fn comment_line(input: Span) -> IResult<Comment> {
+    let (input, _) = char('#')(input)?;
+    let (rest, line) = take_until_eol(input.clone())?;
+    if !(line.is_empty() || line.starts_with(' ')) {
+        return Err(Err::Error(ParseError::new(input, "not a comment".into())));
+    }
+    let (rest, _) = eol(rest)?;
+    Ok((rest, Comment::new(line.strip_prefix(' ').unwrap_or(*line))))
+}
+
+/// ** heading :tag1:tag2:
+///
+/// The tags of the heading are hashtags after it.
+fn headline(input: Span) -> IResult<Paragraph> {
+    let (input, stars) = take_while1(|c| c == '*')(input)?;
+    let (input, _) = char(' ')(input)?;
+    let (input, line) = take_until_eol(input)?;
+    let (input, _) = eol(input)?;
+
+    let line = line.trim_end();
+    let (text, tags) = match line.rsplit_once(char::is_whitespace) {
+        Some((text, tags))
+            if tags.len() > 2
+                && tags.starts_with(':')
+                && tags.ends_with(':')
+                && !tags.contains("::") =>
+        {
+            (text.trim_end(), tags)
+        }
+        _ => (line, ""),
+    };
+
+    let mut children = vec![Node::new(NodeKind::Heading(Heading::new(
+        text,
+        stars.len(),
+    )))];
+    for tag in tags.split(':').filter(|tag| !tag.is_empty()) {
+        children.push(Node::new(NodeKind::Text(Text::new(" "))));
+        children.push(Node::new(NodeKind::HashTag(HashTag::new(tag))));
+    }
+    Ok((input, Paragraph::new(children)))
+}
+
+// "hoge\n"
+fn paragraph(input: Span) -> IResult<Paragraph> {
+    if input.is_empty() {
+        return Err(Err::Error(ParseError::new(input, "end of input".into())));
+    }
+    let (input, line) = take_until_eol(input)?;
+    let (input, _) = eol(input)?;
+    let (_, children) = many0(node)(line)?;
+    Ok((input, Paragraph::new(children)))
+}
+
+/// #+BEGIN_SRC js :tangle hello.js
+/// console.log("Hello");
+/// #+END_SRC
+///
+/// `SRC` and `EXAMPLE` are code blocks, and the other blocks like `QUOTE` are callouts.
+fn block(input: Span) -> IResult<Node> {
+    let (input, _) = tag_no_case("#+BEGIN_")(input)?;
+    let (input, kind) = take_while1(|c: char| !c.is_whitespace())(input)?;
+    let (input, parameters) = take_until_eol(input)?;
+    let (mut input, _) = char('\n')(input)?;
+
+    let end = format!("#+END_{}", kind);
+    let mut lines = vec![];
+    loop {
+        if input.is_empty() {
+            return Err(Err::Error(ParseError::new(input, format!("no {}", end))));
+        }
+        let (rest, line) = take_until_eol(input)?;
+        let (rest, _) = eol(rest)?;
+        input = rest;
+        if line.trim().eq_ignore_ascii_case(&end) {
+            break;
+        }
+        lines.push(*line);
+    }
+
+    let parameters = parameters.trim();
+    let node = if kind.eq_ignore_ascii_case("SRC") {
+        NodeKind::CodeBlock(code_block(src_file_name(parameters), lines))
+    } else if kind.eq_ignore_ascii_case("EXAMPLE") {
+        NodeKind::CodeBlock(code_block("", lines))
+    } else {
+        // `brackets` has the offsets in `input`, not in `line`
+        let context = OrgParserContext {
+            brackets: LineBrackets::default(),
+            ..input.extra.clone()
+        };
+        let children = lines
+            .into_iter()
+            .map(|line| {
+                let (_, children) = many0(node)(Span::new_extra(line, context.clone()))?;
+                Ok(Node::new(NodeKind::Paragraph(Paragraph::new(children))))
+            })
+            .collect::<Result<_, Err<ParseError<_>>>>()?;
+        let title = (!parameters.is_empty()).then_some(parameters);
+        NodeKind::Callout(Callout::new(kind.to_lowercase(), title, children))
+    };
+    Ok((input, Node::new(node)))
+}
+
+/// The file name of `:tangle`, or the language
+/// "js :tangle hello.js" -> "hello.js"
+fn src_file_name(parameters: &str) -> &str {
+    let mut words = parameters.split_whitespace();
+    let language = words.clone().next().filter(|w| !w.starts_with(':'));
+    while let Some(word) = words.next() {
+        if word == ":tangle" {
+            if let Some(file_name) = words.next().filter(|f| *f != "yes" && *f != "no") {
+                return file_name;
+            }
+        }
+    }
+    language.unwrap_or_default()
+}
+
+fn code_block<'a>(file_name: &'a str, lines: Vec<&'a str>) -> CodeBlock<'a> {
+    CodeBlock {
+        file_name: file_name.into(),
+        children: unindent(lines).into_iter().map(unescape).collect(),
+    }
+}
+
+/// ",* a" -> "* a"
+/// A line which starts with `*` or `#+` has `,` before it in a block.
+fn unescape(line: &str) -> Cow<'_, str> {
+    let code = line.trim_start_matches([' ', '\t']);
+    let unescaped = code.trim_start_matches(',');
+    if code.len() > unescaped.len() && (unescaped.starts_with('*') || unescaped.starts_with("#+")) {
+        let indent = &line[..line.len() - code.len()];
+        Cow::Owned(format!("{}{}", indent, &code[1..]))
+    } else {
+        Cow::Borrowed(line)
+    }
+}
+
+/// Remove the indent which all the lines have, as Emacs indents the code in a block.
+fn unindent(lines: Vec<&str>) -> Vec<&str> {
+    fn indent_len(line: &str) -> usize {
+        line.len() - line.trim_start_matches([' ', '\t']).len()
+    }
+
+    let indent = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| indent_len(line))
+        .min()
+        .unwrap_or(0);
+    // A blank line may have less indent, or other whitespace like U+3000
+    lines
+        .into_iter()
+        .map(|line| &line[indent.min(indent_len(line))..])
+        .collect()
+}
+
+/// #+NAME: name
+/// | a | b | c |
+/// |---+---+---|
+/// | d | e | f |
+fn table(input: Span) -> IResult<Table> {
+    fn row(input: Span) -> IResult<Option<Vec<Cow<str>>>> {
+        let (input, _) = take_while(|c| c == ' ' || c == '\t')(input)?;
+        let (input, _) = char('|')(input)?;
+        let (input, line) = take_until_eol(input)?;
+        let (input, _) = eol(input)?;
+        // |---+---|
+        if line.starts_with('-') {
+            return Ok((input, None));
+        }
+        let line = line.trim_end();
+        let line = line.strip_suffix('|').unwrap_or(line);
+        Ok((
+            input,
+            Some(line.split('|').map(|c| c.trim().into()).collect()),
+        ))
+    }
+
+    let (input, name) = opt(delimited(
+        tag_no_case("#+NAME:"),
+        take_until_eol,
+        char('\n'),
+    ))(input)?;
+    let (input, rows) = many1(row)(input)?;
+
+    let mut rows = rows.into_iter().flatten();
+    let header = rows.next().unwrap_or_default();
+    let name = name.map_or("table", |name| name.fragment().trim());
+    Ok((input, Table::new(name, header, rows.collect())))
+}
+
+fn list(input: Span) -> IResult<List> {
+    let (input, items) = many1(list_item)(input)?;
+
+    // the indents of the parent items
+    let mut indents: Vec<usize> = vec![];
+    let mut children = vec![];
+    for (indent, kind, body) in items {
+        while matches!(indents.last(), Some(&parent) if parent > indent) {
+            indents.pop();
+        }
+        if indents.last() != Some(&indent) {
+            indents.push(indent);
+        }
+        let (_, nodes) = many0(node)(body)?;
+        children.push(ListItem::new(kind, indents.len(), nodes));
+    }
+    Ok((input, List::new(children)))
+}
+
+/// - abc
+///   1. abc
+fn list_item(input: Span) -> IResult<(usize, ListKind, Span)> {
+    let (input, indent) = take_while(|c| c == ' ' || c == '\t')(input)?;
+    let (input, kind) = alt((
+        value(ListKind::Disc, alt((tag("- "), tag("+ ")))),
+        value(
+            ListKind::Decimal,
+            terminated(digit1, alt((tag(". "), tag(") ")))),
+        ),
+        value(
+            ListKind::Alphabet,
+            terminated(
+                satisfy(|c| c.is_ascii_lowercase()),
+                alt((tag(". "), tag(") "))),
+            ),
+        ),
+    ))(input.clone())
+    .or_else(|e| {
+        // `* ` at the start of a line is a heading
+        if indent.is_empty() {
+            return Err(e);
+        }
+        value(ListKind::Disc, tag("* "))(input)
+    })?;
+    let (input, body) = take_until_eol(input)?;
+    let (input, _) = eol(input)?;
+    Ok((input, (indent.len(), kind, body)))
+}
+
+fn node(mut input: Span) -> IResult<Node> {
+    // skip `link` and `hashtag`, which would search "]" again
+    let open = input.starts_with("[[") || input.starts_with("#[");
+    if open && !bracket_closed(&mut input) {
+        return map(inline_text, |s| Node::new(NodeKind::Text(s)))(input);
+    }
+
+    alt((
+        link,
+        map(math, |s| Node::new(NodeKind::Math(s))),
+        map(hashtag, |s| Node::new(NodeKind::HashTag(s))),
+        map(emphasis, |s| Node::new(NodeKind::Emphasis(s))),
+        map(verbatim, |s| Node::new(NodeKind::BlockQuate(s))),
+        map(external_link_plain, |s| {
+            Node::new(NodeKind::ExternalLink(s))
+        }),
+        map(inline_text, |s| Node::new(NodeKind::Text(s))),
+    ))(input)
+}
+
+/// The chars before a markup like `*bold*`
+fn is_pre(c: Option<char>) -> bool {
+    c.is_none_or(|c| c.is_whitespace() || "-({'\"".contains(c))
+}
+
+/// The chars after a markup like `*bold*`
+fn is_post(c: Option<char>) -> bool {
+    c.is_none_or(|c| c.is_whitespace() || "-.,;:!?')}[\"\\".contains(c))
+}
+
+/// A text until the next char which may start another node
+/// "abc *bold*" -> ("*bold*", "abc ")
+fn inline_text(input: Span) -> IResult<Text> {
+    let mut chars = input.char_indices().peekable();
+    let mut prev = None;
+    let mut len = input.len();
+    while let Some((i, c)) = chars.next() {
+        let next = chars.peek().map(|&(_, c)| c);
+        let found = (c == '[' && next == Some('['))
+            || (c == '\\' && next == Some('('))
+            || (is_pre(prev) && matches!(c, '#' | '*' | '/' | '+' | '_' | '~' | '='))
+            || (is_pre(prev) && input[i..].starts_with("http"));
+        // the first char failed to be parsed as a node
+        if found && i > 0 {
+            len = i;
+            break;
+        }
+        prev = Some(c);
+    }
+    if len == 0 {
+        return Err(Err::Error(ParseError::new(input, "".into())));
+    }
+    let (input, value) = nom::InputTake::take_split(&input, len);
+    Ok((input, Text::new(*value)))
+}
+
+/// `marker` text `marker`, like `*bold*`
+fn markup(marker: char) -> impl Fn(Span) -> IResult<Span> {
+    move |input: Span| {
+        let (rest, text) = delimited(
+            char(marker),
+            take_while1(|c| c != marker && c != '\n'),
+            char(marker),
+        )(input.clone())?;
+        let is_markup = !text.starts_with(char::is_whitespace)
+            && !text.ends_with(char::is_whitespace)
+            && is_post(rest.chars().next());
+        if !is_markup {
+            return Err(Err::Error(ParseError::new(input, "not a markup".into())));
+        }
+        Ok((rest, text))
+    }
+}
+
+/// *bold* /italic/ +strikethrough+ _underline_
+fn emphasis(input: Span) -> IResult<Emphasis> {
+    alt((
+        map(markup('*'), |s: Span| Emphasis::bold(*s)),
+        map(markup('/'), |s: Span| Emphasis::italic(*s)),
+        map(markup('+'), |s: Span| Emphasis::strikethrough(*s)),
+        map(markup('_'), |s: Span| Emphasis::underline(*s)),
+    ))(input)
+}
+
+/// ~code~ =verbatim=
+fn verbatim(input: Span) -> IResult<BlockQuate> {
+    map(alt((markup('~'), markup('='))), |s: Span| {
+        BlockQuate::new(*s)
+    })(input)
+}
+
+/// \( math \)
+fn math(input: Span) -> IResult<Math> {
+    let (input, _) = tag("\\(")(input)?;
+    let end = input
+        .find("\\)")
+        .filter(|&end| !input[..end].contains('\n'))
+        .ok_or_else(|| Err::Error(ParseError::new(input.clone(), "no \\)".into())))?;
+    let (input, value) = nom::InputTake::take_split(&input, end);
+    let (input, _) = tag("\\)")(input)?;
+    Ok((input, Math::new(value.trim())))
+}
+
+/// [[https://www.rust-lang.org/][Rust]]
+/// [[file:page.org][page]]
+/// [[https://example.com/image.png]]
+/// [[geo:35.6812,139.7671?z=14][Tokyo Station]]
+fn link(input: Span) -> IResult<Node> {
+    let (input, _) = tag("[[")(input)?;
+    let (input, target) = take_while1(|c| c != ']' && c != '\n')(input)?;
+    let (input, description) = alt((
+        value(None, tag("]]")),
+        map(
+            delimited(tag("]["), take_while1(|c| c != ']' && c != '\n'), tag("]]")),
+            |s: Span| Some(*s),
+        ),
+    ))(input)?;
+
+    let media = input.extra.config.media.clone();
+    let target = *target;
+    let kind = if target.starts_with("https://") || target.starts_with("http://") {
+        match description {
+            Some(image) if media.is_image(image) => {
+                NodeKind::Image(Image::with_link(image, target))
+            }
+            Some(title) => NodeKind::ExternalLink(ExternalLink::new(Some(title), target)),
+            None if media.is_image(target) => NodeKind::Image(Image::new(target)),
+            None => match media.embed_provider(target) {
+                Some(provider) => NodeKind::Embed(Embed::new(provider, target)),
+                None => NodeKind::ExternalLink(ExternalLink::new(None, target)),
+            },
+        }
+    } else if let Some(location) = target.strip_prefix("geo:").and_then(geo_uri) {
+        let (latitude, longitude, zoom) = location;
+        NodeKind::Location(Location::new(latitude, longitude, zoom, description))
+    } else {
+        NodeKind::InternalLink(internal_link(target, description))
+    };
+    Ok((input, Node::new(kind)))
+}
+
+/// "35.6812,139.7671?z=14" -> (35.6812, 139.7671, 14)
+fn geo_uri(value: &str) -> Option<(f64, f64, u32)> {
+    let (coordinates, zoom) = match value.split_once("?z=") {
+        Some((coordinates, zoom)) => (coordinates, zoom.parse().ok()?),
+        None => (value, 14),
+    };
+    let (latitude, longitude) = coordinates.split_once(',')?;
+    Some((latitude.parse().ok()?, longitude.parse().ok()?, zoom))
+}
+
+/// "file:page.org::*heading" -> page, heading
+/// "*heading" -> the heading in the file
+fn internal_link<'a>(target: &'a str, description: Option<&'a str>) -> InternalLink<'a> {
+    let (file, search) = match target.strip_prefix("file:") {
+        Some(path) => match path.split_once("::") {
+            Some((path, search)) => (path, Some(search)),
+            None => (path, None),
+        },
+        None if target.starts_with(['*', '#']) => ("", Some(target)),
+        None => (target, None),
+    };
+    let title = file.strip_suffix(".org").unwrap_or(file);
+    let anchor = search.map(|s| s.trim_start_matches(['*', '#']));
+    InternalLink {
+        title: title.into(),
+        anchor: anchor.map(Cow::from),
+        // `[[page][page]]` is `[page]`
+        label: description.filter(|d| *d != title).map(Cow::from),
+        embed: false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use indoc::indoc;
+    use rstest::rstest;
+
+    fn span(input: &str) -> Span<'_> {
+        Span::new_extra(input, OrgParserContext::default())
+    }
+
+    #[rstest(input, expected,
+        case("- a\n  - b\n    1. c\n  + d\n- e\n", vec![(ListKind::Disc, 1, "a"), (ListKind::Disc, 2, "b"), (ListKind::Decimal, 3, "c"), (ListKind::Disc, 2, "d"), (ListKind::Disc, 1, "e")]),
+        case("1) a\n   b. b\n   * c", vec![(ListKind::Decimal, 1, "a"), (ListKind::Alphabet, 2, "b"), (ListKind::Disc, 2, "c")]),
+        case("\t- a\n\t\t- b\n", vec![(ListKind::Disc, 1, "a"), (ListKind::Disc, 2, "b")]),
+    )]
+    fn list_valid_test(input: &str, expected: Vec<(ListKind, usize, &str)>) {
+        let (rest, list) = list(span(input)).unwrap();
+        assert_eq!(*rest, "");
+        let expected: Vec<_> = expected
+            .into_iter()
+            .map(|(kind, level, text)| {
+                ListItem::new(
+                    kind,
+                    level,
+                    vec![Node::new(NodeKind::Text(Text::new(text)))],
+                )
+            })
+            .collect();
+        assert_eq!(list.children, expected);
+    }
+
+    #[rstest(input, case("* heading\n"), case("-a\n"), case("abc\n"))]
+    fn list_invalid_test(input: &str) {
+        assert!(list(span(input)).is_err());
+    }
+
+    #[rstest(input, expected,
+        case("#+BEGIN_SRC js :tangle hello.js\n  console.log(1);\n    a;\n#+END_SRC\n", ("", CodeBlock::new("hello.js", vec!["console.log(1);", "  a;"]))),
+        case("#+begin_src rust\nfn main() {}\n\n#+end_src", ("", CodeBlock::new("rust", vec!["fn main() {}", ""]))),
+        case("#+BEGIN_SRC\na\n#+END_SRC\nb", ("b", CodeBlock::new("", vec!["a"]))),
+        case("#+BEGIN_SRC org\n,* a\n  ,,#+b\n,c\n#+END_SRC\n", ("", CodeBlock::new("org", vec!["* a", "  ,#+b", ",c"]))),
+        case("#+BEGIN_EXAMPLE\na\n#+END_EXAMPLE\n", ("", CodeBlock::new("", vec!["a"]))),
+        case("#+BEGIN_SRC js\n  a\n\u{3000}\n \n#+END_SRC\n", ("", CodeBlock::new("js", vec!["a", "\u{3000}", ""]))),
+    )]
+    fn code_block_valid_test(input: &str, expected: (&str, CodeBlock)) {
+        assert_eq!(
+            block(span(input)).map(|(input, ret)| (*input, ret)),
+            Ok((expected.0, Node::new(NodeKind::CodeBlock(expected.1))))
+        );
+    }
+
+    #[test]
+    fn callout_valid_test() {
+        let input = "#+BEGIN_NOTE title\n*body*\n#+END_NOTE\n";
+        assert_eq!(
+            block(span(input)).map(|(input, ret)| (*input, ret)),
+            Ok((
+                "",
+                Node::new(NodeKind::Callout(Callout::new(
+                    "note",
+                    Some("title"),
+                    vec![Node::new(NodeKind::Paragraph(Paragraph::new(vec![
+                        Node::new(NodeKind::Emphasis(Emphasis::bold("body")))
+                    ])))]
+                )))
+            ))
+        );
+    }
+
+    #[rstest(input, expected,
+        case("| a | b |\n|---+---|\n| c | d |\n", ("", Table::new("table", vec!["a".into(), "b".into()], vec![vec!["c".into(), "d".into()]]))),
+        case("#+NAME: t\n|a|b\n", ("", Table::new("t", vec!["a".into(), "b".into()], vec![]))),
+        case("| a |\nb\n", ("b\n", Table::new("table", vec!["a".into()], vec![]))),
+    )]
+    fn table_valid_test(input: &str, expected: (&str, Table)) {
+        assert_eq!(
+            table(span(input)).map(|(input, ret)| (*input, ret)),
+            Ok(expected)
+        );
+    }
+
+    #[rstest(input, expected,
+        case("*bold*", ("", Node::new(NodeKind::Emphasis(Emphasis::bold("bold"))))),
+        case("/italic/.", (".", Node::new(NodeKind::Emphasis(Emphasis::italic("italic"))))),
+        case("+strike+", ("", Node::new(NodeKind::Emphasis(Emphasis::strikethrough("strike"))))),
+        case("_underline_", ("", Node::new(NodeKind::Emphasis(Emphasis::underline("underline"))))),
+        case("~code~", ("", Node::new(NodeKind::BlockQuate(BlockQuate::new("code"))))),
+        case("=verbatim=", ("", Node::new(NodeKind::BlockQuate(BlockQuate::new("verbatim"))))),
+        case("\\( x^2 \\)", ("", Node::new(NodeKind::Math(Math::new("x^2"))))),
+        case("[[https://www.rust-lang.org/][Rust]]", ("", Node::new(NodeKind::ExternalLink(ExternalLink::new(Some("Rust"), "https://www.rust-lang.org/"))))),
+        case("[[https://www.rust-lang.org/]]", ("", Node::new(NodeKind::ExternalLink(ExternalLink::new(None, "https://www.rust-lang.org/"))))),
+        case("[[https://example.com/a.png]]", ("", Node::new(NodeKind::Image(Image::new("https://example.com/a.png"))))),
+        case("[[https://example.com/][https://example.com/a.png]]", ("", Node::new(NodeKind::Image(Image::with_link("https://example.com/a.png", "https://example.com/"))))),
+        case("[[https://www.youtube.com/watch?v=xxxxxxxxxxx]]", ("", Node::new(NodeKind::Embed(Embed::new(EmbedProvider::YouTube, "https://www.youtube.com/watch?v=xxxxxxxxxxx"))))),
+        case("[[geo:35.6812,139.7671?z=14][Tokyo Station]]", ("", Node::new(NodeKind::Location(Location::new(35.6812, 139.7671, 14, Some("Tokyo Station")))))),
+        case("[[file:page.org][page]]", ("", Node::new(NodeKind::InternalLink(InternalLink::new("page"))))),
+        case("[[file:page.org::*heading][label]]", ("", Node::new(NodeKind::InternalLink(InternalLink { title: "page".into(), anchor: Some("heading".into()), label: Some("label".into()), embed: false })))),
+        case("[[*heading]]", ("", Node::new(NodeKind::InternalLink(InternalLink { title: "".into(), anchor: Some("heading".into()), ..Default::default() })))),
+        case("[[page]]", ("", Node::new(NodeKind::InternalLink(InternalLink::new("page"))))),
+        case("#tag", ("", Node::new(NodeKind::HashTag(HashTag::new("tag"))))),
+        case("https://example.com/ a", (" a", Node::new(NodeKind::ExternalLink(ExternalLink::plain("https://example.com/"))))),
+    )]
+    fn node_valid_test(input: &str, expected: (&str, Node)) {
+        assert_eq!(
+            node(span(input)).map(|(input, ret)| (*input, ret)),
+            Ok(expected)
+        );
+    }
+
+    #[rstest(input, expected,
+        case("a *b* c", vec![Node::new(NodeKind::Text(Text::new("a "))), Node::new(NodeKind::Emphasis(Emphasis::bold("b"))), Node::new(NodeKind::Text(Text::new(" c")))]),
+        case("and/or 2*3*4", vec![Node::new(NodeKind::Text(Text::new("and/or 2*3*4")))]),
+        case("a * b *", vec![Node::new(NodeKind::Text(Text::new("a "))), Node::new(NodeKind::Text(Text::new("* b "))), Node::new(NodeKind::Text(Text::new("*")))]),
+        case("*a*b", vec![Node::new(NodeKind::Text(Text::new("*a*b")))]),
+        case("[[a]] [[b #[c #d", vec![Node::new(NodeKind::InternalLink(InternalLink::new("a"))), Node::new(NodeKind::Text(Text::new(" "))), Node::new(NodeKind::Text(Text::new("[[b "))), Node::new(NodeKind::Text(Text::new("#[c "))), Node::new(NodeKind::HashTag(HashTag::new("d")))]),
+    )]
+    fn paragraph_valid_test(input: &str, expected: Vec<Node>) {
+        assert_eq!(
+            paragraph(span(input)).map(|(input, ret)| (*input, ret)),
+            Ok(("", Paragraph::new(expected)))
+        );
+    }
+
+    #[test]
+    fn page_test() {
+        let input = indoc! {"
+            #+TITLE: title
+            #+FILETAGS: :tag1:tag2:
+            * heading :a:b:
+            # comment
+            text
+        "};
+        let (rest, page) = page(span(input)).unwrap();
+        assert_eq!(*rest, "");
+        assert_eq!(
+            page,
+            Page {
+                title: Some("title".into()),
+                tags: vec!["tag1".into(), "tag2".into()],
+                nodes: vec![
+                    Node::new(NodeKind::Paragraph(Paragraph::new(vec![
+                        Node::new(NodeKind::Heading(Heading::new("heading", 1))),
+                        Node::new(NodeKind::Text(Text::new(" "))),
+                        Node::new(NodeKind::HashTag(HashTag::new("a"))),
+                        Node::new(NodeKind::Text(Text::new(" "))),
+                        Node::new(NodeKind::HashTag(HashTag::new("b"))),
+                    ]))),
+                    Node::new(NodeKind::Paragraph(Paragraph::new(vec![Node::new(
+                        NodeKind::Comment(Comment::new("comment"))
+                    )]))),
+                    Node::new(NodeKind::Paragraph(Paragraph::new(vec![Node::new(
+                        NodeKind::Text(Text::new("text"))
+                    )]))),
+                ],
+                ..Default::default()
+            }
+        );
+    }
+}
@@ -1,34 +1,139 @@
 use nom::{
     branch::alt,
-    bytes::complete::{tag, take_until, take_while},
+    bytes::complete::{tag, take_until, take_while, take_while1},
     character::complete::{char, digit1},
-    combinator::{eof, map, opt},
+    combinator::{eof, map, opt, recognize},
     multi::{many0, many1},
-    sequence::delimited,
-    sequence::terminated,
+    sequence::{delimited, pair, terminated},
     Err, InputTake,
 };
 
 use super::utils::*;
 use super::{error, ParseError};
 use crate::ast::*;
+use crate::media::MediaConfig;
+use std::borrow::Cow;
+use std::sync::Arc;
 
 pub type Span<'a> = error::Span<'a, ScrapboxParserContext>;
 pub type IResult<'a, O> = error::IResult<'a, O, ScrapboxParserContext>;
 
-#[derive(Default, Debug, Copy, Clone, PartialEq)]
+#[derive(Default, Debug, Clone, PartialEq)]
 pub struct ScrapboxParserContext {
+    pub config: ScrapboxParserConfig,
     /// current indent size of list
     pub indent: usize,
+    /// the last "]" of the current line
+    pub brackets: LineBrackets,
+}
+
+impl AsMut<LineBrackets> for ScrapboxParserContext {
+    fn as_mut(&mut self) -> &mut LineBrackets {
+        &mut self.brackets
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct ScrapboxParserConfig {
+    /// If true, the first line is read as `Page::title` instead of a paragraph.
+    pub title: bool,
+    /// If true, the lines of hashtags at the end of the page are read as `Page::tags`.
+    pub tags: bool,
+    /// a pointer to keep `Span` small
+    pub media: Arc<MediaConfig>,
 }
 
 pub fn page(input: Span) -> IResult<Page> {
-    let (input, nodes) = many0(alt((
+    let config = input.extra.config.clone();
+
+    let (input, title) = if config.title {
+        opt(title)(input)?
+    } else {
+        (input, None)
+    };
+
+    let (input, mut nodes) = many0(block)(input)?;
+
+    let tags = if config.tags {
+        take_trailing_tags(&mut nodes)
+    } else {
+        vec![]
+    };
+
+    Ok((
+        input,
+        Page {
+            title,
+            tags,
+            nodes,
+            ..Default::default()
+        },
+    ))
+}
+
+/// A node at the top level of a page
+pub(crate) fn block(input: Span) -> IResult<Node> {
+    alt((
         // parser for multiline block
         map(list, |s| Node::new(NodeKind::List(s))),
         map(paragraph, |s| Node::new(NodeKind::Paragraph(s))),
-    )))(input)?;
-    Ok((input, Page { nodes }))
+    ))(input)
+}
+
+/// The first line of a page
+pub(crate) fn title(input: Span) -> IResult<Cow<str>> {
+    let (input, title) = take_until_eol(input)?;
+    let (input, _) = alt((tag("\n"), eof))(input)?;
+    if title.trim().is_empty() {
+        return Err(Err::Error(ParseError::new(input, "empty title".into())));
+    }
+    Ok((input, title.fragment().trim().into()))
+}
+
+/// Remove the lines which only have hashtags (and the blank lines after them)
+/// from the end of the page, and return the tags.
+///
+/// ```text
+/// body
+/// #tag1 #tag2
+/// #tag3
+/// ```
+pub(crate) fn take_trailing_tags<'a>(nodes: &mut Vec<Node<'a>>) -> Vec<Cow<'a, str>> {
+    fn tags_of<'a>(node: &Node<'a>) -> Option<Vec<Cow<'a, str>>> {
+        let paragraph = match &node.kind {
+            NodeKind::Paragraph(p) => p,
+            _ => return None,
+        };
+        let mut tags = vec![];
+        for child in &paragraph.children {
+            match &child.kind {
+                NodeKind::HashTag(tag) => tags.push(tag.value.clone()),
+                NodeKind::Text(text) if text.value.chars().all(is_space) => {}
+                _ => return None,
+            }
+        }
+        Some(tags)
+    }
+
+    let mut tags = vec![];
+    let mut len = nodes.len();
+    for node in nodes.iter().rev() {
+        match tags_of(node) {
+            // blank line
+            Some(line) if line.is_empty() => {}
+            Some(mut line) => {
+                line.append(&mut tags);
+                tags = line;
+            }
+            None => break,
+        }
+        len -= 1;
+    }
+
+    if !tags.is_empty() {
+        nodes.truncate(len);
+    }
+    tags
 }
 
 fn paragraph(input: Span) -> IResult<Paragraph> {
@@ -41,10 +146,15 @@ fn paragraph(input: Span) -> IResult<Paragraph> {
 }
 
 fn list(input: Span) -> IResult<List> {
-    map(many1(list_item), |children| List::new(children))(input)
+    map(many1(list_item), List::new)(input)
 }
 
 fn node(input: Span) -> IResult<Node> {
+    let (input, unclosed) = unclosed_bracket(input)?;
+    if let Some(text) = unclosed {
+        return Ok((input, Node::new(NodeKind::Text(text))));
+    }
+
     alt((
         map(code_block, |s| Node::new(NodeKind::CodeBlock(s))),
         map(table, |s| Node::new(NodeKind::Table(s))),
@@ -54,6 +164,7 @@ fn node(input: Span) -> IResult<Node> {
         map(emphasis, |c| Node::new(NodeKind::Emphasis(c))),
         map(bold, |c| Node::new(NodeKind::Emphasis(c))),
         external_link_or_image,
+        map(location, |c| Node::new(NodeKind::Location(c))),
         map(math, |c| Node::new(NodeKind::Math(c))),
         map(external_link_other_project, |s| {
             Node::new(NodeKind::ExternalLink(s))
@@ -65,6 +176,8 @@ fn node(input: Span) -> IResult<Node> {
         }),
         map(commandline, |s| Node::new(NodeKind::BlockQuate(s))),
         map(text, |s| Node::new(NodeKind::Text(s))),
+        // "`" which isn't closed in the line
+        map(tag("`"), |s: Span| Node::new(NodeKind::Text(Text::new(*s)))),
     ))(input)
 }
 
@@ -76,14 +189,11 @@ fn internal_link(input: Span) -> IResult<InternalLink> {
 
 // [/help-jp/Scrapbox]
 fn external_link_other_project(input: Span) -> IResult<ExternalLink> {
-    let (input, title) = brackets(input)?;
-    let (title, _) = tag("/")(title)?;
+    let (input, text) = brackets(input)?;
+    let (title, _) = tag("/")(text.clone())?;
     Ok((
         input,
-        ExternalLink::new(
-            Some(&format!("/{}", title)),
-            &format!("https://scrapbox.io/{}", title),
-        ),
+        ExternalLink::new(Some(*text), format!("https://scrapbox.io/{}", title)),
     ))
 }
 
@@ -99,10 +209,11 @@ fn external_link_or_image(input: Span) -> IResult<Node> {
         let (input, _) = space0(input)?;
         let (input, url) = url(input)?;
         let (title, _) = space0(input)?;
+        let extra = title.extra.clone();
         let title = if title.is_empty() { None } else { Some(title) };
         Ok((
-            Span::new_extra("", input.extra),
-            ExternalLink::new(title.map(|s: Span| *s), &url),
+            Span::new_extra("", extra),
+            ExternalLink::new(title.map(|s: Span| *s), url),
         ))
     }
 
@@ -126,71 +237,139 @@ fn external_link_or_image(input: Span) -> IResult<Node> {
         let (input, _) = space1(link)?;
         let (rest, url) = url(input)?;
         assert!(rest.is_empty());
-        Ok((rest, ExternalLink::new(title.map(|s: Span| *s), &url)))
+        Ok((rest, ExternalLink::new(title.map(|s: Span| *s), url)))
     }
 
     let (rest, link) = alt((url_title, title_url))(text)?;
     assert!(rest.is_empty());
 
-    let ext = ["svg", "jpg", "jpeg", "png", "gif"];
-    let is_image = |url: &str| ext.iter().any(|e| url.ends_with(e));
-    dbg!(&link);
-    if is_image(&link.url) {
-        Ok((input, Node::new(NodeKind::Image(Image::new(&link.url)))))
-    } else if let Some(true) = link.title.as_ref().map(|t| is_image(t)) {
-        // workaround
+    let media = input.extra.config.media.clone();
+    // the other is a link only when it is a URL
+    let is_url = |s: &Cow<str>| matches!(url(Span::new_extra(s, input.extra.clone())), Ok((rest, _)) if rest.is_empty());
+    if media.is_image(&link.url) {
+        let image = Image {
+            uri: link.url.clone(),
+            link: link.title.clone().filter(is_url),
+        };
+        Ok((input, Node::new(NodeKind::Image(image))))
+    } else if let Some(title) = link.title.clone().filter(|t| media.is_image(t)) {
+        // [link_url image_url]
+        let image = Image {
+            uri: title,
+            link: Some(link.url.clone()),
+        };
+        Ok((input, Node::new(NodeKind::Image(image))))
+    } else if let (None, Some(provider)) = (&link.title, media.embed_provider(&link.url)) {
+        // [https://www.youtube.com/watch?v=xxxxxxxxxxx]
         Ok((
             input,
-            Node::new(NodeKind::Image(Image::new(&link.title.unwrap()))),
+            Node::new(NodeKind::Embed(Embed::new(provider, link.url))),
         ))
     } else {
         Ok((input, Node::new(NodeKind::ExternalLink(link))))
     }
 }
 
+/// [N35.6812,E139.7671,Z14]
+/// [N35.6812,E139.7671,Z14 Tokyo Station]
+fn location(input: Span) -> IResult<Location> {
+    let (input, text) = brackets(input)?;
+
+    // N35.6812 -> 35.6812, S35.6812 -> -35.6812
+    fn coordinate(input: Span, positive: char, negative: char, max: f64) -> IResult<f64> {
+        let (input, sign) = alt((char(positive), char(negative)))(input)?;
+        let (rest, value) = take_while1(|c: char| c.is_ascii_digit() || c == '.')(input.clone())?;
+        match value.parse::<f64>() {
+            Ok(value) if value > max => Err(Err::Error(ParseError::new(
+                input,
+                "coordinate out of range".into(),
+            ))),
+            Ok(value) if sign == positive => Ok((rest, value)),
+            Ok(value) => Ok((rest, -value)),
+            Err(_) => Err(Err::Error(ParseError::new(
+                input,
+                "invalid coordinate".into(),
+            ))),
+        }
+    }
+
+    let (text, latitude) = coordinate(text, 'N', 'S', 90.0)?;
+    let (text, _) = char(',')(text)?;
+    let (text, longitude) = coordinate(text, 'E', 'W', 180.0)?;
+    let (text, _) = tag(",Z")(text)?;
+    let (text, zoom) = digit1(text)?;
+    let zoom = zoom
+        .parse::<u32>()
+        .map_err(|_| Err::Error(ParseError::new(zoom, "invalid zoom".into())))?;
+
+    let label = if text.is_empty() {
+        None
+    } else {
+        let (label, _) = space1(text)?;
+        Some(label.trim_end())
+    };
+
+    Ok((
+        input,
+        Location::new(latitude, longitude, zoom, label.filter(|l| !l.is_empty())),
+    ))
+}
+
 /// [/icons/todo.icon]
+#[allow(dead_code)]
 fn icon() {}
 
+const DECORATIONS: &[char] = &['*', '/', '-', '_', '!', '#', '~', '%', '{', '}', '<', '>'];
+
 /// [*-/** emphasis]
 /// [[Bold]] or [* Bold] or [*** Bold]
 /// [/ italic]
 /// [- strikethrough]
+/// [_ underline]
+/// [# marker]
+/// [!~%{}<> other decorations]
 fn emphasis(input: Span) -> IResult<Emphasis> {
     let (input, text) = brackets(input)?;
 
-    let (rest, tokens) = take_while(|c| ['*', '/', '-'].contains(&c))(text)?;
+    let (rest, tokens) = take_while(|c| DECORATIONS.contains(&c))(text)?;
     let (text, _) = char(' ')(rest)?;
 
-    let mut bold = 0;
-    let mut italic = 0;
-    let mut strikethrough = 0;
+    let mut emphasis = Emphasis::new(text.trim(), 0, 0, 0);
     for c in tokens.chars() {
-        match &c {
-            '*' => bold += 1,
-            '/' => italic += 1,
-            '-' => strikethrough += 1,
-            _ => {}
+        match c {
+            '*' => emphasis.bold += 1,
+            '/' => emphasis.italic += 1,
+            '-' => emphasis.strikethrough += 1,
+            '_' => emphasis.underline += 1,
+            '!' => emphasis.emphasized += 1,
+            '#' => emphasis.highlight += 1,
+            '~' => emphasis.wavy += 1,
+            '%' => emphasis.percent += 1,
+            c => emphasis.custom.to_mut().push(c),
         }
     }
+    if emphasis.marks() != *tokens {
+        emphasis.order = Some((*tokens).into());
+    }
 
-    Ok((
-        input,
-        Emphasis::new(text.trim(), bold, italic, strikethrough),
-    ))
+    Ok((input, emphasis))
 }
 
 // [[bold]]
 fn bold(input: Span) -> IResult<Emphasis> {
     map(
-        delimited(tag("[["), take_while(|c| c != ']'), tag("]]")),
-        |s: Span| Emphasis::bold(s.trim()),
+        delimited(tag("[["), take_while(|c| c != ']' && c != '\n'), tag("]]")),
+        |s: Span| Emphasis {
+            double_bracket: true,
+            ..Emphasis::bold(s.trim())
+        },
     )(input)
 }
 
 /// [$ Tex here]
 fn math(input: Span) -> IResult<Math> {
     map(
-        delimited(tag("[$"), take_while(|c| c != ']'), char(']')),
+        delimited(tag("[$"), take_while(|c| c != ']' && c != '\n'), char(']')),
         |s: Span| Math::new(s.trim()),
     )(input)
 }
@@ -222,21 +401,21 @@ fn table(input: Span) -> IResult<Table> {
     let (input, name) = take_until("\n")(input)?;
     let (input, _) = char('\n')(input)?;
 
-    fn row(input: Span) -> IResult<Vec<String>> {
+    fn row(input: Span) -> IResult<Vec<Cow<str>>> {
         let prefix = format!(" {}", " ".repeat(input.extra.indent));
         let (input, _) = tag(prefix.as_str())(input)?;
         let (input, text) = take_until_eol(input)?;
         let (input, _) = opt(tag("\n"))(input)?;
 
-        fn take_until_t(input: Span) -> IResult<String> {
+        fn take_until_t(input: Span) -> IResult<Cow<str>> {
             let (input, value) = take_until("\t")(input)?;
             let (input, _) = tag("\t")(input)?;
-            Ok((input, value.to_string()))
+            Ok((input, (*value.fragment()).into()))
         }
 
-        fn take_until_n(input: Span) -> IResult<String> {
+        fn take_until_n(input: Span) -> IResult<Cow<str>> {
             let (input, value) = take_until_eol(input)?;
-            Ok((input, value.to_string()))
+            Ok((input, (*value.fragment()).into()))
         }
 
         let (text, mut x) = many0(take_until_t)(text)?;
@@ -261,17 +440,17 @@ fn table(input: Span) -> IResult<Table> {
 }
 
 /// >
+#[allow(dead_code)]
 fn quote() {}
 
 /// $ hoge or % hoge
 fn commandline(input: Span) -> IResult<BlockQuate> {
-    let (input, prefix) = alt((tag("$ "), tag("% ")))(input)?;
-    let prefix = prefix.to_string();
-    let (input, text) = take_until_eol(input)?;
-    Ok((input, BlockQuate::new(&format!("{}{}", prefix, text))))
+    let (input, text) = recognize(pair(alt((tag("$ "), tag("% "))), take_until_eol))(input)?;
+    Ok((input, BlockQuate::new(*text)))
 }
 
 /// ? hoge
+#[allow(dead_code)]
 fn helpfeel() {}
 
 /// "\tabc"
@@ -292,8 +471,22 @@ fn list_item(input: Span) -> IResult<ListItem> {
     let (mut input, children) = many0(node)(input)?;
     input.extra.indent = 0;
 
-    let (input, _) = alt((tag("\n"), eof))(input)?;
-    Ok((input, ListItem::new(kind, tabs.len(), children)))
+    // `code:` and `table:` take the newline of their last line,
+    // and the next newline is a blank line after the list
+    let block = children
+        .last()
+        .is_some_and(|node| matches!(node.kind, NodeKind::CodeBlock(_) | NodeKind::Table(_)));
+    if !block {
+        (input, _) = alt((tag("\n"), eof))(input)?;
+    }
+    Ok((
+        input,
+        ListItem {
+            indent: Some(tabs.iter().collect::<String>().into()),
+            number: decimal.map(|s| (*s).into()),
+            ..ListItem::new(kind, tabs.len(), children)
+        },
+    ))
 }
 
 #[cfg(test)]
@@ -303,13 +496,14 @@ mod test {
     use rstest::rstest;
 
     #[rstest(input, expected,
-        case("\t\t123abc\n", ("", List::new(vec![ListItem::new(ListKind::Disc, 2, vec![Node::new(NodeKind::Text(Text::new("123abc")))])]))),
-        case(" \t123abc\n", ("", List::new(vec![ListItem::new(ListKind::Disc, 2, vec![Node::new(NodeKind::Text(Text::new("123abc")))])]))),
-        case("\t 123abc\n", ("", List::new(vec![ListItem::new(ListKind::Disc, 2, vec![Node::new(NodeKind::Text(Text::new("123abc")))])]))),
-        case("  123abc\n", ("", List::new(vec![ListItem::new(ListKind::Disc, 2, vec![Node::new(NodeKind::Text(Text::new("123abc")))])]))),
-        case("  123abc", ("", List::new(vec![ListItem::new(ListKind::Disc, 2, vec![Node::new(NodeKind::Text(Text::new("123abc")))])]))),
-        case("　　123abc", ("", List::new(vec![ListItem::new(ListKind::Disc, 2, vec![Node::new(NodeKind::Text(Text::new("123abc")))])]))),
-        case("\t123. abc\n", ("", List::new(vec![ListItem::new(ListKind::Decimal, 1, vec![Node::new(NodeKind::Text(Text::new("abc")))])]))),
+        case("\t\t123abc\n", ("", List::new(vec![ListItem { indent: Some("\t\t".into()), ..ListItem::new(ListKind::Disc, 2, vec![Node::new(NodeKind::Text(Text::new("123abc")))]) }]))),
+        case(" \t123abc\n", ("", List::new(vec![ListItem { indent: Some(" \t".into()), ..ListItem::new(ListKind::Disc, 2, vec![Node::new(NodeKind::Text(Text::new("123abc")))]) }]))),
+        case("\t 123abc\n", ("", List::new(vec![ListItem { indent: Some("\t ".into()), ..ListItem::new(ListKind::Disc, 2, vec![Node::new(NodeKind::Text(Text::new("123abc")))]) }]))),
+        case("  123abc\n", ("", List::new(vec![ListItem { indent: Some("  ".into()), ..ListItem::new(ListKind::Disc, 2, vec![Node::new(NodeKind::Text(Text::new("123abc")))]) }]))),
+        case("  123abc", ("", List::new(vec![ListItem { indent: Some("  ".into()), ..ListItem::new(ListKind::Disc, 2, vec![Node::new(NodeKind::Text(Text::new("123abc")))]) }]))),
+        case("　　123abc", ("", List::new(vec![ListItem { indent: Some("　　".into()), ..ListItem::new(ListKind::Disc, 2, vec![Node::new(NodeKind::Text(Text::new("123abc")))]) }]))),
+        case("\t123. abc\n", ("", List::new(vec![ListItem { indent: Some("\t".into()), number: Some("123".into()), ..ListItem::new(ListKind::Decimal, 1, vec![Node::new(NodeKind::Text(Text::new("abc")))]) }]))),
+        case(" code:a.js\n  a\n\n", ("\n", List::new(vec![ListItem { indent: Some(" ".into()), ..ListItem::new(ListKind::Disc, 1, vec![Node::new(NodeKind::CodeBlock(CodeBlock::new("a.js", vec!["a"])))]) }]))),
     )]
     fn list_valid_test(input: &str, expected: (&str, List)) {
         assert_eq!(
@@ -346,8 +540,14 @@ mod test {
     )]
     fn code_block_in_list_valid_test(input: &str, expected: (&str, CodeBlock)) {
         assert_eq!(
-            code_block(Span::new_extra(input, ScrapboxParserContext { indent: 1 }))
-                .map(|(input, ret)| (*input, ret)),
+            code_block(Span::new_extra(
+                input,
+                ScrapboxParserContext {
+                    indent: 1,
+                    ..Default::default()
+                }
+            ))
+            .map(|(input, ret)| (*input, ret)),
             Ok(expected)
         );
     }
@@ -371,18 +571,27 @@ mod test {
     )]
     fn table_in_list_valid_test(input: &str, expected: (&str, Table)) {
         assert_eq!(
-            table(Span::new_extra(input, ScrapboxParserContext { indent: 1 }))
-                .map(|(input, ret)| (*input, ret)),
+            table(Span::new_extra(
+                input,
+                ScrapboxParserContext {
+                    indent: 1,
+                    ..Default::default()
+                }
+            ))
+            .map(|(input, ret)| (*input, ret)),
             Ok(expected)
         );
     }
 
     #[rstest(input, expected,
         case("[https://www.rust-lang.org/static/images/rust-logo-blk.svg]", ("", Image::new("https://www.rust-lang.org/static/images/rust-logo-blk.svg"))),
-        // TODO(tkat0): enable link
-        case("[https://www.rust-lang.org/static/images/rust-logo-blk.svg https://www.rust-lang.org/]", ("", Image::new("https://www.rust-lang.org/static/images/rust-logo-blk.svg"))),
-        case("[https://www.rust-lang.org/ https://www.rust-lang.org/static/images/rust-logo-blk.svg]", ("", Image::new("https://www.rust-lang.org/static/images/rust-logo-blk.svg"))),
-        case("[https://www.rust-lang.org/　https://www.rust-lang.org/static/images/rust-logo-blk.svg]", ("", Image::new("https://www.rust-lang.org/static/images/rust-logo-blk.svg"))),
+        case("[https://www.rust-lang.org/static/images/rust-logo-blk.svg https://www.rust-lang.org/]", ("", Image::with_link("https://www.rust-lang.org/static/images/rust-logo-blk.svg", "https://www.rust-lang.org/"))),
+        case("[https://www.rust-lang.org/ https://www.rust-lang.org/static/images/rust-logo-blk.svg]", ("", Image::with_link("https://www.rust-lang.org/static/images/rust-logo-blk.svg", "https://www.rust-lang.org/"))),
+        case("[https://www.rust-lang.org/　https://www.rust-lang.org/static/images/rust-logo-blk.svg]", ("", Image::with_link("https://www.rust-lang.org/static/images/rust-logo-blk.svg", "https://www.rust-lang.org/"))),
+        case("[https://www.rust-lang.org/static/images/rust-logo-blk.svg caption]", ("", Image::new("https://www.rust-lang.org/static/images/rust-logo-blk.svg"))),
+        case("[https://gyazo.com/5f93e65a3b979ae5333aca4f32600611]", ("", Image::new("https://gyazo.com/5f93e65a3b979ae5333aca4f32600611"))),
+        case("[https://scrapbox.io/files/62c8e4e2d6a6a1001d8a0f4b.png]", ("", Image::new("https://scrapbox.io/files/62c8e4e2d6a6a1001d8a0f4b.png"))),
+        case("[https://example.com/image.webp?width=100]", ("", Image::new("https://example.com/image.webp?width=100"))),
     )]
     fn image_valid_test(input: &str, expected: (&str, Image)) {
         assert_eq!(
@@ -399,12 +608,61 @@ mod test {
         );
     }
 
+    #[test]
+    fn image_custom_media_test() {
+        let context = ScrapboxParserContext {
+            config: ScrapboxParserConfig {
+                media: Arc::new(MediaConfig {
+                    image_extensions: vec!["bmp".into()],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let image = |input: &str| {
+            let (_, node) =
+                external_link_or_image(Span::new_extra(input, context.clone())).unwrap();
+            matches!(node.kind, NodeKind::Image(_))
+        };
+        assert!(image("[https://example.com/a.bmp]"));
+        assert!(!image("[https://example.com/a.png]"));
+    }
+
+    #[rstest(input, expected,
+        case("[https://www.youtube.com/watch?v=dQw4w9WgXcQ]", ("", Embed::new(EmbedProvider::YouTube, "https://www.youtube.com/watch?v=dQw4w9WgXcQ"))),
+        case("[https://twitter.com/rustlang/status/1549787419512807424]", ("", Embed::new(EmbedProvider::Twitter, "https://twitter.com/rustlang/status/1549787419512807424"))),
+        case("[https://vimeo.com/76979871]", ("", Embed::new(EmbedProvider::Vimeo, "https://vimeo.com/76979871"))),
+        case("[https://example.com/movie.mp4]", ("", Embed::new(EmbedProvider::Video, "https://example.com/movie.mp4"))),
+        case("[https://example.com/music.mp3]", ("", Embed::new(EmbedProvider::Audio, "https://example.com/music.mp3"))),
+    )]
+    fn embed_valid_test(input: &str, expected: (&str, Embed)) {
+        assert_eq!(
+            external_link_or_image(Span::new_extra(input, ScrapboxParserContext::default())).map(
+                |(input, ret)| {
+                    if let NodeKind::Embed(embed) = ret.kind {
+                        (*input, embed)
+                    } else {
+                        panic!();
+                    }
+                }
+            ),
+            Ok(expected)
+        );
+    }
+
     #[rstest(input, expected,
         case("[* text]", ("", Emphasis::bold_level("text", 1))),
         case("[***** text]", ("", Emphasis::bold_level("text", 5))),
         case("[/ text]", ("", Emphasis::italic("text"))),
-        case("[*/*-* text]", ("", Emphasis::new("text", 3, 1, 1))),
-        case("[*/*-*  text　]", ("", Emphasis::new("text", 3, 1, 1))),
+        case("[*/*-* text]", ("", Emphasis { order: Some("*/*-*".into()), ..Emphasis::new("text", 3, 1, 1) })),
+        case("[*/*-*  text　]", ("", Emphasis { order: Some("*/*-*".into()), ..Emphasis::new("text", 3, 1, 1) })),
+        case("[**/- text]", ("", Emphasis::new("text", 2, 1, 1))),
+        case("[/* text]", ("", Emphasis { order: Some("/*".into()), ..Emphasis::new("text", 1, 1, 0) })),
+        case("[_ text]", ("", Emphasis::underline("text"))),
+        case("[# text]", ("", Emphasis::highlight("text"))),
+        case("[*_!~% text]", ("", Emphasis { underline: 1, emphasized: 1, wavy: 1, percent: 1, ..Emphasis::bold("text") })),
+        case("[{<*>} text]", ("", Emphasis { custom: "{<>}".into(), order: Some("{<*>}".into()), ..Emphasis::bold("text") })),
     )]
     fn emphasis_valid_test(input: &str, expected: (&str, Emphasis)) {
         assert_eq!(
@@ -415,8 +673,8 @@ mod test {
     }
 
     #[rstest(input, expected,
-        case("[[text]]", ("", Emphasis::bold_level("text", 1))),
-        case("[[ text　]]", ("", Emphasis::bold_level("text", 1))),
+        case("[[text]]", ("", Emphasis { double_bracket: true, ..Emphasis::bold_level("text", 1) })),
+        case("[[ text　]]", ("", Emphasis { double_bracket: true, ..Emphasis::bold_level("text", 1) })),
     )]
     fn bold_valid_test(input: &str, expected: (&str, Emphasis)) {
         assert_eq!(
@@ -464,6 +722,7 @@ mod test {
         // Scrapbox actually doesn't parse this
         case("[ https://www.rust-lang.org/ Rust ]", ("", ExternalLink::new(Some("Rust "), "https://www.rust-lang.org/"))),
         case("[Rust https://www.rust-lang.org/ https://www.rust-lang.org/]", ("", ExternalLink::new(Some("Rust https://www.rust-lang.org/"), "https://www.rust-lang.org/"))),
+        case("[video https://www.youtube.com/watch?v=dQw4w9WgXcQ]", ("", ExternalLink::new(Some("video"), "https://www.youtube.com/watch?v=dQw4w9WgXcQ"))),
     )]
     fn external_link_valid_test(input: &str, expected: (&str, ExternalLink)) {
         assert_eq!(
@@ -480,6 +739,41 @@ mod test {
         );
     }
 
+    #[rstest(input, expected,
+        case("[N35.6812,E139.7671,Z14]", ("", Location::new(35.6812, 139.7671, 14, None))),
+        case("[N35.6812,E139.7671,Z14 Tokyo Station]", ("", Location::new(35.6812, 139.7671, 14, Some("Tokyo Station")))),
+        case("[S33.8568,W151.2153,Z5　Sydney ]", ("", Location::new(-33.8568, -151.2153, 5, Some("Sydney")))),
+        case("[S90,W180,Z1]", ("", Location::new(-90.0, -180.0, 1, None))),
+    )]
+    fn location_valid_test(input: &str, expected: (&str, Location)) {
+        assert_eq!(
+            location(Span::new_extra(input, ScrapboxParserContext::default()))
+                .map(|(input, ret)| (*input, ret)),
+            Ok(expected)
+        );
+    }
+
+    #[rstest(
+        input,
+        case("[N35.6812,E139.7671]"),
+        case("[N35.6812 E139.7671,Z14]"),
+        case("[E139.7671,N35.6812,Z14]"),
+        case("[N35.6.812,E139.7671,Z14]"),
+        case("[N35.6812,E139.7671,Z14Tokyo]"),
+        case("[N135,E500,Z14]"),
+        case("[S90.1,E139.7671,Z14]"),
+        case("[N35.6812,W180.5,Z14]"),
+        case("[Nagoya]")
+    )]
+    fn location_invalid_test(input: &str) {
+        if let Ok(ok) = location(Span::new_extra(input, ScrapboxParserContext::default())) {
+            panic!("{:?}", ok)
+        }
+        // a link as it was before `location`
+        let (_, node) = node(Span::new_extra(input, ScrapboxParserContext::default())).unwrap();
+        assert!(matches!(node.kind, NodeKind::InternalLink(_)), "{:?}", node);
+    }
+
     #[rstest(input, expected,
         case(r#"[$ \frac{-b \pm \sqrt{b^2-4ac}}{2a} ]"#, ("", Math::new(r#"\frac{-b \pm \sqrt{b^2-4ac}}{2a}"#))),
     )]
@@ -494,6 +788,7 @@ mod test {
     #[rstest(input, expected,
         case("abc #tag ", ("#tag ", Node::new(NodeKind::Text(Text::new("abc "))))),
         case("[title]abc", ("abc", Node::new(NodeKind::InternalLink(InternalLink::new("title"))))),
+        case("#[multi word tag] abc", (" abc", Node::new(NodeKind::HashTag(HashTag::bracketed("multi word tag"))))),
         case("[", ("", Node::new(NodeKind::Text(Text::new("["))))),
         case(r#"[$ \frac{-b \pm \sqrt{b^2-4ac}}{2a} ]"#, ("", Node::new(NodeKind::Math(Math::new(r#"\frac{-b \pm \sqrt{b^2-4ac}}{2a}"#))))),
     )]
@@ -524,6 +819,25 @@ mod test {
                 Node::new(NodeKind::InternalLink(InternalLink::new("internal link")))
                 ]
             ))),
+        case("a `b\nc`\n", ("c`\n", Paragraph::new(
+            vec![
+                Node::new(NodeKind::Text(Text::new("a "))),
+                Node::new(NodeKind::Text(Text::new("`"))),
+                Node::new(NodeKind::Text(Text::new("b"))),
+                ]
+            ))),
+        case("[a] #[b [c #d\n", ("", Paragraph::new(
+            vec![
+                Node::new(NodeKind::InternalLink(InternalLink::new("a"))),
+                Node::new(NodeKind::Text(Text::new(" "))),
+                Node::new(NodeKind::Text(Text::new("#"))),
+                Node::new(NodeKind::Text(Text::new("["))),
+                Node::new(NodeKind::Text(Text::new("b "))),
+                Node::new(NodeKind::Text(Text::new("["))),
+                Node::new(NodeKind::Text(Text::new("c "))),
+                Node::new(NodeKind::HashTag(HashTag::new("d"))),
+                ]
+            ))),
     )]
     fn paragraph_valid_test(input: &str, expected: (&str, Paragraph)) {
         assert_eq!(
@@ -561,7 +875,8 @@ mod test {
                         Node::new(NodeKind::BlockQuate(BlockQuate::new("code"))),
                         Node::new(NodeKind::Text(Text::new("bbb"))),
                 ]))),
-            ]
+            ],
+            ..Default::default()
         })),
         case(indoc! {"
             a
@@ -580,7 +895,8 @@ mod test {
                         value: "b".into(),
                     }))
                 ]))),
-            ]
+            ],
+            ..Default::default()
         }))
     )]
     fn page_valid_test(input: &str, expected: (&str, Page)) {
@@ -590,4 +906,82 @@ mod test {
             Ok(expected)
         );
     }
+
+    #[rstest(input, expected,
+        case(indoc! {"
+            title
+            abc
+            #tag1 #tag2
+            #tag3　
+
+        "}, ("", Page {
+            title: Some("title".into()),
+            tags: vec!["tag1".into(), "tag2".into(), "tag3".into()],
+            nodes: vec![
+                Node::new(NodeKind::Paragraph(Paragraph::new(vec![
+                        Node::new(NodeKind::Text(Text::new("abc"))),
+                ]))),
+            ],
+            ..Default::default()
+        })),
+        case(indoc! {"
+            title
+            #tag1 abc
+        "}, ("", Page {
+            title: Some("title".into()),
+            nodes: vec![
+                Node::new(NodeKind::Paragraph(Paragraph::new(vec![
+                        Node::new(NodeKind::HashTag(HashTag::new("tag1"))),
+                        Node::new(NodeKind::Text(Text::new(" abc"))),
+                ]))),
+            ],
+            ..Default::default()
+        })),
+        case("", ("", Page::default())),
+    )]
+    fn page_with_metadata_valid_test(input: &str, expected: (&str, Page)) {
+        let context = ScrapboxParserContext {
+            config: ScrapboxParserConfig {
+                title: true,
+                tags: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            page(Span::new_extra(input, context)).map(|(input, ret)| (*input, ret)),
+            Ok(expected)
+        );
+    }
+
+    #[test]
+    fn page_borrows_input_test() {
+        let input = "title\nabc [link] #tag\n";
+        let context = ScrapboxParserContext {
+            config: ScrapboxParserConfig {
+                title: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let (_, page) = page(Span::new_extra(input, context)).unwrap();
+        assert!(matches!(page.title, Some(Cow::Borrowed("title"))));
+        let children = match &page.nodes[0].kind {
+            NodeKind::Paragraph(p) => &p.children,
+            kind => panic!("{:?}", kind),
+        };
+        assert!(matches!(
+            &children[0].kind,
+            NodeKind::Text(Text {
+                value: Cow::Borrowed("abc ")
+            })
+        ));
+        assert!(matches!(
+            &children[1].kind,
+            NodeKind::InternalLink(InternalLink {
+                title: Cow::Borrowed("link"),
+                ..
+            })
+        ));
+    }
 }
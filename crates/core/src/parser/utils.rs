@@ -1,10 +1,10 @@
 use nom::{
     branch::alt,
-    bytes::complete::{tag, take, take_until, take_while, take_while1},
+    bytes::complete::{tag, take, take_while, take_while1},
     character::complete::char,
-    combinator::{map, opt, peek},
-    sequence::{delimited, preceded, terminated},
-    Err, Slice,
+    combinator::{map, opt, recognize, verify},
+    sequence::{delimited, pair, preceded, terminated},
+    Err, InputTake, Slice,
 };
 
 use crate::ast::*;
@@ -12,12 +12,62 @@ use crate::ast::*;
 use super::*;
 
 pub fn take_until_eol<X: Clone>(input: Span<X>) -> IResult<Span<X>, X> {
-    alt((take_until("\n"), take(input.chars().count())))(input)
+    let len = input.find('\n').unwrap_or(input.len());
+    Ok(input.take_split(len))
 }
 
 // [abc]
 pub fn brackets<X: Clone>(input: Span<X>) -> IResult<Span<X>, X> {
-    delimited(char('['), take_while(|c| c != ']'), char(']'))(input)
+    delimited(char('['), take_while(|c| c != ']' && c != '\n'), char(']'))(input)
+}
+
+/// The last "]" of the line being parsed, which a context of a parser keeps.
+///
+/// The parsers of a node in brackets search "]" until the end of the line.
+/// They fail fast at "[" which isn't closed, or a line of "[" would be quadratic.
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct LineBrackets {
+    /// the offset of the start of the line
+    start: usize,
+    /// the offset after the end of the line, or 0 before the first line
+    end: usize,
+    /// the offset of the last "]" in the line, or 0 if there is none
+    last: usize,
+}
+
+impl LineBrackets {
+    /// true if "]" follows `offset` in the line. `input` is the rest from `offset`.
+    /// The line is searched once, and again when `offset` is out of the line.
+    fn follows(&mut self, offset: usize, input: &str) -> bool {
+        if !(self.start..self.end).contains(&offset) {
+            let len = input.find('\n').unwrap_or(input.len());
+            self.start = offset;
+            self.end = offset + len + 1;
+            self.last = input[..len].rfind(']').map_or(0, |i| offset + i);
+        }
+        self.last > offset
+    }
+}
+
+/// true if "]" follows `input` in the line. See `LineBrackets`.
+pub fn bracket_closed<X: AsMut<LineBrackets>>(input: &mut Span<X>) -> bool {
+    let offset = input.location_offset();
+    let fragment = *input.fragment();
+    input.extra.as_mut().follows(offset, fragment)
+}
+
+/// "[" which isn't closed in the line, or "#" and "!" before it, is a text.
+/// Otherwise it returns `None` and `input` to try the other parsers.
+pub fn unclosed_bracket<X: Clone + AsMut<LineBrackets>>(
+    mut input: Span<X>,
+) -> IResult<Option<Text>, X> {
+    let open =
+        input.starts_with('[') || (input.starts_with(['#', '!']) && input[1..].starts_with('['));
+    if !open || bracket_closed(&mut input) {
+        return Ok((input, None));
+    }
+    let (input, text) = take(1usize)(input)?;
+    Ok((input, Some(Text::new(*text))))
 }
 
 // (abc)
@@ -25,108 +75,101 @@ pub fn parentheses<X: Clone>(input: Span<X>) -> IResult<Span<X>, X> {
     delimited(char('('), take_while(|c| c != ')'), char(')'))(input)
 }
 
-pub fn url<X: Clone>(input: Span<X>) -> IResult<String, X> {
-    let (url, protocol) = alt((tag("https://"), tag("http://")))(input)?;
-
+pub fn url<'a, X: Clone>(input: Span<'a, X>) -> IResult<'a, &'a str, X> {
     fn is_token(c: char) -> bool {
-        match c as u8 {
-            33..=126 => true,
-            _ => false,
-        }
+        matches!(c as u8, 33..=126)
     }
 
-    let (rest, url) = take_while(|c| is_token(c))(url)?;
+    let (rest, url) = recognize(pair(
+        alt((tag("https://"), tag("http://"))),
+        take_while(is_token),
+    ))(input)?;
 
-    Ok((rest, format!("{}{}", protocol, url)))
+    Ok((rest, *url.fragment()))
 }
 
 // https://www.rust-lang.org/
 pub fn external_link_plain<X: Clone>(input: Span<X>) -> IResult<ExternalLink, X> {
-    map(url, |s| ExternalLink::new(None, &s))(input)
+    map(url, ExternalLink::plain)(input)
 }
 
 /// #tag
+/// #[multi word tag]
 pub fn hashtag<X: Clone>(input: Span<X>) -> IResult<HashTag, X> {
-    let terminators = vec![" ", "　", "\n"];
-
-    // TODO(tkat0): "#[tag]" -> Error
-    //  it should be handled with text + internal link
-
-    map(
-        preceded(
-            tag("#"),
-            take_while(move |c: char| !terminators.contains(&c.to_string().as_str())),
+    let terminators = [" ", "　", "\n"];
+
+    alt((
+        map(
+            delimited(
+                tag("#["),
+                take_while1(|c: char| c != ']' && c != '\n'),
+                char(']'),
+            ),
+            |s: Span<X>| HashTag::bracketed(*s),
+        ),
+        map(
+            preceded(
+                tag("#"),
+                verify(
+                    take_while1(move |c: char| !terminators.contains(&c.to_string().as_str())),
+                    // "#[tag" is not a hashtag
+                    |s: &Span<X>| !s.starts_with('['),
+                ),
+            ),
+            |s: Span<X>| HashTag::new(*s),
         ),
-        |s: Span<X>| HashTag::new(*s),
-    )(input)
+    ))(input)
 }
 
-pub fn text<X: Clone + Copy>(input: Span<X>) -> IResult<Text, X> {
+pub fn text<X: Clone>(input: Span<X>) -> IResult<Text, X> {
     if input.is_empty() {
         return Err(Err::Error(ParseError::new(input, "".into())));
     }
 
-    if input.starts_with("#") {
-        return Err(Err::Error(ParseError::new(input, "".into())));
-    }
+    // "#" which is not a hashtag like "# abc" is a text
+    let start = if input.starts_with('#') {
+        if hashtag(input.clone()).is_ok() {
+            return Err(Err::Error(ParseError::new(input, "".into())));
+        }
+        1
+    } else {
+        0
+    };
 
-    if let (rest, Some(value)) = opt(tag("["))(input)? {
+    if let (rest, Some(value)) = opt(tag("["))(input.clone())? {
         return Ok((rest, Text::new(*value)));
     }
 
+    // scan once until the next char which may start another node
     // "abc #tag" -> ("#tag", "abc ")
-    fn take_until_tag<X: Clone>(input: Span<X>) -> IResult<Span<X>, X> {
-        // " #tag" -> ("#tag", " ")
-        // allow "abc#tag"
-        let (input, _) = peek(take_until(" #"))(input)?;
-        take_until("#")(input)
-    }
-
-    fn take_until_bracket<X: Clone>(input: Span<X>) -> IResult<Span<X>, X> {
-        take_while(|c| c != '[')(input)
-    }
-
-    // shortest match to avoid overeating
-    // TODO(tkat0): refactor
-    let ret = vec![
-        peek(take_until_tag)(input),
-        peek(take_until_bracket)(input),
-        peek(take_until_eol)(input),
-        peek(take_until("`"))(input),
-    ];
-
-    let ret = ret
-        .iter()
-        .filter(|r| r.is_ok())
-        .filter_map(|x| x.as_ref().ok())
-        .min_by(|(_, a), (_, b)| a.len().cmp(&b.len()));
-
-    match ret {
-        Some(&(input, consumed)) => {
-            if consumed.is_empty() {
-                return Err(Err::Error(ParseError::new(input, "".into())));
-            }
-            let input = input.slice(consumed.len()..);
-            let text = Text {
-                value: consumed.to_string(),
-            };
-            return Ok((input, text));
-        }
-        None => {
-            return Err(Err::Error(ParseError::new(input, "".into())));
-        }
+    // allow "abc#tag"
+    let mut prev = None;
+    let len = input
+        .char_indices()
+        .skip(start)
+        .find(|&(_, c)| {
+            let found = matches!(c, '[' | '\n' | '`') || (c == '#' && prev == Some(' '));
+            prev = Some(c);
+            found
+        })
+        .map_or(input.len(), |(i, _)| i);
+
+    if len == 0 {
+        return Err(Err::Error(ParseError::new(input, "".into())));
     }
+    let text = Text::new(*input.slice(..len).fragment());
+    Ok((input.slice(len..), text))
 }
 
 /// `block_quate`
 pub fn block_quate<X: Clone>(input: Span<X>) -> IResult<BlockQuate, X> {
     let (input, _) = tag("`")(input)?;
 
-    // take until ` except \`
+    // take until ` except \`, in the same line
     let mut count: usize = 0;
     let mut is_esc = false;
     for c in input.chars() {
-        if !is_esc && c == '`' {
+        if (!is_esc && c == '`') || c == '\n' {
             break;
         }
         count += 1;
@@ -179,12 +222,19 @@ mod test {
         );
     }
 
+    #[rstest(input, case("[abc"), case("[abc\ndef]"))]
+    fn brackets_invalid_test(input: &str) {
+        if let Ok(ok) = brackets(Span::new(input)) {
+            panic!("{:?}", ok)
+        }
+    }
+
     #[rstest(input, expected,
-        case("http://www.rust-lang.org", ("", "http://www.rust-lang.org".into())),
-        case("https://www.rust-lang.org", ("", "https://www.rust-lang.org".into())),
-        case("https://www.rust-lang.org abc", (" abc", "https://www.rust-lang.org".into())),
+        case("http://www.rust-lang.org", ("", "http://www.rust-lang.org")),
+        case("https://www.rust-lang.org", ("", "https://www.rust-lang.org")),
+        case("https://www.rust-lang.org abc", (" abc", "https://www.rust-lang.org")),
     )]
-    fn url_valid_test(input: &str, expected: (&str, String)) {
+    fn url_valid_test(input: &str, expected: (&str, &str)) {
         assert_eq!(
             url(Span::new(input)).map(|(input, ret)| (*input, ret)),
             Ok(expected)
@@ -192,7 +242,7 @@ mod test {
     }
 
     #[rstest(input, expected,
-        case("https://www.rust-lang.org/ abc", (" abc", ExternalLink::new(None, "https://www.rust-lang.org/"))),
+        case("https://www.rust-lang.org/ abc", (" abc", ExternalLink::plain("https://www.rust-lang.org/"))),
     )]
     fn external_link_plain_valid_test(input: &str, expected: (&str, ExternalLink)) {
         assert_eq!(
@@ -207,7 +257,8 @@ mod test {
         case("#tag ", (" ", HashTag::new("tag"))),
         case("#tag　", ("　", HashTag::new("tag"))),
         case("####tag", ("", HashTag::new("###tag"))),
-        case("#[tag", ("", HashTag::new("[tag"))),
+        case("#[tag]", ("", HashTag::bracketed("tag"))),
+        case("#[multi word tag] abc", (" abc", HashTag::bracketed("multi word tag"))),
     )]
     fn hashtag_valid_test(input: &str, expected: (&str, HashTag)) {
         assert_eq!(
@@ -216,12 +267,12 @@ mod test {
         );
     }
 
-    // #[rstest(input, case("#[tag]"), case("# tag"))]
-    // fn hashtag_invalid_test(input: &str) {
-    //     if let Ok(ok) = hashtag(Span::new(input)) {
-    //         panic!("{:?}", ok)
-    //     }
-    // }
+    #[rstest(input, case("#[tag"), case("#[]"), case("# tag"), case("#"))]
+    fn hashtag_invalid_test(input: &str) {
+        if let Ok(ok) = hashtag(Span::new(input)) {
+            panic!("{:?}", ok)
+        }
+    }
 
     #[rstest(input, expected,
         case(" #tag", ("#tag", Text::new(" "))),
@@ -235,6 +286,10 @@ mod test {
         case("abc #tag", ("#tag", Text::new("abc "))),
         case("あいう", ("", Text::new("あいう"))),
         case("[", ("", Text::new("["))),
+        case("#[tag", ("[tag", Text::new("#"))),
+        case("# abc #tag", ("#tag", Text::new("# abc "))),
+        case("abc#def #tag", ("#tag", Text::new("abc#def "))),
+        case("abc#def\nabc #tag", ("\nabc #tag", Text::new("abc#def"))),
     )]
     fn text_valid_test(input: &str, expected: (&str, Text)) {
         assert_eq!(
@@ -243,7 +298,7 @@ mod test {
         );
     }
 
-    #[rstest(input, case(""), case("#tag"))]
+    #[rstest(input, case(""), case("#tag"), case("#[tag]"), case("`"), case("\n"))]
     fn text_invalid_test(input: &str) {
         if let Ok(ok) = text(Span::new(input)) {
             panic!("{:?}", ok)
@@ -264,7 +319,7 @@ mod test {
         );
     }
 
-    #[rstest(input, case("123abc"), case("`123abc"))]
+    #[rstest(input, case("123abc"), case("`123abc"), case("`123\nabc`"))]
     fn block_quate_invalid_test(input: &str) {
         if let Ok(ok) = block_quate(Span::new(input)) {
             panic!("{:?}", ok)
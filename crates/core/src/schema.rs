@@ -0,0 +1,215 @@
+use std::fmt;
+
+use schemars::{schema_for, JsonSchema};
+use serde::{Deserialize, Serialize};
+
+use crate::ast::Page;
+
+/// The version of the serialized AST.
+/// Bump it when a change of `ast.rs` breaks the documents written so far.
+pub const AST_VERSION: u32 = 1;
+
+/// The serialized form of `Page` with the version of the format,
+/// like `{"version": 1, "page": {"title": "title", "nodes": []}}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct VersionedPage<'a> {
+    pub version: u32,
+    pub page: Page<'a>,
+}
+
+/// How to write `VersionedPage`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum AstFormat {
+    Json,
+    PrettyJson,
+    #[default]
+    Yaml,
+}
+
+#[derive(Debug)]
+pub enum AstError {
+    Json(serde_json::Error),
+    Yaml(serde_yaml::Error),
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for AstError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AstError::Json(e) => write!(f, "invalid AST: {}", e),
+            AstError::Yaml(e) => write!(f, "invalid AST: {}", e),
+            AstError::UnsupportedVersion(v) => write!(
+                f,
+                "unsupported AST version {} (expected {})",
+                v, AST_VERSION
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AstError {}
+
+impl<'a> VersionedPage<'a> {
+    pub fn new(page: Page<'a>) -> Self {
+        Self {
+            version: AST_VERSION,
+            page,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("AST is always serializable")
+    }
+
+    pub fn to_yaml(&self) -> String {
+        serde_yaml::to_string(self).expect("AST is always serializable")
+    }
+
+    pub fn to_format(&self, format: AstFormat) -> String {
+        match format {
+            AstFormat::Json => serde_json::to_string(self).expect("AST is always serializable"),
+            AstFormat::PrettyJson => self.to_json(),
+            AstFormat::Yaml => self.to_yaml(),
+        }
+    }
+
+    /// Read a document written in JSON or YAML.
+    pub fn parse(input: &str) -> Result<VersionedPage<'static>, AstError> {
+        let versioned: VersionedPage = if input.trim_start().starts_with('{') {
+            serde_json::from_str(input).map_err(AstError::Json)?
+        } else {
+            serde_yaml::from_str(input).map_err(AstError::Yaml)?
+        };
+        if versioned.version != AST_VERSION {
+            return Err(AstError::UnsupportedVersion(versioned.version));
+        }
+        Ok(versioned)
+    }
+}
+
+/// JSON Schema of `VersionedPage`. It is published as `schema/ast.schema.json`.
+pub fn json_schema() -> String {
+    let schema = schema_for!(VersionedPage);
+    serde_json::to_string_pretty(&schema).expect("schema is always serializable")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ast::*;
+    use crate::visitor::markdown_printer::{MarkdownPrinter, MarkdownPrinterConfig};
+    use crate::visitor::org_printer::{OrgPrinter, OrgPrinterConfig};
+
+    fn page() -> Page<'static> {
+        Page {
+            title: Some("title".into()),
+            nodes: vec![Node::new(NodeKind::Paragraph(Paragraph::new(vec![
+                Node::new(NodeKind::Emphasis(Emphasis::bold("bold"))),
+                Node::new(NodeKind::ExternalLink(ExternalLink::plain(
+                    "https://www.rust-lang.org/",
+                ))),
+                Node::new(NodeKind::List(List::new(vec![ListItem::new(
+                    ListKind::Disc,
+                    1,
+                    vec![Node::new(NodeKind::Text(Text::new("abc")))],
+                )]))),
+            ])))],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn roundtrip_test() {
+        let versioned = VersionedPage::new(page());
+        for format in [AstFormat::Json, AstFormat::PrettyJson, AstFormat::Yaml] {
+            assert_eq!(
+                VersionedPage::parse(&versioned.to_format(format)).unwrap(),
+                versioned
+            );
+        }
+        assert!(!versioned.to_format(AstFormat::Json).contains('\n'));
+    }
+
+    #[test]
+    fn parse_handwritten_test() {
+        // `id` and the fields with a default value can be omitted
+        let input = r#"{
+            "version": 1,
+            "page": {
+                "nodes": [
+                    {"kind": {"Paragraph": {"children": [
+                        {"kind": {"Emphasis": {"text": "text", "underline": 1}}},
+                        {"kind": {"ExternalLink": {"title": null, "url": "https://www.rust-lang.org/"}}}
+                    ]}}}
+                ]
+            }
+        }"#;
+        let expected = Page {
+            nodes: vec![Node::new(NodeKind::Paragraph(Paragraph::new(vec![
+                Node::new(NodeKind::Emphasis(Emphasis::underline("text"))),
+                Node::new(NodeKind::ExternalLink(ExternalLink::new(
+                    None,
+                    "https://www.rust-lang.org/",
+                ))),
+            ])))],
+            ..Default::default()
+        };
+        assert_eq!(VersionedPage::parse(input).unwrap().page, expected);
+    }
+
+    #[test]
+    fn print_handwritten_test() {
+        // `level` is 1 or more in a parsed page, but a handwritten one may have 0
+        let input = r#"{
+            "version": 1,
+            "page": {
+                "nodes": [
+                    {"kind": {"List": {"children": [
+                        {"kind": "Disc", "level": 0, "children": [{"kind": {"Text": {"value": "a"}}}]},
+                        {"kind": "Decimal", "level": 0, "children": [{"kind": {"Text": {"value": "b"}}}]}
+                    ]}}}
+                ]
+            }
+        }"#;
+        let mut page = VersionedPage::parse(input).unwrap().page;
+        assert_eq!(
+            MarkdownPrinter::new(MarkdownPrinterConfig::default()).generate(&mut page),
+            "* a\n1. b\n"
+        );
+        assert_eq!(
+            OrgPrinter::new(OrgPrinterConfig::default()).generate(&mut page),
+            "- a\n1. b\n"
+        );
+    }
+
+    #[test]
+    fn parse_invalid_test() {
+        assert!(matches!(
+            VersionedPage::parse("version: 0\npage: {}\n"),
+            Err(AstError::UnsupportedVersion(0))
+        ));
+        assert!(matches!(
+            VersionedPage::parse("{\"page\": {}}"),
+            Err(AstError::Json(_))
+        ));
+        assert!(matches!(
+            VersionedPage::parse("page: []"),
+            Err(AstError::Yaml(_))
+        ));
+    }
+
+    /// Run with `UPDATE_SCHEMA=1` after changing `ast.rs`.
+    #[test]
+    fn json_schema_test() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/schema/ast.schema.json");
+        let schema = json_schema();
+        if std::env::var("UPDATE_SCHEMA").is_ok() {
+            std::fs::write(path, &schema).unwrap();
+        }
+        assert_eq!(
+            std::fs::read_to_string(path).unwrap(),
+            schema,
+            "schema/ast.schema.json is outdated"
+        );
+    }
+}
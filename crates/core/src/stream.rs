@@ -0,0 +1,318 @@
+use std::io::{self, BufRead, Write};
+
+use crate::ast::Page;
+use crate::parser::scrapbox::{self, ScrapboxParserConfig, ScrapboxParserContext};
+use crate::parser::Span;
+use crate::visitor::markdown_printer::{MarkdownPass, MarkdownPrinter};
+use crate::visitor::Visitor;
+
+/// Convert Scrapbox to Markdown line by line, for pages which are too large to keep in memory.
+///
+/// Each top-level block is written as soon as a later line shows that it is complete,
+/// so only the lines of the current block are buffered: one line for a paragraph,
+/// and all the lines of a list or a paragraph with `code:` or `table:`.
+/// The output is the same as `MarkdownPrinter::generate` except for what needs the whole page:
+/// `ScrapboxParserConfig::tags` is ignored, `LocationStyle::FrontMatter` doesn't write
+/// the location to the front matter, and `MarkdownPrinterConfig::toc` is ignored,
+/// so no table of contents is written and a marker line is written as a text.
+pub struct StreamConverter<W: Write> {
+    output: W,
+    context: ScrapboxParserContext,
+    pass: MarkdownPass,
+    printer: MarkdownPrinter,
+    /// The lines which are not written yet
+    buffer: String,
+    /// true if the last line starts with indent
+    indented: bool,
+    /// true after the header is written
+    started: bool,
+    /// true after a parse error. Like `scrapbox::page`, the rest of the input is ignored.
+    failed: bool,
+}
+
+impl<W: Write> StreamConverter<W> {
+    pub fn new(
+        output: W,
+        config: ScrapboxParserConfig,
+        pass: MarkdownPass,
+        printer: MarkdownPrinter,
+    ) -> Self {
+        Self {
+            output,
+            context: ScrapboxParserContext {
+                config,
+                ..Default::default()
+            },
+            pass,
+            printer,
+            buffer: String::new(),
+            indented: true,
+            started: false,
+            failed: false,
+        }
+    }
+
+    /// `line` must be a whole line including the newline, except for the last line of the page.
+    pub fn push_line(&mut self, line: &str) -> io::Result<()> {
+        if self.failed {
+            return Ok(());
+        }
+        if !self.started {
+            self.started = true;
+            if self.context.config.title {
+                if let Ok((_, title)) = scrapbox::title(Span::new_extra(line, self.context.clone()))
+                {
+                    let mut page = Page {
+                        title: Some(title),
+                        ..Default::default()
+                    };
+                    return self.write_header(&mut page);
+                }
+            }
+            self.write_header(&mut Page::default())?;
+        }
+
+        self.buffer.push_str(line);
+        // A line with indent continues a list or a `code:` block,
+        // so a block can end only at a line without indent.
+        // Skip parsing the long block again for each line.
+        let indented = line.starts_with([' ', '\t', '　']);
+        if !(indented && self.indented) {
+            self.write_blocks(false)?;
+        }
+        self.indented = indented;
+        Ok(())
+    }
+
+    /// Write the rest of the page and return the writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        if !self.started {
+            self.started = true;
+            self.write_header(&mut Page::default())?;
+        }
+        if !self.failed {
+            self.write_blocks(true)?;
+        }
+        self.printer.backlinks();
+        self.output
+            .write_all(self.printer.take_document().as_bytes())?;
+        self.output.flush()?;
+        Ok(self.output)
+    }
+
+    fn write_header(&mut self, page: &mut Page<'_>) -> io::Result<()> {
+        self.printer.header(page);
+        self.output
+            .write_all(self.printer.take_document().as_bytes())
+    }
+
+    /// Write the complete blocks at the start of `buffer`.
+    /// A block which reaches the end of `buffer` may continue in the next line unless `eof`.
+    fn write_blocks(&mut self, eof: bool) -> io::Result<()> {
+        let mut offset = 0;
+        while offset < self.buffer.len() {
+            let input = Span::new_extra(&self.buffer[offset..], self.context.clone());
+            let (rest, mut node) = match scrapbox::block(input) {
+                Ok(ok) => ok,
+                Err(_) => {
+                    self.failed = true;
+                    offset = self.buffer.len();
+                    break;
+                }
+            };
+            if rest.is_empty() && !eof {
+                break;
+            }
+            offset += rest.location_offset();
+
+            self.pass.visit_node(&mut node);
+            self.printer.visit_node(&mut node);
+            self.output
+                .write_all(self.printer.take_document().as_bytes())?;
+        }
+
+        self.buffer.drain(..offset);
+        Ok(())
+    }
+}
+
+/// Read Scrapbox from `input` and write Markdown to `output`. See `StreamConverter`.
+pub fn scrapbox_to_markdown<R: BufRead, W: Write>(
+    mut input: R,
+    output: W,
+    config: ScrapboxParserConfig,
+    pass: MarkdownPass,
+    printer: MarkdownPrinter,
+) -> io::Result<W> {
+    let mut converter = StreamConverter::new(output, config, pass, printer);
+    let mut line = String::new();
+    while input.read_line(&mut line)? > 0 {
+        converter.push_line(&line)?;
+        line.clear();
+    }
+    converter.finish()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::visitor::markdown_printer::{MarkdownPrinterConfig, TitleStyle, TocPosition};
+    use indoc::indoc;
+    use proptest::prelude::*;
+    use rstest::rstest;
+
+    fn printer() -> MarkdownPrinter {
+        MarkdownPrinter::new(MarkdownPrinterConfig {
+            front_matter: true,
+            title: TitleStyle::Heading,
+            ..Default::default()
+        })
+    }
+
+    fn convert(input: &str, config: ScrapboxParserConfig) -> String {
+        let output = scrapbox_to_markdown(
+            input.as_bytes(),
+            vec![],
+            config,
+            MarkdownPass::default(),
+            printer(),
+        )
+        .unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    fn generate(input: &str, config: ScrapboxParserConfig) -> String {
+        let context = ScrapboxParserContext {
+            config,
+            ..Default::default()
+        };
+        let (_, mut page) = scrapbox::page(Span::new_extra(input, context)).unwrap();
+        MarkdownPass::default().visit(&mut page);
+        printer().generate(&mut page)
+    }
+
+    #[rstest(input,
+        case(include_str!("../../../src/__test__/help-jp/Scrapboxの使い方_input.txt")),
+        case(include_str!("../../../src/__test__/help-jp/コードブロック_input.txt")),
+        case(""),
+        case("\n"),
+        case("title"),
+        case("title\n abc\n"),
+        case("title\nabc\n\ndef"),
+    )]
+    fn scrapbox_to_markdown_test(input: &str) {
+        for title in [false, true] {
+            let config = ScrapboxParserConfig {
+                title,
+                ..Default::default()
+            };
+            assert_eq!(convert(input, config.clone()), generate(input, config));
+        }
+    }
+
+    #[test]
+    fn push_line_test() {
+        let config = ScrapboxParserConfig {
+            title: true,
+            ..Default::default()
+        };
+        let mut converter =
+            StreamConverter::new(vec![], config, MarkdownPass::default(), printer());
+
+        let lines = [
+            ("title\n", "# title\n"),
+            ("abc\n", ""),
+            ("code:a.js\n", "abc\n"),
+            (" let a;\n", ""),
+            (" let b;\n", ""),
+            ("def\n", ""),
+            (" list\n", "```a.js\nlet a;\nlet b;\n```\ndef\n"),
+            ("  list\n", ""),
+        ];
+        for (line, written) in lines {
+            let len = converter.output.len();
+            converter.push_line(line).unwrap();
+            assert_eq!(String::from_utf8_lossy(&converter.output[len..]), written);
+        }
+        assert_eq!(converter.buffer, " list\n  list\n");
+
+        let output = converter.finish().unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            indoc! {"
+                # title
+                abc
+                ```a.js
+                let a;
+                let b;
+                ```
+                def
+                * list
+                  * list
+            "}
+        );
+    }
+
+    #[rstest(toc,
+        case(TocPosition::Top),
+        case(TocPosition::Marker("{{toc}}".into())),
+    )]
+    fn scrapbox_to_markdown_toc_test(toc: TocPosition) {
+        let input = "title\n{{toc}}\n[** a]\n";
+        let config = ScrapboxParserConfig {
+            title: true,
+            ..Default::default()
+        };
+        let printer = MarkdownPrinter::new(MarkdownPrinterConfig {
+            front_matter: true,
+            title: TitleStyle::Heading,
+            toc: Some(toc),
+            ..Default::default()
+        });
+        let output = scrapbox_to_markdown(
+            input.as_bytes(),
+            vec![],
+            config,
+            MarkdownPass::default(),
+            printer,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "# title\n{{toc}}\n## a\n"
+        );
+    }
+
+    fn line() -> impl Strategy<Value = String> {
+        prop::sample::select(vec![
+            "abc",
+            "[link]",
+            "#tag",
+            "code:a.js",
+            "table:t",
+            "a\tb",
+            "`code`",
+            "[* bold",
+            "1. x",
+            "",
+        ])
+        .prop_map(String::from)
+    }
+
+    fn text() -> impl Strategy<Value = String> {
+        prop::collection::vec((0..3usize, line()), 0..12).prop_map(|lines| {
+            lines
+                .into_iter()
+                .map(|(indent, line)| format!("{}{}\n", " ".repeat(indent), line))
+                .collect()
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn scrapbox_to_markdown_same_as_generate_test(text in text(), title in any::<bool>()) {
+            let config = ScrapboxParserConfig { title, ..Default::default() };
+            prop_assert_eq!(convert(&text, config.clone()), generate(&text, config));
+        }
+    }
+}
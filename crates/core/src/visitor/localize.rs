@@ -0,0 +1,354 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use super::{walk_list, TransformCommand, Visitor};
+use crate::ast::*;
+use crate::link::percent_encode;
+use crate::media::{strip_query, MediaConfig};
+
+const SCRAPBOX_FILES: &str = "https://scrapbox.io/files/";
+
+/// Downloads remote media. `LocalizePass` doesn't know how.
+pub trait Fetcher {
+    /// Save the content of `url` as `path`, which is relative to the output directory.
+    fn fetch(&mut self, url: &str, path: &str) -> io::Result<()>;
+}
+
+/// Copies files from a directory which is filled in advance.
+/// The name of a file in `cache_dir` is the percent-encoded URL.
+pub struct FsCacheFetcher {
+    pub cache_dir: PathBuf,
+    pub output_dir: PathBuf,
+}
+
+impl FsCacheFetcher {
+    pub fn new(cache_dir: impl Into<PathBuf>, output_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            cache_dir: cache_dir.into(),
+            output_dir: output_dir.into(),
+        }
+    }
+
+    pub fn cache_path(&self, url: &str) -> PathBuf {
+        self.cache_dir.join(percent_encode(url))
+    }
+}
+
+impl Fetcher for FsCacheFetcher {
+    fn fetch(&mut self, url: &str, path: &str) -> io::Result<()> {
+        let dest = self.output_dir.join(path);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(self.cache_path(url), dest)?;
+        Ok(())
+    }
+}
+
+/// Rewrites the URLs of remote images and Scrapbox attachments to local paths.
+///
+/// Examples:
+/// - `[https://gyazo.com/<id>]` -> `assets/<id>.png`
+/// - `[https://scrapbox.io/files/<id>.pdf]` -> `assets/<id>.pdf`
+///
+/// If the fetcher fails, the URL is kept as it is.
+pub struct LocalizePass<F: Fetcher> {
+    /// e.g. "assets"
+    pub dir: String,
+    pub media: Arc<MediaConfig>,
+    fetcher: F,
+    /// URL -> local path
+    manifest: BTreeMap<String, String>,
+    /// The local paths in `manifest`
+    paths: HashSet<String>,
+}
+
+impl<F: Fetcher> LocalizePass<F> {
+    pub fn new(dir: &str, fetcher: F) -> Self {
+        Self {
+            dir: dir.into(),
+            media: Arc::default(),
+            fetcher,
+            manifest: BTreeMap::new(),
+            paths: HashSet::new(),
+        }
+    }
+
+    /// URL -> local path of the media fetched so far
+    pub fn manifest(&self) -> &BTreeMap<String, String> {
+        &self.manifest
+    }
+
+    fn localize(&mut self, url: &str) -> Option<String> {
+        if let Some(path) = self.manifest.get(url) {
+            return Some(path.clone());
+        }
+
+        let path = self.unique_path(&file_name(url));
+        match self.fetcher.fetch(url, &path) {
+            Ok(()) => {
+                self.manifest.insert(url.into(), path.clone());
+                self.paths.insert(path.clone());
+                Some(path)
+            }
+            Err(e) => {
+                log::warn!("failed to fetch {}: {}", url, e);
+                None
+            }
+        }
+    }
+
+    /// "a.png" -> "assets/a.png", or "assets/a-1.png" if it is taken by another URL.
+    fn unique_path(&self, file_name: &str) -> String {
+        let (stem, ext) = match file_name.rsplit_once('.') {
+            Some((stem, ext)) => (stem, format!(".{}", ext)),
+            None => (file_name, String::new()),
+        };
+        let mut path = format!("{}/{}", self.dir, file_name);
+        let mut i = 1;
+        while self.paths.contains(&path) {
+            path = format!("{}/{}-{}{}", self.dir, stem, i, ext);
+            i += 1;
+        }
+        path
+    }
+
+    fn is_remote_media(&self, url: &str) -> bool {
+        (url.starts_with("https://") || url.starts_with("http://"))
+            && (self.media.is_image(url) || url.starts_with(SCRAPBOX_FILES))
+    }
+}
+
+/// "https://gyazo.com/<id>" -> "<id>.png"
+/// "https://example.com/a/b.png?w=100" -> "b.png"
+fn file_name(url: &str) -> String {
+    let path = strip_query(url).trim_end_matches('/');
+    let name = path.rsplit('/').next().unwrap_or(path);
+    let name: String = name
+        .chars()
+        .filter(|c| !matches!(c, '\\' | ':' | '*' | '"' | '<' | '>' | '|'))
+        .collect();
+
+    if path.starts_with("https://gyazo.com/") && !name.contains('.') {
+        // Gyazo serves a page without an extension
+        format!("{}.png", name)
+    } else if name.is_empty() || name == "." || name == ".." {
+        // not to write a directory
+        "file".into()
+    } else {
+        name
+    }
+}
+
+impl<F: Fetcher> Visitor for LocalizePass<F> {
+    fn visit_list<'a>(&mut self, value: &mut List<'a>) -> Option<TransformCommand<'a>> {
+        walk_list(self, value)
+    }
+
+    fn visit_image<'a>(&mut self, value: &Image<'a>) -> Option<TransformCommand<'a>> {
+        if !self.is_remote_media(&value.uri) {
+            return None;
+        }
+        let path = self.localize(&value.uri)?;
+        Some(TransformCommand::Replace(NodeKind::Image(Image {
+            uri: path.into(),
+            ..value.clone()
+        })))
+    }
+
+    fn visit_external_link<'a>(
+        &mut self,
+        value: &ExternalLink<'a>,
+    ) -> Option<TransformCommand<'a>> {
+        if !self.is_remote_media(&value.url) {
+            return None;
+        }
+        let path = self.localize(&value.url)?;
+        Some(TransformCommand::Replace(NodeKind::ExternalLink(
+            ExternalLink {
+                url: path.into(),
+                ..value.clone()
+            },
+        )))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+
+    /// Fetches nothing, but records the requests.
+    #[derive(Default)]
+    struct MockFetcher {
+        fetched: Vec<(String, String)>,
+    }
+
+    impl Fetcher for MockFetcher {
+        fn fetch(&mut self, url: &str, path: &str) -> io::Result<()> {
+            if url.contains("404") {
+                return Err(io::Error::new(io::ErrorKind::NotFound, "not found"));
+            }
+            self.fetched.push((url.into(), path.into()));
+            Ok(())
+        }
+    }
+
+    fn paragraph(children: Vec<NodeKind>) -> Node {
+        Node::new(NodeKind::Paragraph(Paragraph::new(
+            children.into_iter().map(Node::new).collect(),
+        )))
+    }
+
+    #[rstest(
+        input,
+        expected,
+        case(
+            "https://gyazo.com/5f93e65a3b979ae5333aca4f32600611",
+            "5f93e65a3b979ae5333aca4f32600611.png"
+        ),
+        case(
+            "https://i.gyazo.com/da78df293f9e83a74b5402411e2f2e01.png",
+            "da78df293f9e83a74b5402411e2f2e01.png"
+        ),
+        case(
+            "https://scrapbox.io/files/62c8e4e2d6a6a1001d8a0f4b.pdf",
+            "62c8e4e2d6a6a1001d8a0f4b.pdf"
+        ),
+        case("https://example.com/a/b.webp?w=100", "b.webp"),
+        case("https://example.com/", "example.com"),
+        case("https://scrapbox.io/files/..", "file"),
+        case("https://scrapbox.io/files/.", "file"),
+        case("https://example.com/a/:", "file")
+    )]
+    fn file_name_test(input: &str, expected: &str) {
+        assert_eq!(file_name(input), expected);
+    }
+
+    #[test]
+    fn localize_test() {
+        let mut pass = LocalizePass::new("assets", MockFetcher::default());
+
+        let mut page = Page {
+            nodes: vec![
+                paragraph(vec![
+                    NodeKind::Image(Image::new(
+                        "https://gyazo.com/5f93e65a3b979ae5333aca4f32600611",
+                    )),
+                    NodeKind::ExternalLink(ExternalLink::new(
+                        Some("slide"),
+                        "https://scrapbox.io/files/62c8e4e2d6a6a1001d8a0f4b.pdf",
+                    )),
+                    NodeKind::ExternalLink(ExternalLink::new(None, "https://www.rust-lang.org/")),
+                    NodeKind::Image(Image::new("assets/local.png")),
+                ]),
+                Node::new(NodeKind::List(List::new(vec![ListItem::new(
+                    ListKind::Disc,
+                    1,
+                    vec![
+                        Node::new(NodeKind::Image(Image::with_link(
+                            "https://gyazo.com/5f93e65a3b979ae5333aca4f32600611",
+                            "https://www.rust-lang.org/",
+                        ))),
+                        Node::new(NodeKind::Image(Image::new(
+                            "https://example.com/5f93e65a3b979ae5333aca4f32600611.png",
+                        ))),
+                        Node::new(NodeKind::Image(Image::new("https://example.com/404.png"))),
+                    ],
+                )]))),
+            ],
+            ..Default::default()
+        };
+
+        pass.visit(&mut page);
+
+        let expected = Page {
+            nodes: vec![
+                paragraph(vec![
+                    NodeKind::Image(Image::new("assets/5f93e65a3b979ae5333aca4f32600611.png")),
+                    NodeKind::ExternalLink(ExternalLink::new(
+                        Some("slide"),
+                        "assets/62c8e4e2d6a6a1001d8a0f4b.pdf",
+                    )),
+                    NodeKind::ExternalLink(ExternalLink::new(None, "https://www.rust-lang.org/")),
+                    NodeKind::Image(Image::new("assets/local.png")),
+                ]),
+                Node::new(NodeKind::List(List::new(vec![ListItem::new(
+                    ListKind::Disc,
+                    1,
+                    vec![
+                        Node::new(NodeKind::Image(Image::with_link(
+                            "assets/5f93e65a3b979ae5333aca4f32600611.png",
+                            "https://www.rust-lang.org/",
+                        ))),
+                        Node::new(NodeKind::Image(Image::new(
+                            "assets/5f93e65a3b979ae5333aca4f32600611-1.png",
+                        ))),
+                        Node::new(NodeKind::Image(Image::new("https://example.com/404.png"))),
+                    ],
+                )]))),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(page, expected);
+        assert_eq!(
+            pass.manifest(),
+            &BTreeMap::from([
+                (
+                    "https://example.com/5f93e65a3b979ae5333aca4f32600611.png".to_string(),
+                    "assets/5f93e65a3b979ae5333aca4f32600611-1.png".to_string()
+                ),
+                (
+                    "https://gyazo.com/5f93e65a3b979ae5333aca4f32600611".to_string(),
+                    "assets/5f93e65a3b979ae5333aca4f32600611.png".to_string()
+                ),
+                (
+                    "https://scrapbox.io/files/62c8e4e2d6a6a1001d8a0f4b.pdf".to_string(),
+                    "assets/62c8e4e2d6a6a1001d8a0f4b.pdf".to_string()
+                ),
+            ])
+        );
+        // the same URL is fetched once
+        assert_eq!(pass.fetcher.fetched.len(), 3);
+    }
+
+    #[test]
+    fn fs_cache_fetcher_test() {
+        let cache = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        let url = "https://i.gyazo.com/da78df293f9e83a74b5402411e2f2e01.png";
+
+        let fetcher = FsCacheFetcher::new(cache.path(), output.path());
+        fs::write(fetcher.cache_path(url), b"png").unwrap();
+
+        let mut pass = LocalizePass::new("assets", fetcher);
+        let mut page = Page {
+            nodes: vec![paragraph(vec![
+                NodeKind::Image(Image::new(url)),
+                NodeKind::Image(Image::new("https://i.gyazo.com/missing.png")),
+            ])],
+            ..Default::default()
+        };
+        pass.visit(&mut page);
+
+        assert_eq!(
+            fs::read(
+                output
+                    .path()
+                    .join("assets/da78df293f9e83a74b5402411e2f2e01.png")
+            )
+            .unwrap(),
+            b"png"
+        );
+        assert_eq!(pass.manifest().len(), 1);
+
+        let mut fetcher = pass.fetcher;
+        assert!(fetcher
+            .fetch("https://i.gyazo.com/missing.png", "a.png")
+            .is_err());
+    }
+}
@@ -1,6 +1,14 @@
-use super::{walk_paragraph, TransformCommand, Visitor};
+use super::{walk_list, walk_paragraph, TransformCommand, Visitor};
 use crate::ast::*;
+use crate::front_matter::FrontMatter;
+use crate::link::{LinkResolver, WikiLinkResolver};
+use crate::media::{image_source, vimeo_id, youtube_id, MediaConfig};
+use crate::outline;
+use crate::parser::markdown::MarkdownFlavor;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
+#[derive(Debug, Copy, Clone)]
 pub struct MarkdownPass {
     // Examples:
     // - `h1_level1` == 3: [*** text] -> `# text`
@@ -23,14 +31,11 @@ impl Default for MarkdownPass {
 }
 
 impl Visitor for MarkdownPass {
-    fn visit_emphasis(&mut self, emphasis: &Emphasis) -> Option<TransformCommand> {
+    fn visit_emphasis<'a>(&mut self, emphasis: &Emphasis<'a>) -> Option<TransformCommand<'a>> {
         let h_level = (self.h1_level + 1).saturating_sub(emphasis.bold);
-        if 0 < h_level
-            && h_level <= self.h1_level
-            && (self.bold_to_h || (!self.bold_to_h && emphasis.bold > 1))
-        {
+        if 0 < h_level && h_level <= self.h1_level && (self.bold_to_h || emphasis.bold > 1) {
             Some(TransformCommand::Replace(NodeKind::Heading(Heading::new(
-                &emphasis.text,
+                emphasis.text.clone(),
                 h_level,
             ))))
         } else {
@@ -38,12 +43,12 @@ impl Visitor for MarkdownPass {
         }
     }
 
-    fn visit_list(&mut self, value: &mut List) -> Option<TransformCommand> {
+    fn visit_list<'a>(&mut self, value: &mut List<'a>) -> Option<TransformCommand<'a>> {
         // NOTE: table or code blcok in a list is not supported. It is flattened.
         let mut new_nodes: Vec<Node> = vec![];
         let mut prev_is_flattened = true;
         for item in value.children.iter() {
-            if let Some(NodeKind::CodeBlock(code_block)) = &item.children.get(0).map(|c| &c.kind) {
+            if let Some(NodeKind::CodeBlock(code_block)) = &item.children.first().map(|c| &c.kind) {
                 new_nodes.push(Node::new(NodeKind::CodeBlock(code_block.clone())));
                 prev_is_flattened = true;
 
@@ -55,7 +60,7 @@ impl Visitor for MarkdownPass {
                         children,
                     )]))))
                 }
-            } else if let Some(NodeKind::Table(table)) = &item.children.get(0).map(|c| &c.kind) {
+            } else if let Some(NodeKind::Table(table)) = &item.children.first().map(|c| &c.kind) {
                 new_nodes.push(Node::new(NodeKind::Table(table.clone())));
                 prev_is_flattened = true;
 
@@ -90,14 +95,121 @@ impl Visitor for MarkdownPass {
     }
 }
 
+/// How to write `Page::title`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TitleStyle {
+    /// `# title` at the top of the document
+    Heading,
+    /// `title: title` in front matter
+    #[default]
+    FrontMatter,
+    /// The title is not written.
+    Hidden,
+}
+
+/// How to write a tag with spaces like `#[multi word tag]`, which Markdown can't have.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TagNormalization {
+    /// `#multi_word_tag`
+    #[default]
+    Underscore,
+    /// `#multi-word-tag`
+    Hyphen,
+    /// `#multi/word/tag` (nested tags of Obsidian)
+    Nested,
+}
+
+impl TagNormalization {
+    pub fn normalize(&self, tag: &str) -> String {
+        let separator = match self {
+            TagNormalization::Underscore => "_",
+            TagNormalization::Hyphen => "-",
+            TagNormalization::Nested => "/",
+        };
+        tag.split(char::is_whitespace)
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>()
+            .join(separator)
+    }
+}
+
+/// How to write `Embed`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum EmbedStyle {
+    /// `![](url)`, which Obsidian shows as a player
+    Embed,
+    /// `<iframe>`, `<video>` or `<audio>`
+    Html,
+    /// The URL as it is
+    #[default]
+    Link,
+}
+
+/// How to write the decorations which Markdown doesn't have, like `[_ underline]`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DecorationFallback {
+    /// `<u>underline</u>`, `<mark>marker</mark>` and so on
+    #[default]
+    Html,
+    /// Only the text
+    Plain,
+}
+
+/// How to write `Location`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum LocationStyle {
+    /// `[label](https://www.openstreetmap.org/?mlat=35.6812&mlon=139.7671#map=14/35.6812/139.7671)`
+    #[default]
+    OpenStreetMap,
+    /// `[label](geo:35.6812,139.7671?z=14)`
+    GeoUri,
+    /// `location: [35.6812, 139.7671]` in front matter for the first location of the page,
+    /// and the label in the body.
+    /// The other locations, or all of them without front matter, are `OpenStreetMap`.
+    FrontMatter,
+}
+
+/// Where to write the table of contents of the headings (see `outline::outline`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TocPosition {
+    /// After the title of the page
+    Top,
+    /// Instead of the first line which is only the text, like `{{toc}}`.
+    /// The table of contents is not written without the line.
+    Marker(String),
+}
+
+#[derive(Clone)]
 pub struct MarkdownPrinterConfig {
     pub indent: String,
+    pub flavor: MarkdownFlavor,
+    /// If true, the metadata of `Page` is written as YAML front matter.
+    pub front_matter: bool,
+    pub title: TitleStyle,
+    pub tag_normalization: TagNormalization,
+    /// If not empty, `## Backlinks` section is appended. (see `LinkGraph::backlinks`)
+    pub backlinks: Vec<String>,
+    pub media: Arc<MediaConfig>,
+    pub embed: EmbedStyle,
+    pub location: LocationStyle,
+    pub decoration: DecorationFallback,
+    pub toc: Option<TocPosition>,
 }
 
 impl Default for MarkdownPrinterConfig {
     fn default() -> Self {
         Self {
             indent: "  ".into(),
+            flavor: MarkdownFlavor::Generic,
+            front_matter: true,
+            title: TitleStyle::FrontMatter,
+            tag_normalization: TagNormalization::Underscore,
+            backlinks: vec![],
+            media: Arc::default(),
+            embed: EmbedStyle::Link,
+            location: LocationStyle::OpenStreetMap,
+            decoration: DecorationFallback::Html,
+            toc: None,
         }
     }
 }
@@ -105,32 +217,175 @@ impl Default for MarkdownPrinterConfig {
 pub struct MarkdownPrinter {
     document: String,
     config: MarkdownPrinterConfig,
+    // The table of contents which is not written yet
+    toc: Option<String>,
+    // The location written in front matter, and not visited yet
+    front_matter_location: Option<(f64, f64)>,
+    link_resolver: Box<dyn LinkResolver>,
 }
 
 impl MarkdownPrinter {
     pub fn new(config: MarkdownPrinterConfig) -> Self {
+        Self::with_link_resolver(config, Box::new(WikiLinkResolver))
+    }
+
+    pub fn with_link_resolver(
+        config: MarkdownPrinterConfig,
+        link_resolver: Box<dyn LinkResolver>,
+    ) -> Self {
         Self {
             document: String::new(),
             config,
+            toc: None,
+            front_matter_location: None,
+            link_resolver,
         }
     }
 
-    pub fn generate(&mut self, page: &mut Page) -> String {
+    /// Titles of the pages which are linked but don't exist.
+    pub fn missing_links(&self) -> Vec<String> {
+        self.link_resolver.missing_links()
+    }
+
+    pub fn generate(&mut self, page: &mut Page<'_>) -> String {
+        self.header(page);
+        if self.config.toc.is_some() {
+            let outline = outline::outline("", page);
+            self.toc = Some(outline::to_markdown(&outline, &self.config.indent));
+        }
+        if self.config.toc == Some(TocPosition::Top) {
+            if let Some(toc) = self.toc.take() {
+                self.document.push_str(&toc);
+                self.document.push('\n');
+            }
+        }
         self.visit(page);
+        self.backlinks();
         self.document.clone()
     }
+
+    /// Front matter and the title of the page
+    pub(crate) fn header(&mut self, page: &mut Page<'_>) {
+        if self.config.front_matter {
+            let mut front_matter = FrontMatter::from_page(page);
+            if self.config.title != TitleStyle::FrontMatter {
+                front_matter.title = None;
+            }
+            front_matter.tags = front_matter
+                .tags
+                .iter()
+                .map(|tag| self.config.tag_normalization.normalize(tag))
+                .collect();
+            if self.config.location == LocationStyle::FrontMatter {
+                let mut finder = LocationFinder::default();
+                finder.visit(page);
+                self.front_matter_location = finder.location;
+                if let Some((latitude, longitude)) = finder.location {
+                    front_matter.extra.insert(
+                        "location".into(),
+                        serde_yaml::Value::Sequence(vec![latitude.into(), longitude.into()]),
+                    );
+                }
+            }
+            if !front_matter.is_empty() {
+                self.document.push_str(&front_matter.to_yaml());
+            }
+        }
+        if let (TitleStyle::Heading, Some(title)) = (self.config.title, &page.title) {
+            self.document.push_str(&format!("# {}\n", title));
+        }
+    }
+
+    /// Markdown written since the last call
+    pub(crate) fn take_document(&mut self) -> String {
+        std::mem::take(&mut self.document)
+    }
+
+    pub(crate) fn backlinks(&mut self) {
+        if self.config.backlinks.is_empty() {
+            return;
+        }
+        self.document.push_str("\n## Backlinks\n\n");
+        for title in self.config.backlinks.clone() {
+            self.document.push_str("* ");
+            self.visit_internal_link(&InternalLink::new(&title));
+            self.document.push('\n');
+        }
+    }
+
+    fn is_obsidian(&self) -> bool {
+        self.config.flavor == MarkdownFlavor::Obsidian
+    }
+
+    fn is_toc_marker(&self, value: &Paragraph<'_>) -> bool {
+        let marker = match &self.config.toc {
+            Some(TocPosition::Marker(marker)) => marker,
+            _ => return false,
+        };
+        let mut text = String::new();
+        for node in value.children.iter() {
+            match &node.kind {
+                NodeKind::Text(t) => text.push_str(&t.value),
+                _ => return false,
+            }
+        }
+        text.trim() == marker
+    }
+}
+
+/// Finds the latitude and longitude of the first `Location` of a page.
+#[derive(Default)]
+struct LocationFinder {
+    location: Option<(f64, f64)>,
+}
+
+impl Visitor for LocationFinder {
+    fn is_finish(&mut self) -> bool {
+        self.location.is_some()
+    }
+
+    fn visit_list<'a>(&mut self, value: &mut List<'a>) -> Option<TransformCommand<'a>> {
+        walk_list(self, value)
+    }
+
+    fn visit_location<'a>(&mut self, value: &Location<'a>) -> Option<TransformCommand<'a>> {
+        self.location = Some((value.latitude, value.longitude));
+        None
+    }
+}
+
+/// A URL can have `"` and so on, which ends an attribute of HTML.
+fn escape_html(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
 }
 
 impl Visitor for MarkdownPrinter {
-    fn visit_paragraph(&mut self, value: &mut Paragraph) -> Option<TransformCommand> {
+    fn visit_paragraph<'a>(&mut self, value: &mut Paragraph<'a>) -> Option<TransformCommand<'a>> {
+        if self.toc.is_some() && self.is_toc_marker(value) {
+            if let Some(toc) = self.toc.take() {
+                self.document.push_str(&toc);
+            }
+            return None;
+        }
         walk_paragraph(self, value);
-        self.document.push_str("\n");
+        self.document.push('\n');
         None
     }
 
-    fn visit_list(&mut self, value: &mut List) -> Option<TransformCommand> {
+    fn visit_list<'a>(&mut self, value: &mut List<'a>) -> Option<TransformCommand<'a>> {
         for item in value.children.iter_mut() {
-            let indent = self.config.indent.repeat(item.level - 1);
+            let indent = self.config.indent.repeat(item.level.saturating_sub(1));
             match &item.kind {
                 ListKind::Disc => self.document.push_str(&format!("{}* ", indent)),
                 ListKind::Decimal => self.document.push_str(&format!("{}1. ", indent)),
@@ -139,38 +394,64 @@ impl Visitor for MarkdownPrinter {
             for node in item.children.iter_mut() {
                 self.visit_node(node);
             }
-            self.document.push_str("\n");
+            self.document.push('\n');
         }
         None
     }
 
-    fn visit_hashtag(&mut self, value: &HashTag) -> Option<TransformCommand> {
-        self.document.push_str(&format!("#{}", value.value));
+    fn visit_hashtag<'a>(&mut self, value: &HashTag<'a>) -> Option<TransformCommand<'a>> {
+        let tag = self.config.tag_normalization.normalize(&value.value);
+        self.document.push_str(&format!("#{}", tag));
         None
     }
 
-    fn visit_internal_link(&mut self, value: &InternalLink) -> Option<TransformCommand> {
-        self.document.push_str(&format!("[[{}]]", value.title));
+    fn visit_internal_link<'a>(
+        &mut self,
+        value: &InternalLink<'a>,
+    ) -> Option<TransformCommand<'a>> {
+        if let Some(path) = self.link_resolver.resolve(value) {
+            // "![](page.md)" would be an image, so an embedded page is a link
+            let text = match (&value.label, &value.anchor) {
+                (Some(label), _) => label.clone(),
+                (None, Some(anchor)) if value.title.is_empty() => anchor.clone(),
+                _ => value.title.clone(),
+            };
+            self.document.push_str(&format!("[{}]({})", text, path));
+            return None;
+        }
+        if value.embed {
+            self.document.push('!');
+        }
+        self.document.push_str(&format!("[[{}", value.title));
+        if let Some(anchor) = &value.anchor {
+            self.document.push_str(&format!("#{}", anchor));
+        }
+        if let Some(label) = &value.label {
+            self.document.push_str(&format!("|{}", label));
+        }
+        self.document.push_str("]]");
         None
     }
 
-    fn visit_external_link(&mut self, value: &ExternalLink) -> Option<TransformCommand> {
+    fn visit_external_link<'a>(
+        &mut self,
+        value: &ExternalLink<'a>,
+    ) -> Option<TransformCommand<'a>> {
         if let Some(title) = &value.title {
             self.document
                 .push_str(&format!("[{}]({})", title, value.url));
         } else {
-            if value.url.starts_with("https://gyazo.com/") {
-                self.document
-                    .push_str(&format!("![]({}/max_size/400)", value.url));
+            if self.config.media.is_image(&value.url) {
+                self.visit_image(&Image::new(value.url.as_ref()));
             } else {
-                self.document.push_str(&format!("{}", value.url));
+                self.document.push_str(&value.url);
             }
         }
         None
     }
 
-    fn visit_emphasis(&mut self, value: &Emphasis) -> Option<TransformCommand> {
-        let mut tmp = value.text.clone();
+    fn visit_emphasis<'a>(&mut self, value: &Emphasis<'a>) -> Option<TransformCommand<'a>> {
+        let mut tmp = value.text.to_string();
         if value.bold > 0 {
             tmp = format!("**{}**", tmp);
         }
@@ -180,25 +461,55 @@ impl Visitor for MarkdownPrinter {
         if value.strikethrough > 0 {
             tmp = format!("~~{}~~", tmp);
         }
+        if value.highlight > 0 && self.is_obsidian() {
+            tmp = format!("=={}==", tmp);
+        }
+
+        if self.config.decoration == DecorationFallback::Html {
+            if value.highlight > 0 && !self.is_obsidian() {
+                tmp = format!("<mark>{}</mark>", tmp);
+            }
+            if value.underline > 0 {
+                tmp = format!("<u>{}</u>", tmp);
+            }
+            if value.emphasized > 0 {
+                tmp = format!("<em>{}</em>", tmp);
+            }
+            if value.wavy > 0 {
+                tmp = format!(
+                    r#"<span style="text-decoration: underline wavy">{}</span>"#,
+                    tmp
+                );
+            }
+            if value.percent > 0 {
+                tmp = format!("<s>{}</s>", tmp);
+            }
+            if !value.custom.is_empty() {
+                // same as the class names of Scrapbox
+                let classes: Vec<String> = value
+                    .custom
+                    .chars()
+                    .map(|c| format!("deco-{}", escape_html(&c.to_string())))
+                    .collect();
+                tmp = format!(r#"<span class="{}">{}</span>"#, classes.join(" "), tmp);
+            }
+        }
         self.document.push_str(&tmp);
         None
     }
 
-    fn visit_heading(&mut self, value: &Heading) -> Option<TransformCommand> {
-        self.document.push_str(&format!(
-            "{} {}",
-            "#".repeat(value.level as usize),
-            value.text
-        ));
+    fn visit_heading<'a>(&mut self, value: &Heading<'a>) -> Option<TransformCommand<'a>> {
+        self.document
+            .push_str(&format!("{} {}", "#".repeat(value.level), value.text));
         None
     }
 
-    fn visit_block_quate(&mut self, value: &BlockQuate) -> Option<TransformCommand> {
+    fn visit_block_quate<'a>(&mut self, value: &BlockQuate<'a>) -> Option<TransformCommand<'a>> {
         self.document.push_str(&format!("`{}`", value.value));
         None
     }
 
-    fn visit_code_block(&mut self, value: &CodeBlock) -> Option<TransformCommand> {
+    fn visit_code_block<'a>(&mut self, value: &CodeBlock<'a>) -> Option<TransformCommand<'a>> {
         self.document.push_str(&format!("```{}\n", value.file_name));
         for code in &value.children {
             self.document.push_str(&format!("{}\n", code));
@@ -207,44 +518,126 @@ impl Visitor for MarkdownPrinter {
         None
     }
 
-    fn visit_table(&mut self, value: &Table) -> Option<TransformCommand> {
+    fn visit_table<'a>(&mut self, value: &Table<'a>) -> Option<TransformCommand<'a>> {
         if value.header.is_empty() {
             return None;
         }
 
         self.document
             .push_str(&format!("| {} |", value.header.join(" | ")));
-        self.document.push_str("\n");
+        self.document.push('\n');
 
-        let sep = vec!["---"];
+        let sep = ["---"];
         self.document.push_str(&format!(
             "| {} |",
             sep.repeat(value.header.len()).join(" | ")
         ));
 
-        self.document.push_str("\n");
+        self.document.push('\n');
         for row in &value.rows {
             if row.is_empty() {
                 break;
             }
             self.document.push_str(&format!("| {} |", row.join(" | ")));
-            self.document.push_str("\n");
+            self.document.push('\n');
         }
         None
     }
 
-    fn visit_image(&mut self, value: &Image) -> Option<TransformCommand> {
-        self.document.push_str(&format!("![]({})", value.uri));
+    fn visit_image<'a>(&mut self, value: &Image<'a>) -> Option<TransformCommand<'a>> {
+        let is_local = !value.uri.contains("://");
+        let image = if self.is_obsidian() && is_local {
+            // e.g. localized by `LocalizePass`
+            format!("![[{}]]", value.uri)
+        } else {
+            format!("![]({})", image_source(&value.uri))
+        };
+        match &value.link {
+            Some(link) => self.document.push_str(&format!("[{}]({})", image, link)),
+            None => self.document.push_str(&image),
+        }
         None
     }
 
-    fn visit_math(&mut self, value: &Math) -> Option<TransformCommand> {
+    fn visit_embed<'a>(&mut self, value: &Embed<'a>) -> Option<TransformCommand<'a>> {
+        let url = &value.url;
+        let embed = match (self.config.embed, value.provider) {
+            (EmbedStyle::Link, _) => url.to_string(),
+            (EmbedStyle::Embed, _) => format!("![]({})", url),
+            (EmbedStyle::Html, EmbedProvider::YouTube) => format!(
+                r#"<iframe src="https://www.youtube.com/embed/{}" allowfullscreen></iframe>"#,
+                escape_html(youtube_id(url).unwrap_or_default())
+            ),
+            (EmbedStyle::Html, EmbedProvider::Vimeo) => format!(
+                r#"<iframe src="https://player.vimeo.com/video/{}" allowfullscreen></iframe>"#,
+                escape_html(vimeo_id(url).unwrap_or_default())
+            ),
+            (EmbedStyle::Html, EmbedProvider::Twitter) => format!(
+                r#"<blockquote class="twitter-tweet"><a href="{}"></a></blockquote>"#,
+                escape_html(url)
+            ),
+            (EmbedStyle::Html, EmbedProvider::Video) => {
+                format!(r#"<video src="{}" controls></video>"#, escape_html(url))
+            }
+            (EmbedStyle::Html, EmbedProvider::Audio) => {
+                format!(r#"<audio src="{}" controls></audio>"#, escape_html(url))
+            }
+        };
+        self.document.push_str(&embed);
+        None
+    }
+
+    fn visit_location<'a>(&mut self, value: &Location<'a>) -> Option<TransformCommand<'a>> {
+        let (lat, lon, zoom) = (value.latitude, value.longitude, value.zoom);
+        let label = value
+            .label
+            .as_deref()
+            .map_or_else(|| format!("{},{}", lat, lon), str::to_string);
+        let in_front_matter = self.front_matter_location == Some((lat, lon));
+        let location = match self.config.location {
+            LocationStyle::FrontMatter if in_front_matter => {
+                self.front_matter_location = None;
+                label
+            }
+            LocationStyle::OpenStreetMap | LocationStyle::FrontMatter => format!(
+                "[{}](https://www.openstreetmap.org/?mlat={}&mlon={}#map={}/{}/{})",
+                label, lat, lon, zoom, lat, lon
+            ),
+            LocationStyle::GeoUri => format!("[{}](geo:{},{}?z={})", label, lat, lon, zoom),
+        };
+        self.document.push_str(&location);
+        None
+    }
+
+    fn visit_math<'a>(&mut self, value: &Math<'a>) -> Option<TransformCommand<'a>> {
         self.document.push_str(&format!("$${}$$", value.value));
         None
     }
 
-    fn visit_text(&mut self, value: &Text) -> Option<TransformCommand> {
-        self.document.push_str(&format!("{}", value.value));
+    fn visit_callout<'a>(&mut self, value: &mut Callout<'a>) -> Option<TransformCommand<'a>> {
+        self.document.push_str(&format!("> [!{}]", value.kind));
+        if let Some(title) = &value.title {
+            self.document.push_str(&format!(" {}", title));
+        }
+        self.document.push('\n');
+        for node in value.children.iter_mut() {
+            self.document.push_str("> ");
+            self.visit_node(node);
+        }
+        None
+    }
+
+    fn visit_comment<'a>(&mut self, value: &Comment<'a>) -> Option<TransformCommand<'a>> {
+        if self.is_obsidian() {
+            self.document.push_str(&format!("%%{}%%", value.value));
+        } else {
+            self.document.push_str(&format!("<!--{}-->", value.value));
+        }
+        None
+    }
+
+    fn visit_text<'a>(&mut self, value: &Text<'a>) -> Option<TransformCommand<'a>> {
+        self.document.push_str(&value.value);
         None
     }
 }
@@ -252,14 +645,16 @@ impl Visitor for MarkdownPrinter {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::link::PathLinkResolver;
     use indoc::indoc;
+    use rstest::rstest;
 
     #[test]
     fn pass_test() {
         let mut pass = MarkdownPass::default();
 
         assert_eq!(
-            pass.visit_emphasis(&mut Emphasis::bold_level("text", 3)),
+            pass.visit_emphasis(&Emphasis::bold_level("text", 3)),
             Some(TransformCommand::Replace(NodeKind::Heading(Heading::new(
                 "text", 1
             ))))
@@ -275,7 +670,7 @@ mod test {
 
         // TODO(tkat0): not supoprted: `[*-/ mix]` -> `### *~~mix~~*` (but `### mix`)
         assert_eq!(
-            pass.visit_emphasis(&mut Emphasis::bold_level("text", 1)),
+            pass.visit_emphasis(&Emphasis::bold_level("text", 1)),
             Some(TransformCommand::Replace(NodeKind::Heading(Heading::new(
                 "text", 3
             ))))
@@ -367,6 +762,7 @@ mod test {
                     )),
                 )]))),
             ],
+            ..Default::default()
         };
 
         let markdown = visitor.generate(&mut page);
@@ -396,4 +792,454 @@ mod test {
 
         assert_eq!(markdown, expected)
     }
+
+    #[test]
+    fn codegen_obsidian_test() {
+        let mut visitor = MarkdownPrinter::new(MarkdownPrinterConfig {
+            flavor: MarkdownFlavor::Obsidian,
+            ..Default::default()
+        });
+
+        let mut page = Page {
+            tags: vec!["tag1".into(), "tag2".into()],
+            nodes: vec![
+                Node::new(NodeKind::Paragraph(Paragraph::new(vec![
+                    Node::new(NodeKind::InternalLink(InternalLink {
+                        title: "page".into(),
+                        anchor: Some("heading".into()),
+                        label: Some("label".into()),
+                        embed: false,
+                    })),
+                    Node::new(NodeKind::Text(Text::new(" "))),
+                    Node::new(NodeKind::InternalLink(InternalLink::embed("page"))),
+                    Node::new(NodeKind::Text(Text::new(" "))),
+                    Node::new(NodeKind::Emphasis(Emphasis::highlight("highlight"))),
+                    Node::new(NodeKind::Comment(Comment::new("comment"))),
+                ]))),
+                Node::new(NodeKind::Callout(Callout::new(
+                    "info",
+                    Some("title"),
+                    vec![Node::new(NodeKind::Paragraph(Paragraph::new(vec![
+                        Node::new(NodeKind::Text(Text::new("body"))),
+                    ])))],
+                ))),
+            ],
+            ..Default::default()
+        };
+
+        let markdown = visitor.generate(&mut page);
+
+        let expected = indoc! {r#"
+            ---
+            tags:
+            - tag1
+            - tag2
+            ---
+            [[page#heading|label]] ![[page]] ==highlight==%%comment%%
+            > [!info] title
+            > body
+        "#};
+
+        assert_eq!(markdown, expected)
+    }
+
+    #[test]
+    fn codegen_generic_obsidian_syntax_test() {
+        let mut visitor = MarkdownPrinter::new(MarkdownPrinterConfig::default());
+
+        let mut page = Page {
+            nodes: vec![Node::new(NodeKind::Paragraph(Paragraph::new(vec![
+                Node::new(NodeKind::Emphasis(Emphasis::highlight("highlight"))),
+                Node::new(NodeKind::Comment(Comment::new("comment"))),
+            ])))],
+            ..Default::default()
+        };
+
+        let markdown = visitor.generate(&mut page);
+
+        assert_eq!(markdown, "<mark>highlight</mark><!--comment-->\n")
+    }
+
+    #[rstest(
+        input,
+        decoration,
+        expected,
+        case(Emphasis::underline("text"), DecorationFallback::Html, "<u>text</u>"),
+        case(Emphasis::underline("text"), DecorationFallback::Plain, "text"),
+        case(Emphasis { underline: 1, ..Emphasis::bold("text") }, DecorationFallback::Html, "<u>**text**</u>"),
+        case(Emphasis { underline: 1, ..Emphasis::bold("text") }, DecorationFallback::Plain, "**text**"),
+        case(Emphasis::highlight("text"), DecorationFallback::Plain, "text"),
+        case(Emphasis { emphasized: 1, ..Default::default() }, DecorationFallback::Html, "<em></em>"),
+        case(Emphasis { wavy: 1, ..Emphasis::underline("text") }, DecorationFallback::Html, r#"<span style="text-decoration: underline wavy"><u>text</u></span>"#),
+        case(Emphasis { percent: 1, ..Emphasis::italic("text") }, DecorationFallback::Html, "<s>*text*</s>"),
+        case(Emphasis { custom: "{<".into(), ..Emphasis::italic("text") }, DecorationFallback::Html, r#"<span class="deco-{ deco-&lt;">*text*</span>"#),
+        case(Emphasis { custom: "{<".into(), ..Emphasis::italic("text") }, DecorationFallback::Plain, "*text*")
+    )]
+    fn codegen_decoration_test(input: Emphasis, decoration: DecorationFallback, expected: &str) {
+        let mut visitor = MarkdownPrinter::new(MarkdownPrinterConfig {
+            decoration,
+            front_matter: false,
+            ..Default::default()
+        });
+        let mut page = Page {
+            nodes: vec![Node::new(NodeKind::Paragraph(Paragraph::new(vec![
+                Node::new(NodeKind::Emphasis(input)),
+            ])))],
+            ..Default::default()
+        };
+        assert_eq!(visitor.generate(&mut page), format!("{}\n", expected));
+    }
+
+    #[test]
+    fn codegen_front_matter_test() {
+        let mut page = Page {
+            title: Some("title".into()),
+            tags: vec!["tag".into()],
+            nodes: vec![Node::new(NodeKind::Paragraph(Paragraph::new(vec![
+                Node::new(NodeKind::Text(Text::new("abc"))),
+            ])))],
+            ..Default::default()
+        };
+
+        let mut visitor = MarkdownPrinter::new(MarkdownPrinterConfig::default());
+        assert_eq!(
+            visitor.generate(&mut page.clone()),
+            "---\ntitle: title\ntags:\n- tag\n---\nabc\n"
+        );
+
+        let mut visitor = MarkdownPrinter::new(MarkdownPrinterConfig {
+            front_matter: false,
+            ..Default::default()
+        });
+        assert_eq!(visitor.generate(&mut page), "abc\n");
+    }
+
+    #[rstest(
+        normalization,
+        expected,
+        case(TagNormalization::Underscore, "#multi_word_tag #tag\n"),
+        case(TagNormalization::Hyphen, "#multi-word-tag #tag\n"),
+        case(TagNormalization::Nested, "#multi/word/tag #tag\n")
+    )]
+    fn codegen_tag_normalization_test(normalization: TagNormalization, expected: &str) {
+        let mut visitor = MarkdownPrinter::new(MarkdownPrinterConfig {
+            tag_normalization: normalization,
+            ..Default::default()
+        });
+
+        let mut page = Page {
+            nodes: vec![Node::new(NodeKind::Paragraph(Paragraph::new(vec![
+                Node::new(NodeKind::HashTag(HashTag::bracketed("multi word　tag"))),
+                Node::new(NodeKind::Text(Text::new(" "))),
+                Node::new(NodeKind::HashTag(HashTag::new("tag"))),
+            ])))],
+            ..Default::default()
+        };
+
+        assert_eq!(visitor.generate(&mut page), expected);
+    }
+
+    #[test]
+    fn codegen_title_test() {
+        let page = Page {
+            title: Some("title".into()),
+            nodes: vec![Node::new(NodeKind::Paragraph(Paragraph::new(vec![
+                Node::new(NodeKind::Text(Text::new("abc"))),
+            ])))],
+            ..Default::default()
+        };
+
+        let generate = |title: TitleStyle| {
+            let mut visitor = MarkdownPrinter::new(MarkdownPrinterConfig {
+                title,
+                ..Default::default()
+            });
+            visitor.generate(&mut page.clone())
+        };
+
+        assert_eq!(generate(TitleStyle::Heading), "# title\nabc\n");
+        assert_eq!(
+            generate(TitleStyle::FrontMatter),
+            "---\ntitle: title\n---\nabc\n"
+        );
+        assert_eq!(generate(TitleStyle::Hidden), "abc\n");
+    }
+
+    #[test]
+    fn codegen_link_resolver_test() {
+        let mut visitor = MarkdownPrinter::with_link_resolver(
+            MarkdownPrinterConfig::default(),
+            Box::new(PathLinkResolver::new("pages/", ".md", ["Page Title"])),
+        );
+
+        let mut page = Page {
+            nodes: vec![Node::new(NodeKind::Paragraph(Paragraph::new(vec![
+                Node::new(NodeKind::InternalLink(InternalLink::new("page_title"))),
+                Node::new(NodeKind::Text(Text::new(" "))),
+                Node::new(NodeKind::InternalLink(InternalLink {
+                    title: "missing".into(),
+                    label: Some("label".into()),
+                    ..Default::default()
+                })),
+                Node::new(NodeKind::Text(Text::new(" "))),
+                Node::new(NodeKind::InternalLink(InternalLink {
+                    title: "".into(),
+                    anchor: Some("heading".into()),
+                    ..Default::default()
+                })),
+                Node::new(NodeKind::Text(Text::new(" "))),
+                Node::new(NodeKind::InternalLink(InternalLink {
+                    title: "Page Title".into(),
+                    embed: true,
+                    ..Default::default()
+                })),
+            ])))],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            visitor.generate(&mut page),
+            "[page_title](pages/Page%20Title.md) [label](pages/missing.md) [heading](#heading) [Page Title](pages/Page%20Title.md)\n"
+        );
+        assert_eq!(visitor.missing_links(), vec!["missing"]);
+    }
+
+    #[test]
+    fn codegen_backlinks_test() {
+        let mut page = Page {
+            nodes: vec![Node::new(NodeKind::Paragraph(Paragraph::new(vec![
+                Node::new(NodeKind::Text(Text::new("abc"))),
+            ])))],
+            ..Default::default()
+        };
+
+        let mut visitor = MarkdownPrinter::new(MarkdownPrinterConfig {
+            backlinks: vec!["a".into(), "b c".into()],
+            ..Default::default()
+        });
+        assert_eq!(
+            visitor.generate(&mut page.clone()),
+            "abc\n\n## Backlinks\n\n* [[a]]\n* [[b c]]\n"
+        );
+
+        let mut visitor = MarkdownPrinter::with_link_resolver(
+            MarkdownPrinterConfig {
+                backlinks: vec!["b c".into()],
+                ..Default::default()
+            },
+            Box::new(PathLinkResolver::new("", ".md", ["b c"])),
+        );
+        assert_eq!(
+            visitor.generate(&mut page),
+            "abc\n\n## Backlinks\n\n* [b c](b%20c.md)\n"
+        );
+    }
+
+    #[test]
+    fn codegen_toc_test() {
+        let page = Page {
+            title: Some("title".into()),
+            nodes: vec![
+                Node::new(NodeKind::Paragraph(Paragraph::new(vec![Node::new(
+                    NodeKind::Text(Text::new("abc")),
+                )]))),
+                Node::new(NodeKind::Paragraph(Paragraph::new(vec![Node::new(
+                    NodeKind::Text(Text::new(" {{toc}} ")),
+                )]))),
+                Node::new(NodeKind::Paragraph(Paragraph::new(vec![Node::new(
+                    NodeKind::Heading(Heading::new("A", 1)),
+                )]))),
+                Node::new(NodeKind::Paragraph(Paragraph::new(vec![Node::new(
+                    NodeKind::Heading(Heading::new("B b", 2)),
+                )]))),
+            ],
+            ..Default::default()
+        };
+
+        let generate = |toc: TocPosition| {
+            let mut visitor = MarkdownPrinter::new(MarkdownPrinterConfig {
+                title: TitleStyle::Heading,
+                toc: Some(toc),
+                ..Default::default()
+            });
+            visitor.generate(&mut page.clone())
+        };
+
+        assert_eq!(
+            generate(TocPosition::Top),
+            indoc! {"
+                # title
+                * [A](#a)
+                  * [B b](#b-b)
+
+                abc
+                 {{toc}} 
+                # A
+                ## B b
+            "}
+        );
+        assert_eq!(
+            generate(TocPosition::Marker("{{toc}}".into())),
+            indoc! {"
+                # title
+                abc
+                * [A](#a)
+                  * [B b](#b-b)
+                # A
+                ## B b
+            "}
+        );
+        assert_eq!(
+            generate(TocPosition::Marker("[toc]".into())),
+            "# title\nabc\n {{toc}} \n# A\n## B b\n"
+        );
+    }
+
+    #[rstest(input, expected,
+        case(Image::new("https://www.rust-lang.org/static/images/rust-logo-blk.svg"), "![](https://www.rust-lang.org/static/images/rust-logo-blk.svg)\n"),
+        case(Image::with_link("https://www.rust-lang.org/static/images/rust-logo-blk.svg", "https://www.rust-lang.org/"), "[![](https://www.rust-lang.org/static/images/rust-logo-blk.svg)](https://www.rust-lang.org/)\n"),
+        case(Image::new("https://gyazo.com/5f93e65a3b979ae5333aca4f32600611"), "![](https://gyazo.com/5f93e65a3b979ae5333aca4f32600611/max_size/400)\n"),
+    )]
+    fn codegen_image_test(input: Image, expected: &str) {
+        let mut visitor = MarkdownPrinter::new(MarkdownPrinterConfig::default());
+        let mut page = Page {
+            nodes: vec![Node::new(NodeKind::Paragraph(Paragraph::new(vec![
+                Node::new(NodeKind::Image(input)),
+            ])))],
+            ..Default::default()
+        };
+        assert_eq!(visitor.generate(&mut page), expected);
+    }
+
+    #[rstest(
+        flavor,
+        expected,
+        case(
+            MarkdownFlavor::Generic,
+            "![](assets/a.png) ![](https://example.com/a.png)\n"
+        ),
+        case(
+            MarkdownFlavor::Obsidian,
+            "![[assets/a.png]] ![](https://example.com/a.png)\n"
+        )
+    )]
+    fn codegen_local_image_test(flavor: MarkdownFlavor, expected: &str) {
+        let mut visitor = MarkdownPrinter::new(MarkdownPrinterConfig {
+            flavor,
+            ..Default::default()
+        });
+        let mut page = Page {
+            nodes: vec![Node::new(NodeKind::Paragraph(Paragraph::new(vec![
+                Node::new(NodeKind::Image(Image::new("assets/a.png"))),
+                Node::new(NodeKind::Text(Text::new(" "))),
+                Node::new(NodeKind::Image(Image::new("https://example.com/a.png"))),
+            ])))],
+            ..Default::default()
+        };
+        assert_eq!(visitor.generate(&mut page), expected);
+    }
+
+    #[rstest(style, input, expected,
+        case(EmbedStyle::Link, Embed::new(EmbedProvider::YouTube, "https://youtu.be/dQw4w9WgXcQ"), "https://youtu.be/dQw4w9WgXcQ\n"),
+        case(EmbedStyle::Embed, Embed::new(EmbedProvider::YouTube, "https://youtu.be/dQw4w9WgXcQ"), "![](https://youtu.be/dQw4w9WgXcQ)\n"),
+        case(EmbedStyle::Html, Embed::new(EmbedProvider::YouTube, "https://youtu.be/dQw4w9WgXcQ"), "<iframe src=\"https://www.youtube.com/embed/dQw4w9WgXcQ\" allowfullscreen></iframe>\n"),
+        case(EmbedStyle::Html, Embed::new(EmbedProvider::Vimeo, "https://vimeo.com/76979871"), "<iframe src=\"https://player.vimeo.com/video/76979871\" allowfullscreen></iframe>\n"),
+        case(EmbedStyle::Html, Embed::new(EmbedProvider::Twitter, "https://twitter.com/rustlang/status/1549787419512807424"), "<blockquote class=\"twitter-tweet\"><a href=\"https://twitter.com/rustlang/status/1549787419512807424\"></a></blockquote>\n"),
+        case(EmbedStyle::Html, Embed::new(EmbedProvider::Video, "https://example.com/movie.mp4"), "<video src=\"https://example.com/movie.mp4\" controls></video>\n"),
+        case(EmbedStyle::Html, Embed::new(EmbedProvider::Audio, "https://example.com/music.mp3"), "<audio src=\"https://example.com/music.mp3\" controls></audio>\n"),
+        case(EmbedStyle::Html, Embed::new(EmbedProvider::Video, "https://e.com/x\"onerror=alert(1)\".mp4?a=1&b=<2>"), "<video src=\"https://e.com/x&quot;onerror=alert(1)&quot;.mp4?a=1&amp;b=&lt;2&gt;\" controls></video>\n"),
+        case(EmbedStyle::Html, Embed::new(EmbedProvider::Twitter, "https://twitter.com/a/status/1'"), "<blockquote class=\"twitter-tweet\"><a href=\"https://twitter.com/a/status/1&#39;\"></a></blockquote>\n"),
+    )]
+    fn codegen_embed_test(style: EmbedStyle, input: Embed, expected: &str) {
+        let mut visitor = MarkdownPrinter::new(MarkdownPrinterConfig {
+            embed: style,
+            ..Default::default()
+        });
+        let mut page = Page {
+            nodes: vec![Node::new(NodeKind::Paragraph(Paragraph::new(vec![
+                Node::new(NodeKind::Embed(input)),
+            ])))],
+            ..Default::default()
+        };
+        assert_eq!(visitor.generate(&mut page), expected);
+    }
+
+    #[rstest(style, expected,
+        case(LocationStyle::OpenStreetMap, "* [Tokyo Station](https://www.openstreetmap.org/?mlat=35.6812&mlon=139.7671#map=14/35.6812/139.7671) [-33.8568,-151.2153](https://www.openstreetmap.org/?mlat=-33.8568&mlon=-151.2153#map=5/-33.8568/-151.2153)\n"),
+        case(LocationStyle::GeoUri, "* [Tokyo Station](geo:35.6812,139.7671?z=14) [-33.8568,-151.2153](geo:-33.8568,-151.2153?z=5)\n"),
+        case(LocationStyle::FrontMatter, "---\nlocation:\n- 35.6812\n- 139.7671\n---\n* Tokyo Station [-33.8568,-151.2153](https://www.openstreetmap.org/?mlat=-33.8568&mlon=-151.2153#map=5/-33.8568/-151.2153)\n"),
+    )]
+    fn codegen_location_test(style: LocationStyle, expected: &str) {
+        let mut visitor = MarkdownPrinter::new(MarkdownPrinterConfig {
+            location: style,
+            ..Default::default()
+        });
+        let mut page = Page {
+            nodes: vec![Node::new(NodeKind::List(List::new(vec![ListItem::new(
+                ListKind::Disc,
+                1,
+                vec![
+                    Node::new(NodeKind::Location(Location::new(
+                        35.6812,
+                        139.7671,
+                        14,
+                        Some("Tokyo Station"),
+                    ))),
+                    Node::new(NodeKind::Text(Text::new(" "))),
+                    Node::new(NodeKind::Location(Location::new(
+                        -33.8568, -151.2153, 5, None,
+                    ))),
+                ],
+            )])))],
+            ..Default::default()
+        };
+        assert_eq!(visitor.generate(&mut page), expected);
+    }
+
+    #[test]
+    fn codegen_location_front_matter_test() {
+        let page = Page {
+            nodes: vec![Node::new(NodeKind::Paragraph(Paragraph::new(vec![
+                Node::new(NodeKind::Location(Location::new(
+                    35.6812,
+                    139.7671,
+                    14,
+                    Some("Tokyo Station"),
+                ))),
+                Node::new(NodeKind::Text(Text::new(" "))),
+                Node::new(NodeKind::Location(Location::new(
+                    34.7025,
+                    135.4959,
+                    14,
+                    Some("Osaka Station"),
+                ))),
+                Node::new(NodeKind::Text(Text::new(" "))),
+                Node::new(NodeKind::Location(Location::new(
+                    -33.8568, -151.2153, 5, None,
+                ))),
+            ])))],
+            ..Default::default()
+        };
+        let generate = |front_matter: bool| {
+            MarkdownPrinter::new(MarkdownPrinterConfig {
+                location: LocationStyle::FrontMatter,
+                front_matter,
+                ..Default::default()
+            })
+            .generate(&mut page.clone())
+        };
+
+        let links = "[Osaka Station](https://www.openstreetmap.org/?mlat=34.7025&mlon=135.4959#map=14/34.7025/135.4959) [-33.8568,-151.2153](https://www.openstreetmap.org/?mlat=-33.8568&mlon=-151.2153#map=5/-33.8568/-151.2153)";
+        assert_eq!(
+            generate(true),
+            format!(
+                "---\nlocation:\n- 35.6812\n- 139.7671\n---\nTokyo Station {}\n",
+                links
+            )
+        );
+        assert_eq!(
+            generate(false),
+            format!("[Tokyo Station](https://www.openstreetmap.org/?mlat=35.6812&mlon=139.7671#map=14/35.6812/139.7671) {}\n", links)
+        );
+    }
 }
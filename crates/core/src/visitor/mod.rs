@@ -2,89 +2,120 @@ use std::borrow::BorrowMut;
 
 use crate::ast::*;
 
+pub mod localize;
 pub mod markdown_printer;
+pub mod org_printer;
 pub mod scrapbox_printer;
 
 #[derive(Debug, PartialEq)]
-pub enum TransformCommand {
+pub enum TransformCommand<'a> {
     /// Replace the current node with the specified node.
-    Replace(NodeKind),
+    Replace(NodeKind<'a>),
     /// Delete the current node.
     Delete,
 }
 
+/// The methods are generic over the lifetime of the AST,
+/// so a visitor works on both a borrowed `Page<'a>` and an owned `Page<'static>`.
 pub trait Visitor: Sized {
     /// if returns true, visitor doesn't walk nodes
     fn is_finish(&mut self) -> bool {
         false
     }
 
-    fn visit(&mut self, value: &mut Page) {
+    fn visit(&mut self, value: &mut Page<'_>) {
         self.visit_page(value);
     }
 
-    fn visit_page(&mut self, value: &mut Page) {
+    fn visit_page(&mut self, value: &mut Page<'_>) {
         walk_page(self, value);
     }
 
-    fn visit_node(&mut self, value: &mut Node) {
+    fn visit_node(&mut self, value: &mut Node<'_>) {
         walk_node(self, value)
     }
 
-    fn visit_paragraph(&mut self, value: &mut Paragraph) -> Option<TransformCommand> {
+    fn visit_paragraph<'a>(&mut self, value: &mut Paragraph<'a>) -> Option<TransformCommand<'a>> {
         walk_paragraph(self, value)
     }
 
-    fn visit_list(&mut self, value: &mut List) -> Option<TransformCommand> {
+    /// The children of a list aren't walked unless a visitor calls `walk_list`
+    fn visit_list<'a>(&mut self, _value: &mut List<'a>) -> Option<TransformCommand<'a>> {
         None
     }
 
-    fn visit_hashtag(&mut self, value: &HashTag) -> Option<TransformCommand> {
+    fn visit_list_item(&mut self, value: &mut ListItem<'_>) {
+        walk_list_item(self, value)
+    }
+
+    fn visit_hashtag<'a>(&mut self, _value: &HashTag<'a>) -> Option<TransformCommand<'a>> {
+        None
+    }
+
+    fn visit_internal_link<'a>(
+        &mut self,
+        _value: &InternalLink<'a>,
+    ) -> Option<TransformCommand<'a>> {
+        None
+    }
+
+    fn visit_external_link<'a>(
+        &mut self,
+        _value: &ExternalLink<'a>,
+    ) -> Option<TransformCommand<'a>> {
+        None
+    }
+
+    fn visit_emphasis<'a>(&mut self, _value: &Emphasis<'a>) -> Option<TransformCommand<'a>> {
         None
     }
 
-    fn visit_internal_link(&mut self, value: &InternalLink) -> Option<TransformCommand> {
+    fn visit_heading<'a>(&mut self, _value: &Heading<'a>) -> Option<TransformCommand<'a>> {
         None
     }
 
-    fn visit_external_link(&mut self, value: &ExternalLink) -> Option<TransformCommand> {
+    fn visit_block_quate<'a>(&mut self, _value: &BlockQuate<'a>) -> Option<TransformCommand<'a>> {
         None
     }
 
-    fn visit_emphasis(&mut self, value: &Emphasis) -> Option<TransformCommand> {
+    fn visit_code_block<'a>(&mut self, _value: &CodeBlock<'a>) -> Option<TransformCommand<'a>> {
         None
     }
 
-    fn visit_heading(&mut self, value: &Heading) -> Option<TransformCommand> {
+    fn visit_table<'a>(&mut self, _value: &Table<'a>) -> Option<TransformCommand<'a>> {
         None
     }
 
-    fn visit_block_quate(&mut self, value: &BlockQuate) -> Option<TransformCommand> {
+    fn visit_image<'a>(&mut self, _value: &Image<'a>) -> Option<TransformCommand<'a>> {
         None
     }
 
-    fn visit_code_block(&mut self, value: &CodeBlock) -> Option<TransformCommand> {
+    fn visit_embed<'a>(&mut self, _value: &Embed<'a>) -> Option<TransformCommand<'a>> {
         None
     }
 
-    fn visit_table(&mut self, value: &Table) -> Option<TransformCommand> {
+    fn visit_location<'a>(&mut self, _value: &Location<'a>) -> Option<TransformCommand<'a>> {
         None
     }
 
-    fn visit_image(&mut self, value: &Image) -> Option<TransformCommand> {
+    fn visit_math<'a>(&mut self, _value: &Math<'a>) -> Option<TransformCommand<'a>> {
         None
     }
 
-    fn visit_math(&mut self, value: &Math) -> Option<TransformCommand> {
+    fn visit_callout<'a>(&mut self, value: &mut Callout<'a>) -> Option<TransformCommand<'a>> {
+        walk_callout(self, value)
+    }
+
+    fn visit_comment<'a>(&mut self, _value: &Comment<'a>) -> Option<TransformCommand<'a>> {
         None
     }
 
-    fn visit_text(&mut self, _text: &Text) -> Option<TransformCommand> {
+    fn visit_text<'a>(&mut self, _text: &Text<'a>) -> Option<TransformCommand<'a>> {
         None
     }
 }
 
-pub fn walk_page<V: Visitor>(visitor: &mut V, value: &mut Page) {
+pub fn walk_page<V: Visitor>(visitor: &mut V, value: &mut Page<'_>) {
     for node in value.nodes.iter_mut() {
         if visitor.is_finish() {
             return;
@@ -93,7 +124,7 @@ pub fn walk_page<V: Visitor>(visitor: &mut V, value: &mut Page) {
     }
 }
 
-pub fn walk_node<V: Visitor>(visitor: &mut V, value: &mut Node) {
+pub fn walk_node<V: Visitor>(visitor: &mut V, value: &mut Node<'_>) {
     if visitor.is_finish() {
         return;
     }
@@ -109,7 +140,11 @@ pub fn walk_node<V: Visitor>(visitor: &mut V, value: &mut Node) {
         NodeKind::CodeBlock(v) => visitor.visit_code_block(v),
         NodeKind::Table(v) => visitor.visit_table(v),
         NodeKind::Image(v) => visitor.visit_image(v),
+        NodeKind::Embed(v) => visitor.visit_embed(v),
+        NodeKind::Location(v) => visitor.visit_location(v),
         NodeKind::Math(v) => visitor.visit_math(v),
+        NodeKind::Callout(v) => visitor.visit_callout(v),
+        NodeKind::Comment(v) => visitor.visit_comment(v),
         NodeKind::Text(v) => visitor.visit_text(v),
         NodeKind::Nop => None,
     };
@@ -125,10 +160,10 @@ pub fn walk_node<V: Visitor>(visitor: &mut V, value: &mut Node) {
     }
 }
 
-pub fn walk_paragraph<V: Visitor>(
+pub fn walk_paragraph<'a, V: Visitor>(
     visitor: &mut V,
-    value: &mut Paragraph,
-) -> Option<TransformCommand> {
+    value: &mut Paragraph<'a>,
+) -> Option<TransformCommand<'a>> {
     for node in value.children.iter_mut() {
         if visitor.is_finish() {
             return None;
@@ -137,3 +172,38 @@ pub fn walk_paragraph<V: Visitor>(
     }
     None
 }
+
+pub fn walk_callout<'a, V: Visitor>(
+    visitor: &mut V,
+    value: &mut Callout<'a>,
+) -> Option<TransformCommand<'a>> {
+    for node in value.children.iter_mut() {
+        if visitor.is_finish() {
+            return None;
+        }
+        visitor.visit_node(node);
+    }
+    None
+}
+
+pub fn walk_list<'a, V: Visitor>(
+    visitor: &mut V,
+    value: &mut List<'a>,
+) -> Option<TransformCommand<'a>> {
+    for item in value.children.iter_mut() {
+        if visitor.is_finish() {
+            return None;
+        }
+        visitor.visit_list_item(item);
+    }
+    None
+}
+
+pub fn walk_list_item<V: Visitor>(visitor: &mut V, value: &mut ListItem<'_>) {
+    for node in value.children.iter_mut() {
+        if visitor.is_finish() {
+            return;
+        }
+        visitor.visit_node(node);
+    }
+}
@@ -0,0 +1,597 @@
+use super::{walk_paragraph, TransformCommand, Visitor};
+use crate::ast::*;
+use crate::visitor::markdown_printer::TagNormalization;
+
+pub struct OrgPrinterConfig {
+    /// indent of a nested list
+    pub indent: String,
+}
+
+impl Default for OrgPrinterConfig {
+    fn default() -> Self {
+        Self {
+            indent: "  ".into(),
+        }
+    }
+}
+
+/// Writes a page in Org syntax of Emacs.
+/// Use `MarkdownPass` before it, which makes headings of `[** text]`
+/// and takes code blocks and tables out of lists.
+pub struct OrgPrinter {
+    document: String,
+    config: OrgPrinterConfig,
+}
+
+impl OrgPrinter {
+    pub fn new(config: OrgPrinterConfig) -> Self {
+        Self {
+            document: String::new(),
+            config,
+        }
+    }
+
+    pub fn generate(&mut self, page: &mut Page<'_>) -> String {
+        if let Some(title) = &page.title {
+            self.document.push_str(&format!("#+TITLE: {}\n", title));
+        }
+        if !page.tags.is_empty() {
+            // a tag of Org can't have spaces
+            let tags: Vec<String> = page
+                .tags
+                .iter()
+                .map(|tag| TagNormalization::Underscore.normalize(tag))
+                .collect();
+            self.document
+                .push_str(&format!("#+FILETAGS: :{}:\n", tags.join(":")));
+        }
+        self.visit(page);
+        self.document.clone()
+    }
+}
+
+/// The language of Org Babel for the file name of `code:`
+/// "hello.rs" -> "rust"
+fn src_language(file_name: &str) -> &str {
+    let extension = match file_name.rsplit_once('.') {
+        Some((_, extension)) => extension,
+        None => return file_name,
+    };
+    match extension {
+        "rs" => "rust",
+        "py" => "python",
+        "rb" => "ruby",
+        "ts" => "typescript",
+        "el" => "emacs-lisp",
+        "hs" => "haskell",
+        "bash" => "sh",
+        "c" | "h" => "C",
+        "cc" | "cpp" | "hpp" => "C++",
+        "yml" => "yaml",
+        "md" => "markdown",
+        extension => extension,
+    }
+}
+
+/// A line of code which starts with `*` or `#+` is escaped with `,`, not to end the block.
+fn escape_code(line: &str) -> String {
+    let code = line.trim_start_matches([' ', '\t']);
+    let unescaped = code.trim_start_matches(',');
+    if unescaped.starts_with('*') || unescaped.starts_with("#+") {
+        let indent = &line[..line.len() - code.len()];
+        format!("{},{}", indent, code)
+    } else {
+        line.to_string()
+    }
+}
+
+impl Visitor for OrgPrinter {
+    fn visit_paragraph<'a>(&mut self, value: &mut Paragraph<'a>) -> Option<TransformCommand<'a>> {
+        // a comment of Org is a line
+        if let [Node {
+            kind: NodeKind::Comment(comment),
+            ..
+        }] = value.children.as_slice()
+        {
+            self.document.push_str(&format!("# {}\n", comment.value));
+            return None;
+        }
+        walk_paragraph(self, value);
+        self.document.push('\n');
+        None
+    }
+
+    fn visit_list<'a>(&mut self, value: &mut List<'a>) -> Option<TransformCommand<'a>> {
+        // the number of the item at each level
+        let mut numbers: Vec<usize> = vec![];
+        for item in value.children.iter_mut() {
+            let level = item.level.max(1);
+            numbers.resize(level, 0);
+            let number = &mut numbers[level - 1];
+            let bullet = match item.kind {
+                ListKind::Disc => {
+                    *number = 0;
+                    "-".to_string()
+                }
+                ListKind::Decimal => {
+                    *number += 1;
+                    match &item.number {
+                        Some(written) => {
+                            // the next item without a number follows it
+                            *number = written.parse().unwrap_or(*number);
+                            format!("{}.", written)
+                        }
+                        None => format!("{}.", number),
+                    }
+                }
+                // Org reads `a.` only with `org-list-allow-alphabetical`
+                ListKind::Alphabet => {
+                    *number += 1;
+                    format!("{}.", number)
+                }
+            };
+            let indent = self.config.indent.repeat(level - 1);
+            self.document.push_str(&format!("{}{} ", indent, bullet));
+            for node in item.children.iter_mut() {
+                self.visit_node(node);
+            }
+            self.document.push('\n');
+        }
+        None
+    }
+
+    fn visit_hashtag<'a>(&mut self, value: &HashTag<'a>) -> Option<TransformCommand<'a>> {
+        // Org has tags only for headings, so it's written as Scrapbox
+        if value.bracketed || value.value.contains(char::is_whitespace) {
+            self.document.push_str(&format!("#[{}]", value.value));
+        } else {
+            self.document.push_str(&format!("#{}", value.value));
+        }
+        None
+    }
+
+    fn visit_internal_link<'a>(
+        &mut self,
+        value: &InternalLink<'a>,
+    ) -> Option<TransformCommand<'a>> {
+        let target = match (value.title.is_empty(), &value.anchor) {
+            (true, Some(anchor)) => format!("*{}", anchor),
+            (false, Some(anchor)) => format!("file:{}.org::*{}", value.title, anchor),
+            (_, None) => format!("file:{}.org", value.title),
+        };
+        let description = match (&value.label, &value.anchor) {
+            (Some(label), _) => label,
+            (None, Some(anchor)) if value.title.is_empty() => anchor,
+            _ => &value.title,
+        };
+        self.document
+            .push_str(&format!("[[{}][{}]]", target, description));
+        None
+    }
+
+    fn visit_external_link<'a>(
+        &mut self,
+        value: &ExternalLink<'a>,
+    ) -> Option<TransformCommand<'a>> {
+        match (&value.title, value.plain) {
+            (Some(title), _) => self
+                .document
+                .push_str(&format!("[[{}][{}]]", value.url, title)),
+            (None, true) => self.document.push_str(&value.url),
+            (None, false) => self.document.push_str(&format!("[[{}]]", value.url)),
+        }
+        None
+    }
+
+    fn visit_emphasis<'a>(&mut self, value: &Emphasis<'a>) -> Option<TransformCommand<'a>> {
+        let mut tmp = value.text.to_string();
+        if value.bold > 0 {
+            tmp = format!("*{}*", tmp);
+        }
+        if value.italic > 0 {
+            tmp = format!("/{}/", tmp);
+        }
+        if value.strikethrough > 0 {
+            tmp = format!("+{}+", tmp);
+        }
+        if value.underline > 0 {
+            tmp = format!("_{}_", tmp);
+        }
+        self.document.push_str(&tmp);
+        None
+    }
+
+    fn visit_heading<'a>(&mut self, value: &Heading<'a>) -> Option<TransformCommand<'a>> {
+        self.document
+            .push_str(&format!("{} {}", "*".repeat(value.level), value.text));
+        None
+    }
+
+    fn visit_block_quate<'a>(&mut self, value: &BlockQuate<'a>) -> Option<TransformCommand<'a>> {
+        let marker = if value.value.contains('~') { '=' } else { '~' };
+        self.document
+            .push_str(&format!("{}{}{}", marker, value.value, marker));
+        None
+    }
+
+    fn visit_code_block<'a>(&mut self, value: &CodeBlock<'a>) -> Option<TransformCommand<'a>> {
+        let file_name = value.file_name.as_ref();
+        let language = src_language(file_name);
+        match (language, language == file_name) {
+            ("", _) => self.document.push_str("#+BEGIN_SRC\n"),
+            (_, true) => self
+                .document
+                .push_str(&format!("#+BEGIN_SRC {}\n", language)),
+            // keep the file name to write the code to the file
+            (_, false) => self
+                .document
+                .push_str(&format!("#+BEGIN_SRC {} :tangle {}\n", language, file_name)),
+        }
+        for code in &value.children {
+            self.document.push_str(&format!("{}\n", escape_code(code)));
+        }
+        self.document.push_str("#+END_SRC\n");
+        None
+    }
+
+    fn visit_table<'a>(&mut self, value: &Table<'a>) -> Option<TransformCommand<'a>> {
+        if value.header.is_empty() {
+            return None;
+        }
+
+        if !value.name.is_empty() {
+            self.document.push_str(&format!("#+NAME: {}\n", value.name));
+        }
+        self.document
+            .push_str(&format!("| {} |\n", value.header.join(" | ")));
+        self.document.push_str(&format!(
+            "|{}|\n",
+            vec!["---"; value.header.len()].join("+")
+        ));
+        for row in &value.rows {
+            if row.is_empty() {
+                break;
+            }
+            self.document
+                .push_str(&format!("| {} |\n", row.join(" | ")));
+        }
+        None
+    }
+
+    fn visit_image<'a>(&mut self, value: &Image<'a>) -> Option<TransformCommand<'a>> {
+        // a link to an image without a description is shown inline
+        match &value.link {
+            Some(link) => self
+                .document
+                .push_str(&format!("[[{}][{}]]", link, value.uri)),
+            None => self.document.push_str(&format!("[[{}]]", value.uri)),
+        }
+        None
+    }
+
+    fn visit_embed<'a>(&mut self, value: &Embed<'a>) -> Option<TransformCommand<'a>> {
+        self.document.push_str(&format!("[[{}]]", value.url));
+        None
+    }
+
+    fn visit_location<'a>(&mut self, value: &Location<'a>) -> Option<TransformCommand<'a>> {
+        let uri = format!(
+            "geo:{},{}?z={}",
+            value.latitude, value.longitude, value.zoom
+        );
+        match &value.label {
+            Some(label) => self.document.push_str(&format!("[[{}][{}]]", uri, label)),
+            None => self.document.push_str(&format!("[[{}]]", uri)),
+        }
+        None
+    }
+
+    fn visit_math<'a>(&mut self, value: &Math<'a>) -> Option<TransformCommand<'a>> {
+        self.document.push_str(&format!("\\( {} \\)", value.value));
+        None
+    }
+
+    fn visit_callout<'a>(&mut self, value: &mut Callout<'a>) -> Option<TransformCommand<'a>> {
+        let kind = value.kind.to_uppercase();
+        match &value.title {
+            Some(title) => self
+                .document
+                .push_str(&format!("#+BEGIN_{} {}\n", kind, title)),
+            None => self.document.push_str(&format!("#+BEGIN_{}\n", kind)),
+        }
+        for node in value.children.iter_mut() {
+            self.visit_node(node);
+        }
+        self.document.push_str(&format!("#+END_{}\n", kind));
+        None
+    }
+
+    fn visit_comment<'a>(&mut self, _value: &Comment<'a>) -> Option<TransformCommand<'a>> {
+        // Org doesn't have a comment in a line
+        None
+    }
+
+    fn visit_text<'a>(&mut self, value: &Text<'a>) -> Option<TransformCommand<'a>> {
+        self.document.push_str(&value.value);
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::org::{self, OrgParserContext};
+    use crate::parser::scrapbox::{self, ScrapboxParserContext};
+    use crate::parser::Span;
+    use crate::visitor::markdown_printer::MarkdownPass;
+    use crate::visitor::scrapbox_printer::{ScrapboxPass, ScrapboxPrinter, ScrapboxPrinterConfig};
+    use indoc::indoc;
+    use rstest::rstest;
+
+    fn scrapbox_to_org(input: &str) -> String {
+        let (_, mut page) =
+            scrapbox::page(Span::new_extra(input, ScrapboxParserContext::default())).unwrap();
+        MarkdownPass::default().visit(&mut page);
+        OrgPrinter::new(OrgPrinterConfig::default()).generate(&mut page)
+    }
+
+    fn org_to_scrapbox(input: &str) -> String {
+        let (_, mut page) = org::page(Span::new_extra(input, OrgParserContext::default())).unwrap();
+        ScrapboxPass::default().visit(&mut page);
+        ScrapboxPrinter::new(ScrapboxPrinterConfig::default()).generate(&mut page)
+    }
+
+    #[test]
+    fn codegen_test() {
+        let mut page = Page {
+            title: Some("title".into()),
+            tags: vec!["tag1".into(), "multi word".into()],
+            nodes: vec![
+                Node::new(NodeKind::Paragraph(Paragraph::new(vec![Node::new(
+                    NodeKind::Heading(Heading::new("heading", 2)),
+                )]))),
+                Node::new(NodeKind::Paragraph(Paragraph::new(vec![
+                    Node::new(NodeKind::Emphasis(Emphasis::bold("bold"))),
+                    Node::new(NodeKind::Text(Text::new(" "))),
+                    Node::new(NodeKind::Emphasis(Emphasis {
+                        text: "all".into(),
+                        bold: 1,
+                        italic: 1,
+                        strikethrough: 1,
+                        underline: 1,
+                        ..Default::default()
+                    })),
+                    Node::new(NodeKind::Text(Text::new(" "))),
+                    Node::new(NodeKind::BlockQuate(BlockQuate::new("code"))),
+                    Node::new(NodeKind::Text(Text::new(" "))),
+                    Node::new(NodeKind::Math(Math::new("x^2"))),
+                    Node::new(NodeKind::Comment(Comment::new("inline"))),
+                ]))),
+                Node::new(NodeKind::Paragraph(Paragraph::new(vec![Node::new(
+                    NodeKind::Comment(Comment::new("comment")),
+                )]))),
+                Node::new(NodeKind::List(List::new(vec![
+                    ListItem::disc(1, vec![Node::new(NodeKind::Text(Text::new("a")))]),
+                    ListItem::decimal(2, vec![Node::new(NodeKind::Text(Text::new("b")))]),
+                    ListItem::decimal(2, vec![Node::new(NodeKind::Text(Text::new("c")))]),
+                    ListItem::alphabet(3, vec![Node::new(NodeKind::Text(Text::new("d")))]),
+                    ListItem::decimal(2, vec![Node::new(NodeKind::Text(Text::new("e")))]),
+                ]))),
+                Node::new(NodeKind::CodeBlock(CodeBlock::new(
+                    "hello.rs",
+                    vec!["fn main() {", "* a", "    ,#+b", "}"],
+                ))),
+                Node::new(NodeKind::CodeBlock(CodeBlock::new("python", vec!["a"]))),
+                Node::new(NodeKind::Table(Table::new(
+                    "t",
+                    vec!["a".into(), "b".into()],
+                    vec![vec!["c".into(), "d".into()], vec![]],
+                ))),
+                Node::new(NodeKind::Callout(Callout::new(
+                    "note",
+                    Some("title"),
+                    vec![Node::new(NodeKind::Paragraph(Paragraph::new(vec![
+                        Node::new(NodeKind::Text(Text::new("body"))),
+                    ])))],
+                ))),
+            ],
+            ..Default::default()
+        };
+
+        let expected = indoc! {r#"
+            #+TITLE: title
+            #+FILETAGS: :tag1:multi_word:
+            ** heading
+            *bold* _+/*all*/+_ ~code~ \( x^2 \)
+            # comment
+            - a
+              1. b
+              2. c
+                1. d
+              3. e
+            #+BEGIN_SRC rust :tangle hello.rs
+            fn main() {
+            ,* a
+                ,,#+b
+            }
+            #+END_SRC
+            #+BEGIN_SRC python
+            a
+            #+END_SRC
+            #+NAME: t
+            | a | b |
+            |---+---|
+            | c | d |
+            #+BEGIN_NOTE title
+            body
+            #+END_NOTE
+        "#};
+        assert_eq!(
+            OrgPrinter::new(OrgPrinterConfig::default()).generate(&mut page),
+            expected
+        );
+    }
+
+    #[rstest(input, expected,
+        case(InternalLink::new("page"), "[[file:page.org][page]]"),
+        case(InternalLink { title: "page".into(), label: Some("label".into()), ..Default::default() }, "[[file:page.org][label]]"),
+        case(InternalLink { title: "page".into(), anchor: Some("heading".into()), ..Default::default() }, "[[file:page.org::*heading][page]]"),
+        case(InternalLink { title: "".into(), anchor: Some("heading".into()), ..Default::default() }, "[[*heading][heading]]"),
+    )]
+    fn codegen_internal_link_test(input: InternalLink, expected: &str) {
+        let mut printer = OrgPrinter::new(OrgPrinterConfig::default());
+        printer.visit_internal_link(&input);
+        assert_eq!(printer.document, expected);
+    }
+
+    #[rstest(
+        input,
+        expected,
+        case(
+            NodeKind::ExternalLink(ExternalLink::new(Some("Rust"), "https://www.rust-lang.org/")),
+            "[[https://www.rust-lang.org/][Rust]]"
+        ),
+        case(
+            NodeKind::ExternalLink(ExternalLink::new(None, "https://www.rust-lang.org/")),
+            "[[https://www.rust-lang.org/]]"
+        ),
+        case(
+            NodeKind::ExternalLink(ExternalLink::plain("https://www.rust-lang.org/")),
+            "https://www.rust-lang.org/"
+        ),
+        case(
+            NodeKind::Image(Image::new("https://example.com/a.png")),
+            "[[https://example.com/a.png]]"
+        ),
+        case(
+            NodeKind::Image(Image::with_link(
+                "https://example.com/a.png",
+                "https://example.com/"
+            )),
+            "[[https://example.com/][https://example.com/a.png]]"
+        ),
+        case(
+            NodeKind::Location(Location::new(35.6812, 139.7671, 14, Some("Tokyo Station"))),
+            "[[geo:35.6812,139.7671?z=14][Tokyo Station]]"
+        ),
+        case(NodeKind::HashTag(HashTag::bracketed("multi word")), "#[multi word]"),
+        case(NodeKind::BlockQuate(BlockQuate::new("a~b")), "=a~b=")
+    )]
+    fn codegen_node_test(input: NodeKind, expected: &str) {
+        let mut page = Page {
+            nodes: vec![Node::new(NodeKind::Paragraph(Paragraph::new(vec![
+                Node::new(input),
+            ])))],
+            ..Default::default()
+        };
+        assert_eq!(
+            OrgPrinter::new(OrgPrinterConfig::default()).generate(&mut page),
+            format!("{}\n", expected)
+        );
+    }
+
+    #[test]
+    fn scrapbox_to_org_test() {
+        let input = indoc! {"
+            [*** title]
+            abc #tag [link] [Rust https://www.rust-lang.org/] [* bold] [$ x^2]
+             list
+              `code`
+            code:hello.js
+             console.log(1);
+
+            table:t
+             a\tb
+             c\td
+
+        "};
+        let expected = indoc! {r#"
+            * title
+            abc #tag [[file:link.org][link]] [[https://www.rust-lang.org/][Rust]] *bold* \( x^2 \)
+            - list
+              - ~code~
+            #+BEGIN_SRC js :tangle hello.js
+            console.log(1);
+            #+END_SRC
+
+            #+NAME: t
+            | a | b |
+            |---+---|
+            | c | d |
+
+        "#};
+        assert_eq!(scrapbox_to_org(input), expected);
+    }
+
+    #[test]
+    fn org_to_scrapbox_test() {
+        let input = indoc! {r#"
+            #+TITLE: title
+            #+FILETAGS: :tag1:tag2:
+            * heading
+            ** sub heading :a:
+            Text with *bold*, /italic/ and [[https://www.rust-lang.org/][Rust]].
+            - item [[file:page.org][page]]
+              1. \( x^2 \)
+            #+BEGIN_SRC rust
+              fn main() {}
+            #+END_SRC
+            | a | b |
+            |---+---|
+            | c | d |
+        "#};
+        let expected = indoc! {"
+            title
+            [*** heading]
+            [** sub heading] #a
+            Text with [* bold], [/ italic] and [Rust https://www.rust-lang.org/].
+            \titem [page]
+            \t\t1. [$x^2]
+            code:rust
+             fn main() {}
+            table:table
+             a\tb
+             c\td
+            #tag1 #tag2
+        "};
+        assert_eq!(org_to_scrapbox(input), expected);
+    }
+
+    #[test]
+    fn list_number_test() {
+        assert_eq!(
+            scrapbox_to_org(" 3. c\n 4. d\n  1. e\n 10. f\n"),
+            "3. c\n4. d\n  1. e\n10. f\n"
+        );
+
+        // an item without the number follows the last number
+        let text = |value| vec![Node::new(NodeKind::Text(Text::new(value)))];
+        let mut page = Page {
+            nodes: vec![Node::new(NodeKind::List(List::new(vec![
+                ListItem {
+                    number: Some("3".into()),
+                    ..ListItem::decimal(1, text("a"))
+                },
+                ListItem::decimal(1, text("b")),
+            ])))],
+            ..Default::default()
+        };
+        assert_eq!(
+            OrgPrinter::new(OrgPrinterConfig::default()).generate(&mut page),
+            "3. a\n4. b\n"
+        );
+    }
+
+    #[rstest(
+        input,
+        case("[*** a]\n[** b]\n[* c]\n"),
+        case("abc [* bold] [/ italic] [- strike] `code` #tag [link] [https://example.com/]\n"),
+        case("\ta\n\t\tb\n\t\t\tc\n\t1. d\n"),
+        case("code:hello.rs\n fn main() {}\n\n"),
+        case("table:t\n a\tb\n c\td\n\n"),
+        case("[$x^2] [Rust https://www.rust-lang.org/] [https://example.com/a.png]\n")
+    )]
+    fn symmetric_test(input: &str) {
+        assert_eq!(org_to_scrapbox(&scrapbox_to_org(input)), input);
+    }
+}
@@ -1,8 +1,92 @@
-use super::{walk_paragraph, TransformCommand, Visitor};
+use serde::{Deserialize, Serialize};
+
+use super::{walk_list, walk_list_item, walk_paragraph, TransformCommand, Visitor};
 use crate::ast::*;
 
+/// The inverse of `MarkdownPass`
+pub struct ScrapboxPass {
+    // Examples:
+    // - `h1_level` == 3: `# text` -> [*** text]
+    // - `h1_level` == 3: `## text` -> [** text]
+    // - `h1_level` == 3: `### text` -> [* text]
+    // - `h1_level` == 3: `#### text` -> [* text]
+    // - `h1_level` == 5: `# text` -> [***** text]
+    pub h1_level: usize,
+}
+
+impl Default for ScrapboxPass {
+    fn default() -> Self {
+        Self { h1_level: 3 }
+    }
+}
+
+impl ScrapboxPass {
+    /// The level of bold for a heading of `level`
+    pub fn bold_level(&self, level: usize) -> usize {
+        // Scrapbox doesn't have a heading smaller than `[* text]`
+        (self.h1_level + 1).saturating_sub(level).max(1)
+    }
+}
+
+impl Visitor for ScrapboxPass {
+    fn visit_heading<'a>(&mut self, value: &Heading<'a>) -> Option<TransformCommand<'a>> {
+        let bold = self.bold_level(value.level);
+        Some(TransformCommand::Replace(NodeKind::Emphasis(
+            Emphasis::bold_level(value.text.clone(), bold),
+        )))
+    }
+}
+
+/// How to write `[* bold]`, which has no other decoration
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum BoldStyle {
+    /// As it was written
+    Keep,
+    /// `[* bold]`
+    #[default]
+    Asterisk,
+    /// `[[bold]]`
+    DoubleBracket,
+}
+
+/// Rewrite the notations which have several forms into one form.
+/// It changes only how the nodes are written, so `ScrapboxPrinter` gives the same page.
+///
+/// - the indent of lists as it was written is dropped to use `ScrapboxPrinterConfig::indent`
+/// - `[[bold]]` and `[* bold]` are written in `BoldStyle`
+#[derive(Default)]
+pub struct ScrapboxFormatPass {
+    pub bold: BoldStyle,
+}
+
+impl Visitor for ScrapboxFormatPass {
+    fn visit_list<'a>(&mut self, value: &mut List<'a>) -> Option<TransformCommand<'a>> {
+        walk_list(self, value)
+    }
+
+    fn visit_list_item(&mut self, value: &mut ListItem<'_>) {
+        value.indent = None;
+        walk_list_item(self, value);
+    }
+
+    fn visit_emphasis<'a>(&mut self, value: &Emphasis<'a>) -> Option<TransformCommand<'a>> {
+        let mut emphasis = Emphasis {
+            double_bracket: false,
+            ..value.clone()
+        };
+        emphasis.double_bracket = match self.bold {
+            BoldStyle::Keep => return None,
+            BoldStyle::Asterisk => false,
+            // `[[text]]` can't have other decorations
+            BoldStyle::DoubleBracket => emphasis.marks() == "*",
+        };
+        Some(TransformCommand::Replace(NodeKind::Emphasis(emphasis)))
+    }
+}
+
 pub struct ScrapboxPrinterConfig {
     pub indent: String,
+    /// `ScrapboxPass::h1_level` for a heading which is left in the page
     pub h1_mapping: usize,
 }
 
@@ -10,7 +94,7 @@ impl Default for ScrapboxPrinterConfig {
     fn default() -> Self {
         Self {
             indent: "\t".into(),
-            h1_mapping: 4,
+            h1_mapping: ScrapboxPass::default().h1_level,
         }
     }
 }
@@ -18,6 +102,8 @@ impl Default for ScrapboxPrinterConfig {
 pub struct ScrapboxPrinter {
     document: String,
     config: ScrapboxPrinterConfig,
+    /// level of the list item being printed
+    level: usize,
 }
 
 impl ScrapboxPrinter {
@@ -25,29 +111,53 @@ impl ScrapboxPrinter {
         Self {
             document: String::new(),
             config,
+            level: 0,
         }
     }
 
-    pub fn generate(&mut self, page: &mut Page) -> String {
+    pub fn generate(&mut self, page: &mut Page<'_>) -> String {
+        // the first line of a page is its title
+        if let Some(title) = &page.title {
+            self.document.push_str(&format!("{}\n", title));
+        }
         self.visit(page);
+        if !page.tags.is_empty() {
+            let tags: Vec<String> = page.tags.iter().map(|tag| hashtag(tag, false)).collect();
+            self.document.push_str(&format!("{}\n", tags.join(" ")));
+        }
         self.document.clone()
     }
 }
 
+/// `#tag` or `#[multi word tag]`
+fn hashtag(value: &str, bracketed: bool) -> String {
+    if bracketed || value.contains(char::is_whitespace) {
+        format!("#[{}]", value)
+    } else {
+        format!("#{}", value)
+    }
+}
+
 impl Visitor for ScrapboxPrinter {
-    fn visit_paragraph(&mut self, value: &mut Paragraph) -> Option<TransformCommand> {
+    fn visit_paragraph<'a>(&mut self, value: &mut Paragraph<'a>) -> Option<TransformCommand<'a>> {
         walk_paragraph(self, value);
-        self.document.push_str("\n");
+        self.document.push('\n');
         None
     }
 
-    fn visit_list(&mut self, value: &mut List) -> Option<TransformCommand> {
+    fn visit_list<'a>(&mut self, value: &mut List<'a>) -> Option<TransformCommand<'a>> {
         let mut number = 1;
         for item in value.children.iter_mut() {
-            let indent = self.config.indent.repeat(item.level + 1); // TODO(tkat0): consistency
+            let indent = item
+                .indent
+                .as_deref()
+                .map_or_else(|| self.config.indent.repeat(item.level), str::to_string);
             match &item.kind {
-                ListKind::Disc => self.document.push_str(&format!("{}", indent)),
-                ListKind::Decimal => self.document.push_str(&format!("{}{}. ", indent, number)),
+                ListKind::Disc => self.document.push_str(&indent),
+                ListKind::Decimal => match &item.number {
+                    Some(written) => self.document.push_str(&format!("{}{}. ", indent, written)),
+                    None => self.document.push_str(&format!("{}{}. ", indent, number)),
+                },
                 _ => {}
             }
 
@@ -57,103 +167,178 @@ impl Visitor for ScrapboxPrinter {
                 number = 1; // reset
             }
 
+            self.level = item.level;
             for node in item.children.iter_mut() {
                 self.visit_node(node);
             }
-            self.document.push_str("\n");
+            self.level = 0;
+            // `code:` and `table:` end with their last line
+            let block = item.children.last().is_some_and(|node| {
+                matches!(node.kind, NodeKind::CodeBlock(_) | NodeKind::Table(_))
+            });
+            if !block {
+                self.document.push('\n');
+            }
         }
         None
     }
 
-    fn visit_hashtag(&mut self, value: &HashTag) -> Option<TransformCommand> {
-        self.document.push_str(&format!("#{}", value.value));
+    fn visit_hashtag<'a>(&mut self, value: &HashTag<'a>) -> Option<TransformCommand<'a>> {
+        self.document
+            .push_str(&hashtag(&value.value, value.bracketed));
         None
     }
 
-    fn visit_internal_link(&mut self, value: &InternalLink) -> Option<TransformCommand> {
-        self.document.push_str(&format!("[{}]", value.title));
+    fn visit_internal_link<'a>(
+        &mut self,
+        value: &InternalLink<'a>,
+    ) -> Option<TransformCommand<'a>> {
+        // Scrapbox doesn't have a label of a link
+        match &value.anchor {
+            Some(anchor) => self
+                .document
+                .push_str(&format!("[{}#{}]", value.title, anchor)),
+            None => self.document.push_str(&format!("[{}]", value.title)),
+        }
         None
     }
 
-    fn visit_external_link(&mut self, value: &ExternalLink) -> Option<TransformCommand> {
+    fn visit_external_link<'a>(
+        &mut self,
+        value: &ExternalLink<'a>,
+    ) -> Option<TransformCommand<'a>> {
         if let Some(title) = &value.title {
+            // [/help-jp/Scrapbox]
+            if title.starts_with('/') && value.url == format!("https://scrapbox.io{}", title) {
+                self.document.push_str(&format!("[{}]", title));
+                return None;
+            }
             self.document
                 .push_str(&format!("[{} {}]", title, value.url));
+        } else if value.plain {
+            self.document.push_str(&value.url);
         } else {
             self.document.push_str(&format!("[{}]", value.url));
         }
         None
     }
 
-    fn visit_emphasis(&mut self, value: &Emphasis) -> Option<TransformCommand> {
-        self.document.push_str("[");
-        if value.bold > 0 {
-            self.document.push_str("*");
-        }
-        if value.italic > 0 {
-            self.document.push_str("/");
-        }
-        if value.strikethrough > 0 {
-            self.document.push_str("-");
+    fn visit_emphasis<'a>(&mut self, value: &Emphasis<'a>) -> Option<TransformCommand<'a>> {
+        if value.double_bracket {
+            self.document.push_str(&format!("[[{}]]", value.text));
+            return None;
         }
-        self.document.push_str(&format!(" {}]", value.text));
+        self.document
+            .push_str(&format!("[{} {}]", value.marks(), value.text));
         None
     }
 
-    fn visit_heading(&mut self, value: &Heading) -> Option<TransformCommand> {
-        let level = if self.config.h1_mapping + 1 > value.level {
-            self.config.h1_mapping - value.level + 1
-        } else {
-            1
+    fn visit_heading<'a>(&mut self, value: &Heading<'a>) -> Option<TransformCommand<'a>> {
+        let pass = ScrapboxPass {
+            h1_level: self.config.h1_mapping,
         };
+        let level = pass.bold_level(value.level);
         self.document
-            .push_str(&format!("[{} {}]\n", "*".repeat(level), value.text));
+            .push_str(&format!("[{} {}]", "*".repeat(level), value.text));
         None
     }
 
-    fn visit_block_quate(&mut self, value: &BlockQuate) -> Option<TransformCommand> {
+    fn visit_block_quate<'a>(&mut self, value: &BlockQuate<'a>) -> Option<TransformCommand<'a>> {
         self.document.push_str(&format!("`{}`", value.value));
         None
     }
 
-    fn visit_code_block(&mut self, value: &CodeBlock) -> Option<TransformCommand> {
+    fn visit_code_block<'a>(&mut self, value: &CodeBlock<'a>) -> Option<TransformCommand<'a>> {
+        // lines of a code block in a list are indented by spaces
+        let prefix = " ".repeat(self.level + 1);
         self.document
             .push_str(&format!("code:{}\n", value.file_name));
         for code in &value.children {
-            self.document.push_str(&format!(" {}\n", code));
+            self.document.push_str(&format!("{}{}\n", prefix, code));
         }
         None
     }
 
-    fn visit_table(&mut self, value: &Table) -> Option<TransformCommand> {
+    fn visit_table<'a>(&mut self, value: &Table<'a>) -> Option<TransformCommand<'a>> {
+        // `table:name` without rows
+        if value.header.is_empty() && value.rows.is_empty() {
+            self.document.push_str(&format!("table:{}\n", value.name));
+            return None;
+        }
         if value.header.is_empty() {
             return None;
         }
 
+        let prefix = " ".repeat(self.level + 1);
         self.document.push_str(&format!("table:{}\n", value.name));
         self.document
-            .push_str(&format!(" {}\n", value.header.join("\t")));
+            .push_str(&format!("{}{}\n", prefix, value.header.join("\t")));
         for row in &value.rows {
             if row.is_empty() {
                 break;
             }
-            self.document.push_str(&format!(" {}\n", row.join("\t")));
+            self.document
+                .push_str(&format!("{}{}\n", prefix, row.join("\t")));
         }
         None
     }
 
-    fn visit_image(&mut self, value: &Image) -> Option<TransformCommand> {
-        self.document.push_str(&format!("[{}]", value.uri));
+    fn visit_image<'a>(&mut self, value: &Image<'a>) -> Option<TransformCommand<'a>> {
+        match &value.link {
+            Some(link) => self.document.push_str(&format!("[{} {}]", value.uri, link)),
+            None => self.document.push_str(&format!("[{}]", value.uri)),
+        }
+        None
+    }
+
+    fn visit_embed<'a>(&mut self, value: &Embed<'a>) -> Option<TransformCommand<'a>> {
+        self.document.push_str(&format!("[{}]", value.url));
+        None
+    }
+
+    fn visit_location<'a>(&mut self, value: &Location<'a>) -> Option<TransformCommand<'a>> {
+        let ns = if value.latitude < 0.0 { 'S' } else { 'N' };
+        let ew = if value.longitude < 0.0 { 'W' } else { 'E' };
+        self.document.push_str(&format!(
+            "[{}{},{}{},Z{}",
+            ns,
+            value.latitude.abs(),
+            ew,
+            value.longitude.abs(),
+            value.zoom
+        ));
+        if let Some(label) = &value.label {
+            self.document.push_str(&format!(" {}", label));
+        }
+        self.document.push(']');
         None
     }
 
-    fn visit_math(&mut self, value: &Math) -> Option<TransformCommand> {
+    fn visit_math<'a>(&mut self, value: &Math<'a>) -> Option<TransformCommand<'a>> {
         self.document.push_str(&format!("[${}]", value.value));
         None
     }
 
-    fn visit_text(&mut self, value: &Text) -> Option<TransformCommand> {
-        self.document.push_str(&format!("{}", value.value));
+    fn visit_callout<'a>(&mut self, value: &mut Callout<'a>) -> Option<TransformCommand<'a>> {
+        self.document.push_str(&format!("> [* {}]", value.kind));
+        if let Some(title) = &value.title {
+            self.document.push_str(&format!(" {}", title));
+        }
+        self.document.push('\n');
+        for node in value.children.iter_mut() {
+            self.document.push_str("> ");
+            self.visit_node(node);
+        }
+        None
+    }
+
+    fn visit_comment<'a>(&mut self, _value: &Comment<'a>) -> Option<TransformCommand<'a>> {
+        // NOTE: Scrapbox doesn't have comments
+        None
+    }
+
+    fn visit_text<'a>(&mut self, value: &Text<'a>) -> Option<TransformCommand<'a>> {
+        self.document.push_str(&value.value);
         None
     }
 }
@@ -161,35 +346,115 @@ impl Visitor for ScrapboxPrinter {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::parser::markdown::{self, MarkdownParserContext};
+    use crate::parser::scrapbox::{page, ScrapboxParserContext};
+    use crate::parser::Span;
+    use crate::visitor::markdown_printer::{MarkdownPass, MarkdownPrinter, MarkdownPrinterConfig};
     use indoc::indoc;
+    use proptest::prelude::*;
+    use rstest::rstest;
+
+    fn roundtrip(input: &str) -> String {
+        let (_, mut page) = page(Span::new_extra(input, ScrapboxParserContext::default())).unwrap();
+        ScrapboxPrinter::new(ScrapboxPrinterConfig::default()).generate(&mut page)
+    }
 
-    // #[test]
-    // fn pass_test() {
-    //     let mut pass = ScrapboxPass::default();
-
-    //     assert_eq!(
-    //         pass.visit_emphasis(&mut Emphasis::bold_level("text", 3)),
-    //         Some(TransformCommand::Replace(NodeKind::Heading(Heading::new(
-    //             "text", 1
-    //         ))))
-    //     );
-    // }
-
-    // #[test]
-    // fn pass_bold_to_h_test() {
-    //     let mut pass = ScrapboxPass {
-    //         h1_level: 3,
-    //         bold_to_h: true,
-    //     };
-
-    //     // TODO(tkat0): not supoprted: `[*-/ mix]` -> `### *~~mix~~*` (but `### mix`)
-    //     assert_eq!(
-    //         pass.visit_emphasis(&mut Emphasis::bold_level("text", 1)),
-    //         Some(TransformCommand::Replace(NodeKind::Heading(Heading::new(
-    //             "text", 3
-    //         ))))
-    //     )
-    // }
+    #[rstest(
+        input,
+        case(include_str!("../../../../src/__test__/help-jp/Scrapboxの使い方_input.txt")),
+        case(include_str!("../../../../src/__test__/help-jp/コードブロック_input.txt"))
+    )]
+    fn roundtrip_fixture_test(input: &str) {
+        assert_eq!(roundtrip(input), input);
+    }
+
+    #[test]
+    fn generate_owned_test() {
+        let input = include_str!("../../../../src/__test__/help-jp/Scrapboxの使い方_input.txt");
+        let (_, page) = page(Span::new_extra(input, ScrapboxParserContext::default())).unwrap();
+        let mut owned = page.clone().into_owned();
+        let mut page = page;
+        assert_eq!(
+            ScrapboxPrinter::new(ScrapboxPrinterConfig::default()).generate(&mut owned),
+            ScrapboxPrinter::new(ScrapboxPrinterConfig::default()).generate(&mut page)
+        );
+        assert_eq!(
+            MarkdownPrinter::new(MarkdownPrinterConfig::default()).generate(&mut owned),
+            MarkdownPrinter::new(MarkdownPrinterConfig::default()).generate(&mut page)
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn roundtrip_emphasis_test(
+            marks in "[*/\\-_!#~%{}<>]{1,6}",
+            indent in "[\t ]{0,3}",
+        ) {
+            let input = format!("{}[{} text] and [[bold]]\n", indent, marks);
+            prop_assert_eq!(roundtrip(&input), input);
+        }
+    }
+
+    #[rstest(
+        h1_level,
+        level,
+        expected,
+        case(3, 1, 3),
+        case(3, 2, 2),
+        case(3, 3, 1),
+        case(3, 4, 1),
+        case(5, 1, 5)
+    )]
+    fn pass_test(h1_level: usize, level: usize, expected: usize) {
+        let mut pass = ScrapboxPass { h1_level };
+
+        assert_eq!(
+            pass.visit_heading(&Heading::new("text", level)),
+            Some(TransformCommand::Replace(NodeKind::Emphasis(
+                Emphasis::bold_level("text", expected)
+            )))
+        );
+    }
+
+    #[rstest(level, case(1), case(2), case(3), case(4))]
+    fn heading_test(level: usize) {
+        let page = || Page {
+            nodes: vec![Node::new(NodeKind::Heading(Heading::new("text", level)))],
+            ..Default::default()
+        };
+        let mut printed = page();
+        let mut passed = page();
+        ScrapboxPass::default().visit(&mut passed);
+
+        // the same level without the pass
+        assert_eq!(
+            ScrapboxPrinter::new(ScrapboxPrinterConfig::default()).generate(&mut printed),
+            ScrapboxPrinter::new(ScrapboxPrinterConfig::default()).generate(&mut passed)
+        );
+    }
+
+    #[rstest(
+        input,
+        bold_to_h,
+        case("[*** a]\n[** b]\n[* c]\n", true),
+        case("[*** a]\n[** b]\n[* c]\n", false)
+    )]
+    fn pass_symmetric_test(input: &str, bold_to_h: bool) {
+        let (_, mut page) = page(Span::new_extra(input, ScrapboxParserContext::default())).unwrap();
+        MarkdownPass {
+            h1_level: 3,
+            bold_to_h,
+        }
+        .visit(&mut page);
+        let markdown = MarkdownPrinter::new(MarkdownPrinterConfig::default()).generate(&mut page);
+
+        let (_, mut page) =
+            markdown::page(Span::new_extra(&markdown, MarkdownParserContext::default())).unwrap();
+        ScrapboxPass { h1_level: 3 }.visit(&mut page);
+        let scrapbox = ScrapboxPrinter::new(ScrapboxPrinterConfig::default()).generate(&mut page);
+
+        assert_eq!(scrapbox, input);
+    }
 
     #[test]
     fn codegen_test() {
@@ -197,14 +462,14 @@ mod test {
 
         let mut page = Page {
             nodes: vec![
+                Node::new(NodeKind::Paragraph(Paragraph::new(vec![Node::new(
+                    NodeKind::Heading(Heading::new("heading", 1)),
+                )]))),
                 Node::new(NodeKind::Paragraph(Paragraph::new(vec![
-                    Node::new(NodeKind::Heading(Heading::new("heading", 1))),
                     Node::new(NodeKind::Text(Text {
                         value: "abc ".into(),
                     })),
-                    Node::new(NodeKind::HashTag(HashTag {
-                        value: "tag".into(),
-                    })),
+                    Node::new(NodeKind::HashTag(HashTag::new("tag"))),
                     Node::new(NodeKind::Text(Text { value: " ".into() })),
                     Node::new(NodeKind::ExternalLink(ExternalLink::new(
                         Some("Rust"),
@@ -250,14 +515,15 @@ mod test {
                     NodeKind::Math(Math::new(r#"\frac{-b \pm \sqrt{b^2-4ac}}{2a}"#)),
                 )]))),
             ],
+            ..Default::default()
         };
 
         let scrapbox = visitor.generate(&mut page);
 
         let expected = indoc! {"
-            [**** heading]
+            [*** heading]
             abc #tag [Rust https://www.rust-lang.org/]
-            \t\t\tabc
+            \t\tabc
             code:hello.rs
              fn main() {
                  println(\"Hello, World!\");
@@ -277,4 +543,124 @@ mod test {
 
         assert_eq!(scrapbox, expected)
     }
+
+    #[test]
+    fn codegen_obsidian_test() {
+        let mut visitor = ScrapboxPrinter::new(ScrapboxPrinterConfig::default());
+
+        let mut page = Page {
+            nodes: vec![
+                Node::new(NodeKind::Paragraph(Paragraph::new(vec![
+                    Node::new(NodeKind::InternalLink(InternalLink {
+                        title: "page".into(),
+                        anchor: Some("heading".into()),
+                        label: Some("label".into()),
+                        embed: true,
+                    })),
+                    Node::new(NodeKind::Comment(Comment::new("comment"))),
+                    Node::new(NodeKind::Text(Text::new(" "))),
+                    Node::new(NodeKind::HashTag(HashTag::bracketed("tag"))),
+                ]))),
+                Node::new(NodeKind::Callout(Callout::new(
+                    "info",
+                    Some("title"),
+                    vec![Node::new(NodeKind::Paragraph(Paragraph::new(vec![
+                        Node::new(NodeKind::Text(Text::new("body"))),
+                    ])))],
+                ))),
+            ],
+            ..Default::default()
+        };
+
+        let scrapbox = visitor.generate(&mut page);
+
+        let expected = indoc! {"
+            [page#heading] #[tag]
+            > [* info] title
+            > body
+        "};
+
+        assert_eq!(scrapbox, expected)
+    }
+
+    #[test]
+    fn codegen_metadata_test() {
+        let mut visitor = ScrapboxPrinter::new(ScrapboxPrinterConfig::default());
+
+        let mut page = Page {
+            title: Some("title".into()),
+            tags: vec!["tag1".into(), "tag 2".into()],
+            nodes: vec![Node::new(NodeKind::Paragraph(Paragraph::new(vec![
+                Node::new(NodeKind::Text(Text::new("abc"))),
+            ])))],
+            ..Default::default()
+        };
+
+        assert_eq!(visitor.generate(&mut page), "title\nabc\n#tag1 #[tag 2]\n");
+    }
+
+    #[test]
+    fn codegen_image_test() {
+        let mut visitor = ScrapboxPrinter::new(ScrapboxPrinterConfig::default());
+
+        let mut page = Page {
+            nodes: vec![Node::new(NodeKind::Paragraph(Paragraph::new(vec![
+                Node::new(NodeKind::Image(Image::with_link(
+                    "https://i.gyazo.com/da78df293f9e83a74b5402411e2f2e01.png",
+                    "http://cutedog.com",
+                ))),
+            ])))],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            visitor.generate(&mut page),
+            "[https://i.gyazo.com/da78df293f9e83a74b5402411e2f2e01.png http://cutedog.com]\n"
+        );
+    }
+
+    #[test]
+    fn codegen_embed_test() {
+        let mut visitor = ScrapboxPrinter::new(ScrapboxPrinterConfig::default());
+
+        let mut page = Page {
+            nodes: vec![Node::new(NodeKind::Paragraph(Paragraph::new(vec![
+                Node::new(NodeKind::Embed(Embed::new(
+                    EmbedProvider::YouTube,
+                    "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+                ))),
+            ])))],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            visitor.generate(&mut page),
+            "[https://www.youtube.com/watch?v=dQw4w9WgXcQ]\n"
+        );
+    }
+
+    #[test]
+    fn codegen_location_test() {
+        let mut visitor = ScrapboxPrinter::new(ScrapboxPrinterConfig::default());
+
+        let mut page = Page {
+            nodes: vec![Node::new(NodeKind::Paragraph(Paragraph::new(vec![
+                Node::new(NodeKind::Location(Location::new(
+                    35.6812,
+                    139.7671,
+                    14,
+                    Some("Tokyo Station"),
+                ))),
+                Node::new(NodeKind::Location(Location::new(
+                    -33.8568, -151.2153, 5, None,
+                ))),
+            ])))],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            visitor.generate(&mut page),
+            "[N35.6812,E139.7671,Z14 Tokyo Station][S33.8568,W151.2153,Z5]\n"
+        );
+    }
 }
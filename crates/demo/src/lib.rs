@@ -1,17 +1,24 @@
 use scrapbox_converter_core::{
+    ast::Page,
+    document::Document,
+    format::{format_scrapbox, FormatConfig},
     parser::{
         markdown,
         markdown::{MarkdownParserConfig, MarkdownParserContext},
-        scrapbox::{self, ScrapboxParserContext},
+        org::{self, OrgParserConfig, OrgParserContext},
+        scrapbox::{self, ScrapboxParserConfig, ScrapboxParserContext},
         Span,
     },
+    schema::VersionedPage,
     visitor::{
         markdown_printer::{MarkdownPass, MarkdownPrinter, MarkdownPrinterConfig},
-        scrapbox_printer::{ScrapboxPrinter, ScrapboxPrinterConfig},
+        org_printer::{OrgPrinter, OrgPrinterConfig},
+        scrapbox_printer::{ScrapboxPass, ScrapboxPrinter, ScrapboxPrinterConfig},
         Visitor,
     },
-    Config,
+    Config, SourceFormat,
 };
+use std::sync::Arc;
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen(typescript_custom_section)]
@@ -19,70 +26,286 @@ const TS_APPEND_CONTENT: &'static str = r#"
 
 export type IndentKind = {type: "Tab"} | {type: "Space", size: number};
 
+export type MarkdownFlavor = "Generic" | "Obsidian";
+
+export type TitleStyle = "Heading" | "FrontMatter" | "Hidden";
+
+export type TagNormalization = "Underscore" | "Hyphen" | "Nested";
+
+export type EmbedStyle = "Embed" | "Html" | "Link";
+
+export type LocationStyle = "OpenStreetMap" | "GeoUri" | "FrontMatter";
+
+export type DecorationFallback = "Html" | "Plain";
+
+export type AstFormat = "Json" | "PrettyJson" | "Yaml";
+
+export type SourceFormat = "Scrapbox" | "Markdown" | "Org";
+
+export type IndentChar = "Tab" | "Space" | "FullWidthSpace";
+
+export type BoldStyle = "Keep" | "Asterisk" | "DoubleBracket";
+
+export interface FormatConfig {
+  /** if true, the first line is the title of the page (default: false) */
+  title?: boolean;
+  /** indent of lists (default: "Tab") */
+  indent?: IndentChar;
+  /** how to write `[* bold]` and `[[bold]]` (default: "Asterisk") */
+  bold?: BoldStyle;
+}
+
+export interface MediaConfig {
+  /** e.g. ["png", "jpg"], compared case-insensitively */
+  imageExtensions?: string[];
+  /** URL prefixes which always point to an image, e.g. ["https://gyazo.com/"] */
+  imageHosts?: string[];
+  videoExtensions?: string[];
+  audioExtensions?: string[];
+}
+
 export interface Config {
-  /** Maps which bold level of Scrapbox to heading of Markdown */
+  /** Maps which bold level of Scrapbox to heading 1 of Markdown, and back */
   heading1Mapping: number;
   /** Maps bold of Scrapbox to the minimum level of heading of Markdown */
   boldToHeading: boolean;
-  /** indent of markdown list */
+  /** indent of a nested list of Markdown and Org */
   indent: IndentKind;
+  /** dialect of markdown to read and write (default: "Generic") */
+  markdownFlavor?: MarkdownFlavor;
+  /** if set, the first line of Scrapbox is the title of the page */
+  title?: TitleStyle;
+  /** if true, the lines of hashtags at the end of Scrapbox are the tags of the page (default: false) */
+  tags?: boolean;
+  /** how to write a tag with spaces like `#[multi word tag]` (default: "Underscore") */
+  tagNormalization?: TagNormalization;
+  /** how to write YouTube, tweets, videos and so on (default: "Link") */
+  embedStyle?: EmbedStyle;
+  /** how to write a map pin like `[N35.68,E139.76,Z14]` (default: "OpenStreetMap") */
+  locationStyle?: LocationStyle;
+  /** how to write decorations Markdown doesn't have like `[_ underline]` (default: "Html") */
+  decorationFallback?: DecorationFallback;
+  /** output of `toAST` (default: "Yaml") */
+  astFormat?: AstFormat;
+  /** which URLs are images, videos and audio (default: the common extensions) */
+  media?: MediaConfig;
 }
 
 export function scrapboxToMarkdown(input: string, config: Config): string;
-export function scrapboxToAST(input: string, config: Config): string;
 export function markdownToScrapbox(input: string, config: Config): string;
-export function markdownToAST(input: string, config: Config): string;
+export function scrapboxToOrg(input: string, config: Config): string;
+export function orgToScrapbox(input: string, config: Config): string;
+/** AST of `input` written in `config.astFormat` (see `schema/ast.schema.json`) */
+export function toAST(input: string, sourceFormat: SourceFormat, config: Config): string;
+/** `input` is the output of `toAST` in YAML or JSON */
+export function astToScrapbox(input: string, config: Config): string;
+export function astToMarkdown(input: string, config: Config): string;
+/** `input` rewritten in one style. The AST of the page doesn't change. */
+export function formatScrapbox(input: string, config: FormatConfig): string;
 "#;
 
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "Config")]
+    pub type JsConfig;
+}
+
 #[wasm_bindgen(js_name = scrapboxToMarkdown, skip_typescript)]
-pub fn scrapbox_to_markdown(input: &str, config: &JsValue) -> Result<String, JsError> {
-    let config: Config = config.into_serde()?;
-    let (_, mut p) = scrapbox::page(Span::new_extra(input, ScrapboxParserContext::default()))?;
+pub fn scrapbox_to_markdown(input: &str, config: JsValue) -> Result<String, JsError> {
+    let config: Config = serde_wasm_bindgen::from_value(config)?;
+    let context = scrapbox_parser_context(&config);
+    let (_, p) = scrapbox::page(Span::new_extra(input, context))?;
+    Ok(page_to_markdown(p, &config))
+}
+
+/// A page of Scrapbox which is edited in a form.
+/// It parses again only the lines edited since the last conversion.
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct ScrapboxDocument {
+    document: Option<Document>,
+}
+
+#[wasm_bindgen]
+impl ScrapboxDocument {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> ScrapboxDocument {
+        ScrapboxDocument::default()
+    }
+
+    #[wasm_bindgen(js_name = scrapboxToMarkdown)]
+    pub fn scrapbox_to_markdown(
+        &mut self,
+        input: &str,
+        config: JsConfig,
+    ) -> Result<String, JsError> {
+        let config: Config = serde_wasm_bindgen::from_value(config.into())?;
+        let parser_config = scrapbox_parser_context(&config).config;
+        let document = match &mut self.document {
+            Some(document) if *document.config() == parser_config => {
+                document.set_text(input);
+                document
+            }
+            document => document.insert(Document::new(input, parser_config)),
+        };
+        Ok(page_to_markdown(document.page(), &config))
+    }
+}
+
+fn page_to_markdown(mut p: Page, config: &Config) -> String {
     let mut pass = MarkdownPass {
         h1_level: config.heading1_mapping,
         bold_to_h: config.bold_to_heading,
     };
     pass.visit(&mut p);
-    let mut visitor = MarkdownPrinter::new(MarkdownPrinterConfig::default());
+    let mut visitor = MarkdownPrinter::new(markdown_printer_config(config));
+    visitor.generate(&mut p)
+}
+
+fn scrapbox_parser_context(config: &Config) -> ScrapboxParserContext {
+    ScrapboxParserContext {
+        config: ScrapboxParserConfig {
+            title: config.title.is_some(),
+            tags: config.tags,
+            media: Arc::new(config.media.clone()),
+        },
+        ..Default::default()
+    }
+}
+
+fn markdown_parser_context(config: &Config) -> MarkdownParserContext {
+    MarkdownParserContext {
+        config: MarkdownParserConfig {
+            flavor: config.markdown_flavor,
+            media: Arc::new(config.media.clone()),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+fn org_parser_context(config: &Config) -> OrgParserContext {
+    OrgParserContext {
+        config: OrgParserConfig {
+            media: Arc::new(config.media.clone()),
+        },
+        ..Default::default()
+    }
+}
+
+fn scrapbox_printer_config(config: &Config) -> ScrapboxPrinterConfig {
+    ScrapboxPrinterConfig {
+        h1_mapping: config.heading1_mapping,
+        ..Default::default()
+    }
+}
+
+fn org_printer_config(config: &Config) -> OrgPrinterConfig {
+    OrgPrinterConfig {
+        indent: config.indent.to_string(),
+    }
+}
+
+fn markdown_printer_config(config: &Config) -> MarkdownPrinterConfig {
+    MarkdownPrinterConfig {
+        flavor: config.markdown_flavor,
+        title: config.title.unwrap_or_default(),
+        tag_normalization: config.tag_normalization,
+        embed: config.embed_style,
+        location: config.location_style,
+        decoration: config.decoration_fallback,
+        media: Arc::new(config.media.clone()),
+        ..Default::default()
+    }
+}
+
+#[wasm_bindgen(js_name = markdownToScrapbox, skip_typescript)]
+pub fn markdown_to_scrapbox(input: &str, config: JsValue) -> Result<String, JsError> {
+    let config: Config = serde_wasm_bindgen::from_value(config)?;
+    let context = markdown_parser_context(&config);
+    let (_, mut p) = markdown::page(Span::new_extra(input, context))?;
+    let mut pass = ScrapboxPass {
+        h1_level: config.heading1_mapping,
+    };
+    pass.visit(&mut p);
+    let mut visitor = ScrapboxPrinter::new(scrapbox_printer_config(&config));
     Ok(visitor.generate(&mut p))
 }
 
-#[wasm_bindgen(js_name = scrapboxToAST, skip_typescript)]
-pub fn scrapbox_to_ast(input: &str, config: &JsValue) -> Result<String, JsError> {
-    let config: Config = config.into_serde()?;
-    let (_, mut p) = scrapbox::page(Span::new_extra(input, ScrapboxParserContext::default()))?;
+#[wasm_bindgen(js_name = scrapboxToOrg, skip_typescript)]
+pub fn scrapbox_to_org(input: &str, config: JsValue) -> Result<String, JsError> {
+    let config: Config = serde_wasm_bindgen::from_value(config)?;
+    let context = scrapbox_parser_context(&config);
+    let (_, mut p) = scrapbox::page(Span::new_extra(input, context))?;
     let mut pass = MarkdownPass {
         h1_level: config.heading1_mapping,
         bold_to_h: config.bold_to_heading,
     };
     pass.visit(&mut p);
-
-    // TODO: configurable
-    // serde_json::to_string_pretty(&p).map_err(JsError::from)
-    serde_yaml::to_string(&p).map_err(JsError::from)
+    let mut visitor = OrgPrinter::new(org_printer_config(&config));
+    Ok(visitor.generate(&mut p))
 }
 
-#[wasm_bindgen(js_name = markdownToScrapbox, skip_typescript)]
-pub fn markdown_to_scrapbox(input: &str, config: &JsValue) -> Result<String, JsError> {
-    let config: Config = config.into_serde()?;
-    let context = MarkdownParserContext {
-        config: MarkdownParserConfig {},
-        ..Default::default()
+#[wasm_bindgen(js_name = orgToScrapbox, skip_typescript)]
+pub fn org_to_scrapbox(input: &str, config: JsValue) -> Result<String, JsError> {
+    let config: Config = serde_wasm_bindgen::from_value(config)?;
+    let (_, mut p) = org::page(Span::new_extra(input, org_parser_context(&config)))?;
+    let mut pass = ScrapboxPass {
+        h1_level: config.heading1_mapping,
     };
-    let (_, mut p) = markdown::page(Span::new_extra(input, context))?;
-    let mut visitor = ScrapboxPrinter::new(ScrapboxPrinterConfig::default());
+    pass.visit(&mut p);
+    let mut visitor = ScrapboxPrinter::new(scrapbox_printer_config(&config));
     Ok(visitor.generate(&mut p))
 }
 
-#[wasm_bindgen(js_name = markdownToAST, skip_typescript)]
-pub fn markdown_to_ast(input: &str, config: &JsValue) -> Result<String, JsError> {
-    let config: Config = config.into_serde()?;
-    let context = MarkdownParserContext {
-        config: MarkdownParserConfig {},
-        ..Default::default()
+#[wasm_bindgen(js_name = toAST, skip_typescript)]
+pub fn to_ast(input: &str, source_format: JsValue, config: JsValue) -> Result<String, JsError> {
+    let source_format: SourceFormat = serde_wasm_bindgen::from_value(source_format)?;
+    let config: Config = serde_wasm_bindgen::from_value(config)?;
+    let p = match source_format {
+        SourceFormat::Scrapbox => {
+            let context = scrapbox_parser_context(&config);
+            let (_, mut p) = scrapbox::page(Span::new_extra(input, context))?;
+            let mut pass = MarkdownPass {
+                h1_level: config.heading1_mapping,
+                bold_to_h: config.bold_to_heading,
+            };
+            pass.visit(&mut p);
+            p
+        }
+        SourceFormat::Markdown => {
+            let context = markdown_parser_context(&config);
+            let (_, p) = markdown::page(Span::new_extra(input, context))?;
+            p
+        }
+        SourceFormat::Org => {
+            let (_, p) = org::page(Span::new_extra(input, org_parser_context(&config)))?;
+            p
+        }
     };
-    let (_, p) = markdown::page(Span::new_extra(input, context))?;
-    Ok(format!("{:#?}", &p))
+    Ok(VersionedPage::new(p).to_format(config.ast_format))
+}
+
+#[wasm_bindgen(js_name = astToScrapbox, skip_typescript)]
+pub fn ast_to_scrapbox(input: &str, config: JsValue) -> Result<String, JsError> {
+    let config: Config = serde_wasm_bindgen::from_value(config)?;
+    let mut p = VersionedPage::parse(input)?.page;
+    let mut visitor = ScrapboxPrinter::new(scrapbox_printer_config(&config));
+    Ok(visitor.generate(&mut p))
+}
+
+#[wasm_bindgen(js_name = astToMarkdown, skip_typescript)]
+pub fn ast_to_markdown(input: &str, config: JsValue) -> Result<String, JsError> {
+    let config: Config = serde_wasm_bindgen::from_value(config)?;
+    let mut p = VersionedPage::parse(input)?.page;
+    let mut visitor = MarkdownPrinter::new(markdown_printer_config(&config));
+    Ok(visitor.generate(&mut p))
+}
+
+#[wasm_bindgen(js_name = formatScrapbox, skip_typescript)]
+pub fn format_scrapbox_js(input: &str, config: JsValue) -> Result<String, JsError> {
+    let config: FormatConfig = serde_wasm_bindgen::from_value(config)?;
+    Ok(format_scrapbox(input, &config)?)
 }
 
 #[wasm_bindgen(start)]
@@ -0,0 +1,416 @@
+use std::ops::Range;
+
+use scrapbox_converter_core::{
+    ast::*,
+    parser::{
+        scrapbox::{self, ScrapboxParserConfig, ScrapboxParserContext},
+        source_offset, Span,
+    },
+    visitor::{walk_list, walk_list_item, TransformCommand, Visitor},
+};
+
+/// The first line of a file is the title of the page, like Scrapbox.
+pub fn parser_config() -> ScrapboxParserConfig {
+    ScrapboxParserConfig {
+        title: true,
+        ..Default::default()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ItemKind {
+    /// A line which only has `[* bold]`. The level is the number of `*`.
+    Heading {
+        level: usize,
+        text: String,
+    },
+    Emphasis,
+    InternalLink {
+        title: String,
+    },
+    ExternalLink,
+    HashTag {
+        value: String,
+    },
+    BlockQuate,
+    /// From `code:` to the last line of the code
+    CodeBlock {
+        file_name: String,
+    },
+    /// `code:file_name` and `table:name`
+    BlockHeader,
+    CodeLine,
+    /// From `table:` to the last row
+    Table {
+        name: String,
+    },
+    Math,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Item {
+    pub kind: ItemKind,
+    /// Byte range in the text
+    pub range: Range<usize>,
+}
+
+#[derive(Debug, Default)]
+pub struct Analysis {
+    pub title: Option<String>,
+    /// In the order of the text
+    pub items: Vec<Item>,
+}
+
+impl Analysis {
+    pub fn hashtags(&self) -> impl Iterator<Item = &str> {
+        self.items.iter().filter_map(|item| match &item.kind {
+            ItemKind::HashTag { value } => Some(value.as_str()),
+            _ => None,
+        })
+    }
+
+    /// The innermost item at `offset`
+    pub fn item_at(&self, offset: usize) -> Option<&Item> {
+        self.items
+            .iter()
+            .filter(|item| item.range.contains(&offset))
+            .min_by_key(|item| item.range.len())
+    }
+}
+
+pub fn analyze(text: &str) -> Analysis {
+    let context = ScrapboxParserContext {
+        config: parser_config(),
+        ..Default::default()
+    };
+    // `lint_scrapbox` reports the text the parser couldn't read
+    let (_, mut page) = match scrapbox::page(Span::new_extra(text, context)) {
+        Ok(ok) => ok,
+        Err(_) => return Analysis::default(),
+    };
+
+    let mut collector = Collector {
+        text,
+        level: 0,
+        items: vec![],
+    };
+    for node in page.nodes.iter() {
+        if let Some(heading) = collector.heading(node) {
+            collector.items.push(heading);
+        }
+    }
+    collector.visit(&mut page);
+
+    let mut items = collector.items;
+    items.sort_by_key(|item| (item.range.start, std::cmp::Reverse(item.range.end)));
+    Analysis {
+        title: page.title.map(|title| title.into_owned()),
+        items,
+    }
+}
+
+/// Finds the range of the nodes by `source_offset` of their text.
+struct Collector<'t> {
+    text: &'t str,
+    /// level of the list item being visited
+    level: usize,
+    items: Vec<Item>,
+}
+
+impl Collector<'_> {
+    fn offset(&self, s: &str) -> Option<usize> {
+        source_offset(self.text, s)
+    }
+
+    /// `[` and `]` in the line around `start..end`
+    fn brackets_around(&self, start: usize, end: usize) -> Option<Range<usize>> {
+        let line_start = self.text[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = self.text[end..]
+            .find('\n')
+            .map_or(self.text.len(), |i| end + i);
+        let open = self.text[line_start..start].rfind('[')?;
+        let close = self.text[end..line_end].find(']')?;
+        Some(line_start + open..end + close + 1)
+    }
+
+    fn push(&mut self, kind: ItemKind, range: Range<usize>) {
+        self.items.push(Item { kind, range });
+    }
+
+    /// A paragraph of only `[* bold]`, with spaces around it
+    fn heading(&self, node: &Node<'_>) -> Option<Item> {
+        let paragraph = match &node.kind {
+            NodeKind::Paragraph(paragraph) => paragraph,
+            _ => return None,
+        };
+        let mut heading = None;
+        for child in paragraph.children.iter() {
+            match &child.kind {
+                NodeKind::Emphasis(emphasis) if heading.is_none() && emphasis.bold > 0 => {
+                    heading = Some(emphasis)
+                }
+                NodeKind::Text(text) if text.value.trim().is_empty() => {}
+                _ => return None,
+            }
+        }
+        let emphasis = heading?;
+        let start = self.offset(&emphasis.text)?;
+        let range = self.brackets_around(start, start + emphasis.text.len())?;
+        Some(Item {
+            kind: ItemKind::Heading {
+                level: emphasis.bold,
+                text: emphasis.text.to_string(),
+            },
+            range,
+        })
+    }
+}
+
+impl Visitor for Collector<'_> {
+    fn visit_list<'a>(&mut self, value: &mut List<'a>) -> Option<TransformCommand<'a>> {
+        walk_list(self, value)
+    }
+
+    fn visit_list_item(&mut self, value: &mut ListItem<'_>) {
+        self.level = value.level;
+        walk_list_item(self, value);
+        self.level = 0;
+    }
+
+    fn visit_hashtag<'a>(&mut self, value: &HashTag<'a>) -> Option<TransformCommand<'a>> {
+        if let Some(offset) = self.offset(&value.value) {
+            // `#` or `#[`, and `]`
+            let range = match value.bracketed {
+                true => offset - 2..offset + value.value.len() + 1,
+                false => offset - 1..offset + value.value.len(),
+            };
+            let value = value.value.to_string();
+            self.push(ItemKind::HashTag { value }, range);
+        }
+        None
+    }
+
+    fn visit_internal_link<'a>(
+        &mut self,
+        value: &InternalLink<'a>,
+    ) -> Option<TransformCommand<'a>> {
+        if let Some(offset) = self.offset(&value.title) {
+            let range = offset - 1..offset + value.title.len() + 1;
+            let title = value.title.to_string();
+            self.push(ItemKind::InternalLink { title }, range);
+        }
+        None
+    }
+
+    fn visit_external_link<'a>(
+        &mut self,
+        value: &ExternalLink<'a>,
+    ) -> Option<TransformCommand<'a>> {
+        let start = self.offset(&value.url)?;
+        let end = start + value.url.len();
+        let range = match value.plain {
+            true => Some(start..end),
+            false => self.brackets_around(start, end),
+        };
+        if let Some(range) = range {
+            self.push(ItemKind::ExternalLink, range);
+        }
+        None
+    }
+
+    fn visit_emphasis<'a>(&mut self, value: &Emphasis<'a>) -> Option<TransformCommand<'a>> {
+        let start = self.offset(&value.text)?;
+        let end = start + value.text.len();
+        let range = match value.double_bracket {
+            true => self
+                .brackets_around(start, end)
+                .map(|range| range.start - 1..range.end + 1),
+            false => self.brackets_around(start, end),
+        };
+        if let Some(range) = range {
+            self.push(ItemKind::Emphasis, range);
+        }
+        None
+    }
+
+    fn visit_block_quate<'a>(&mut self, value: &BlockQuate<'a>) -> Option<TransformCommand<'a>> {
+        let start = self.offset(&value.value)?;
+        let end = start + value.value.len();
+        // `$ command` has no backquotes
+        let range = match self.text[..start].ends_with('`') {
+            true => start - 1..end + 1,
+            false => start..end,
+        };
+        self.push(ItemKind::BlockQuate, range);
+        None
+    }
+
+    fn visit_code_block<'a>(&mut self, value: &CodeBlock<'a>) -> Option<TransformCommand<'a>> {
+        let file_name = self.offset(&value.file_name)?;
+        let start = file_name - "code:".len();
+        let mut end = file_name + value.file_name.len();
+        self.push(ItemKind::BlockHeader, start..end);
+        for line in value.children.iter() {
+            if let Some(offset) = self.offset(line) {
+                // the indent is not a part of the code
+                let indent = offset - self.level - 1;
+                end = offset + line.len();
+                self.push(ItemKind::CodeLine, indent..end);
+            }
+        }
+        let file_name = value.file_name.to_string();
+        self.push(ItemKind::CodeBlock { file_name }, start..end);
+        None
+    }
+
+    fn visit_table<'a>(&mut self, value: &Table<'a>) -> Option<TransformCommand<'a>> {
+        let name = self.offset(&value.name)?;
+        let start = name - "table:".len();
+        let mut end = name + value.name.len();
+        self.push(ItemKind::BlockHeader, start..end);
+        let cells = value.header.iter().chain(value.rows.iter().flatten());
+        for cell in cells {
+            if let Some(offset) = self.offset(cell) {
+                end = end.max(offset + cell.len());
+            }
+        }
+        let name = value.name.to_string();
+        self.push(ItemKind::Table { name }, start..end);
+        None
+    }
+
+    fn visit_math<'a>(&mut self, value: &Math<'a>) -> Option<TransformCommand<'a>> {
+        let start = self.offset(&value.value)?;
+        if let Some(range) = self.brackets_around(start, start + value.value.len()) {
+            self.push(ItemKind::Math, range);
+        }
+        None
+    }
+}
+
+/// What is being typed at the cursor
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompletionContext {
+    /// `[title`. The offset is after `[`.
+    Link(usize),
+    /// `#tag`. The offset is after `#`.
+    HashTag(usize),
+}
+
+pub fn completion_context(text: &str, offset: usize) -> Option<CompletionContext> {
+    let line_start = text[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let line = &text[line_start..offset];
+
+    if let Some(open) = line.rfind('[') {
+        if !line[open..].contains(']') {
+            return Some(CompletionContext::Link(line_start + open + 1));
+        }
+    }
+    let word = line.rfind([' ', '　', '\t']).map_or(0, |i| {
+        i + line[i..].chars().next().map_or(1, char::len_utf8)
+    });
+    line[word..]
+        .starts_with('#')
+        .then(|| CompletionContext::HashTag(line_start + word + 1))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use indoc::indoc;
+    use rstest::rstest;
+
+    #[test]
+    fn analyze_test() {
+        let text = indoc! {"
+            title
+            [** heading]
+            [link] #tag and `code` [https://example.com]
+             [[bold]] [$ x^2]
+            code:a.js
+             let a;
+            table:t
+             a\tb
+        "};
+        let analysis = analyze(text);
+        assert_eq!(analysis.title, Some("title".into()));
+
+        let items: Vec<(ItemKind, &str)> = analysis
+            .items
+            .iter()
+            .map(|item| (item.kind.clone(), &text[item.range.clone()]))
+            .collect();
+        let heading = ItemKind::Heading {
+            level: 2,
+            text: "heading".into(),
+        };
+        let code_block = ItemKind::CodeBlock {
+            file_name: "a.js".into(),
+        };
+        let table = ItemKind::Table { name: "t".into() };
+        assert_eq!(
+            items,
+            vec![
+                (heading, "[** heading]"),
+                (ItemKind::Emphasis, "[** heading]"),
+                (
+                    ItemKind::InternalLink {
+                        title: "link".into()
+                    },
+                    "[link]"
+                ),
+                (
+                    ItemKind::HashTag {
+                        value: "tag".into()
+                    },
+                    "#tag"
+                ),
+                (ItemKind::BlockQuate, "`code`"),
+                (ItemKind::ExternalLink, "[https://example.com]"),
+                (ItemKind::Emphasis, "[[bold]]"),
+                (ItemKind::Math, "[$ x^2]"),
+                (code_block, "code:a.js\n let a;"),
+                (ItemKind::BlockHeader, "code:a.js"),
+                (ItemKind::CodeLine, " let a;"),
+                (table, "table:t\n a\tb"),
+                (ItemKind::BlockHeader, "table:t"),
+            ]
+        );
+        assert_eq!(analysis.hashtags().collect::<Vec<_>>(), vec!["tag"]);
+    }
+
+    #[test]
+    fn item_at_test() {
+        let text = "title\n[link] #tag\n";
+        let analysis = analyze(text);
+        let kind = |offset| analysis.item_at(offset).map(|item| item.kind.clone());
+        assert_eq!(
+            kind(7),
+            Some(ItemKind::InternalLink {
+                title: "link".into()
+            })
+        );
+        assert_eq!(kind(12), None);
+        assert_eq!(
+            kind(14),
+            Some(ItemKind::HashTag {
+                value: "tag".into()
+            })
+        );
+    }
+
+    #[rstest(
+        text,
+        expected,
+        case("[", Some(CompletionContext::Link(1))),
+        case("abc [ti", Some(CompletionContext::Link(5))),
+        case("[link] ab", None),
+        case("#", Some(CompletionContext::HashTag(1))),
+        case("abc　#ta", Some(CompletionContext::HashTag(7))),
+        case("abc#ta", None),
+        case("[link]\n#t", Some(CompletionContext::HashTag(8)))
+    )]
+    fn completion_context_test(text: &str, expected: Option<CompletionContext>) {
+        assert_eq!(completion_context(text, text.len()), expected);
+    }
+}
@@ -0,0 +1,290 @@
+use lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionTextEdit, Diagnostic, DiagnosticSeverity,
+    DocumentSymbol, Location, NumberOrString, Position, Range, SemanticToken, SemanticTokenType,
+    SymbolKind, TextEdit, Url,
+};
+use scrapbox_converter_core::lint::{self, lint_scrapbox};
+
+use crate::analysis::{analyze, completion_context, parser_config, CompletionContext, ItemKind};
+use crate::line_index::LineIndex;
+use crate::workspace::Workspace;
+
+pub const SOURCE: &str = "scrapbox";
+
+/// The index is the `token_type` of `SemanticToken`
+pub const TOKEN_TYPES: &[SemanticTokenType] = &[
+    // decorations, `code:` and `table:`
+    SemanticTokenType::KEYWORD,
+    // `[internal link]`
+    SemanticTokenType::VARIABLE,
+    // `[https://…]`
+    SemanticTokenType::PROPERTY,
+    // `#tag`
+    SemanticTokenType::DECORATOR,
+    // `code` and the lines of `code:`
+    SemanticTokenType::STRING,
+    // `[$ math]`
+    SemanticTokenType::NUMBER,
+];
+
+fn token_type(kind: &ItemKind) -> Option<u32> {
+    match kind {
+        ItemKind::Emphasis | ItemKind::BlockHeader => Some(0),
+        ItemKind::InternalLink { .. } => Some(1),
+        ItemKind::ExternalLink => Some(2),
+        ItemKind::HashTag { .. } => Some(3),
+        ItemKind::BlockQuate | ItemKind::CodeLine => Some(4),
+        ItemKind::Math => Some(5),
+        // multiline, or the same range as `Emphasis`
+        ItemKind::Heading { .. } | ItemKind::CodeBlock { .. } | ItemKind::Table { .. } => None,
+    }
+}
+
+/// The lints, including the text the parser couldn't read
+pub fn diagnostics(text: &str) -> Vec<Diagnostic> {
+    let index = LineIndex::new(text);
+    lint_scrapbox(text, parser_config())
+        .into_iter()
+        .map(|d| Diagnostic {
+            range: index.range(d.start.offset..d.end.offset),
+            severity: Some(match d.severity {
+                lint::Severity::Error => DiagnosticSeverity::ERROR,
+                lint::Severity::Warning => DiagnosticSeverity::WARNING,
+            }),
+            code: Some(NumberOrString::String(d.rule.id().into())),
+            source: Some(SOURCE.into()),
+            message: d.message,
+            ..Default::default()
+        })
+        .collect()
+}
+
+/// Headings, and `code:` and `table:` in the heading before them.
+/// A heading with more `*` contains the headings with less `*` after it.
+pub fn document_symbols(text: &str) -> Vec<DocumentSymbol> {
+    let index = LineIndex::new(text);
+    let mut roots = vec![];
+    // open headings and their levels
+    let mut stack: Vec<(usize, DocumentSymbol)> = vec![];
+
+    fn close(
+        stack: &mut Vec<(usize, DocumentSymbol)>,
+        roots: &mut Vec<DocumentSymbol>,
+        end: Position,
+    ) {
+        if let Some((_, mut symbol)) = stack.pop() {
+            symbol.range.end = end;
+            match stack.last_mut() {
+                Some((_, parent)) => parent.children.get_or_insert_with(Vec::new).push(symbol),
+                None => roots.push(symbol),
+            }
+        }
+    }
+
+    for item in analyze(text).items {
+        let range = index.range(item.range.clone());
+        let (name, kind) = match item.kind {
+            ItemKind::Heading { level, text } => {
+                while matches!(stack.last(), Some((open, _)) if *open <= level) {
+                    close(&mut stack, &mut roots, range.start);
+                }
+                stack.push((level, symbol(text, SymbolKind::STRING, range)));
+                continue;
+            }
+            ItemKind::CodeBlock { file_name } => (format!("code:{}", file_name), SymbolKind::FILE),
+            ItemKind::Table { name } => (format!("table:{}", name), SymbolKind::ARRAY),
+            _ => continue,
+        };
+        let leaf = symbol(name, kind, range);
+        match stack.last_mut() {
+            Some((_, parent)) => parent.children.get_or_insert_with(Vec::new).push(leaf),
+            None => roots.push(leaf),
+        }
+    }
+    while !stack.is_empty() {
+        close(&mut stack, &mut roots, index.position(text.len()));
+    }
+    roots
+}
+
+fn symbol(name: String, kind: SymbolKind, range: Range) -> DocumentSymbol {
+    #[allow(deprecated)]
+    DocumentSymbol {
+        name,
+        detail: None,
+        kind,
+        tags: None,
+        deprecated: None,
+        range,
+        selection_range: range,
+        children: None,
+    }
+}
+
+/// The file of `[internal link]` or `#tag` at the position
+pub fn definition(text: &str, position: Position, workspace: &Workspace) -> Option<Location> {
+    let index = LineIndex::new(text);
+    let analysis = analyze(text);
+    let title = match &analysis.item_at(index.offset(position))?.kind {
+        ItemKind::InternalLink { title } => title,
+        ItemKind::HashTag { value } => value,
+        _ => return None,
+    };
+    let url = workspace.resolve(title)?;
+    Some(Location::new(url.clone(), Range::default()))
+}
+
+/// Titles after `[`, and hashtags and titles after `#`
+pub fn completion(text: &str, position: Position, workspace: &Workspace) -> Vec<CompletionItem> {
+    let index = LineIndex::new(text);
+    let offset = index.offset(position);
+    let (start, hashtag) = match completion_context(text, offset) {
+        Some(CompletionContext::Link(start)) => (start, false),
+        Some(CompletionContext::HashTag(start)) => (start, true),
+        None => return vec![],
+    };
+    let range = index.range(start..offset);
+    let item = |label: &str, new_text: String, kind: CompletionItemKind| CompletionItem {
+        label: label.into(),
+        kind: Some(kind),
+        text_edit: Some(CompletionTextEdit::Edit(TextEdit::new(range, new_text))),
+        ..Default::default()
+    };
+
+    let mut items = vec![];
+    if hashtag {
+        for tag in workspace.hashtags() {
+            items.push(item(tag, tag.into(), CompletionItemKind::KEYWORD));
+        }
+    }
+    for title in workspace.titles() {
+        let new_text = match hashtag && title.contains(char::is_whitespace) {
+            // #[multi word tag]
+            true => format!("[{}]", title),
+            false => title.into(),
+        };
+        if !items.iter().any(|i: &CompletionItem| i.label == title) {
+            items.push(item(title, new_text, CompletionItemKind::FILE));
+        }
+    }
+    items
+}
+
+/// Tokens in the order of the text. See `TOKEN_TYPES`.
+pub fn semantic_tokens(text: &str) -> Vec<SemanticToken> {
+    let index = LineIndex::new(text);
+    let mut tokens = vec![];
+    let mut prev = Position::default();
+    for item in analyze(text).items {
+        let token_type = match token_type(&item.kind) {
+            Some(token_type) => token_type,
+            None => continue,
+        };
+        let range = index.range(item.range);
+        if range.start.line != range.end.line {
+            continue;
+        }
+        let delta_line = range.start.line - prev.line;
+        tokens.push(SemanticToken {
+            delta_line,
+            delta_start: match delta_line {
+                0 => range.start.character - prev.character,
+                _ => range.start.character,
+            },
+            length: range.end.character - range.start.character,
+            token_type,
+            token_modifiers_bitset: 0,
+        });
+        prev = range.start;
+    }
+    tokens
+}
+
+/// `file:///path/to/a.sb`
+pub fn is_scrapbox(url: &Url) -> bool {
+    url.path()
+        .ends_with(&format!(".{}", crate::workspace::EXTENSION))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn diagnostics_test() {
+        let text = "title\n[link] [abc\n#tag]\n";
+        let diagnostics: Vec<_> = diagnostics(text)
+            .into_iter()
+            .map(|d| (d.range, d.severity, d.code))
+            .collect();
+        assert_eq!(
+            diagnostics,
+            vec![
+                (
+                    Range::new(Position::new(1, 7), Position::new(1, 8)),
+                    Some(DiagnosticSeverity::WARNING),
+                    Some(NumberOrString::String("unbalanced-bracket".into()))
+                ),
+                (
+                    Range::new(Position::new(2, 0), Position::new(2, 5)),
+                    Some(DiagnosticSeverity::WARNING),
+                    Some(NumberOrString::String("hashtag-bracket".into()))
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn document_symbols_test() {
+        let text = indoc! {"
+            title
+            code:top.js
+             a
+
+            [*** h3]
+            [** h2]
+            table:t
+             a\tb
+
+            [* h1]
+            [** h2']
+            code:b.js
+             b
+        "};
+        fn tree(symbols: &[DocumentSymbol]) -> Vec<(String, u32, u32)> {
+            symbols
+                .iter()
+                .flat_map(|s| {
+                    let mut v = vec![(s.name.clone(), s.range.start.line, s.range.end.line)];
+                    for child in tree(s.children.as_deref().unwrap_or_default()) {
+                        v.push((format!("  {}", child.0), child.1, child.2));
+                    }
+                    v
+                })
+                .collect()
+        }
+        assert_eq!(
+            tree(&document_symbols(text)),
+            vec![
+                ("code:top.js".into(), 1, 2),
+                ("h3".into(), 4, 13),
+                ("  h2".into(), 5, 10),
+                ("    table:t".into(), 6, 7),
+                ("    h1".into(), 9, 10),
+                ("  h2'".into(), 10, 13),
+                ("    code:b.js".into(), 11, 12),
+            ]
+        );
+    }
+
+    #[test]
+    fn semantic_tokens_test() {
+        let text = "title\n[link] あ #tag\n `c`\n";
+        let tokens: Vec<_> = semantic_tokens(text)
+            .into_iter()
+            .map(|t| (t.delta_line, t.delta_start, t.length, t.token_type))
+            .collect();
+        assert_eq!(tokens, vec![(1, 0, 6, 1), (0, 9, 4, 3), (1, 1, 3, 4)]);
+    }
+}
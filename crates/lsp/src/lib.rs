@@ -0,0 +1,393 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, DidSaveTextDocument,
+    Notification as _, PublishDiagnostics,
+};
+use lsp_types::request::{
+    Completion, DocumentSymbolRequest, GotoDefinition, Request as _, SemanticTokensFullRequest,
+};
+use lsp_types::{
+    CompletionOptions, CompletionParams, CompletionResponse, DidChangeTextDocumentParams,
+    DidCloseTextDocumentParams, DidOpenTextDocumentParams, DidSaveTextDocumentParams,
+    DocumentSymbolParams, DocumentSymbolResponse, GotoDefinitionParams, GotoDefinitionResponse,
+    InitializeParams, OneOf, PublishDiagnosticsParams, SemanticTokens, SemanticTokensFullOptions,
+    SemanticTokensLegend, SemanticTokensOptions, SemanticTokensParams, SemanticTokensResult,
+    SemanticTokensServerCapabilities, ServerCapabilities, TextDocumentSyncCapability,
+    TextDocumentSyncKind, Url,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+mod analysis;
+mod handlers;
+mod line_index;
+mod workspace;
+
+use workspace::Workspace;
+
+type BoxError = Box<dyn Error + Sync + Send>;
+
+pub fn capabilities() -> ServerCapabilities {
+    ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        definition_provider: Some(OneOf::Left(true)),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        completion_provider: Some(CompletionOptions {
+            trigger_characters: Some(vec!["[".into(), "#".into()]),
+            ..Default::default()
+        }),
+        semantic_tokens_provider: Some(SemanticTokensServerCapabilities::SemanticTokensOptions(
+            SemanticTokensOptions {
+                legend: SemanticTokensLegend {
+                    token_types: handlers::TOKEN_TYPES.to_vec(),
+                    token_modifiers: vec![],
+                },
+                full: Some(SemanticTokensFullOptions::Bool(true)),
+                ..Default::default()
+            },
+        )),
+        ..Default::default()
+    }
+}
+
+/// Serve a client until it sends `exit`.
+/// The `.sb` files under the root of the workspace are the pages to link.
+pub fn run(connection: Connection) -> Result<(), BoxError> {
+    let (id, params) = connection.initialize_start()?;
+    let params: InitializeParams = serde_json::from_value(params)?;
+    connection.initialize_finish(
+        id,
+        serde_json::json!({
+            "capabilities": capabilities(),
+            "serverInfo": { "name": "scrapbox-lsp", "version": env!("CARGO_PKG_VERSION") },
+        }),
+    )?;
+
+    let mut server = Server::default();
+    #[allow(deprecated)]
+    let roots = match params.workspace_folders {
+        Some(folders) => folders.into_iter().map(|f| f.uri).collect(),
+        None => params.root_uri.into_iter().collect::<Vec<_>>(),
+    };
+    for root in roots {
+        if let Ok(path) = root.to_file_path() {
+            server.workspace.scan(&path);
+        }
+    }
+
+    for message in &connection.receiver {
+        match message {
+            Message::Request(req) => {
+                if connection.handle_shutdown(&req)? {
+                    return Ok(());
+                }
+                connection.sender.send(server.handle_request(req).into())?;
+            }
+            Message::Notification(not) => {
+                for not in server.handle_notification(not) {
+                    connection.sender.send(not.into())?;
+                }
+            }
+            Message::Response(_) => {}
+        }
+    }
+    Ok(())
+}
+
+#[derive(Default)]
+struct Server {
+    /// The texts of the open files
+    documents: HashMap<Url, String>,
+    workspace: Workspace,
+}
+
+fn params<P: DeserializeOwned>(value: serde_json::Value) -> Result<P, serde_json::Error> {
+    serde_json::from_value(value)
+}
+
+fn to_value<T: Serialize>(value: T) -> serde_json::Value {
+    serde_json::to_value(value).expect("lsp types are always serializable")
+}
+
+impl Server {
+    /// The open file, or the file on the disk
+    fn text(&self, url: &Url) -> Option<String> {
+        match self.documents.get(url) {
+            Some(text) => Some(text.clone()),
+            None => fs::read_to_string(url.to_file_path().ok()?).ok(),
+        }
+    }
+
+    fn handle_request(&mut self, req: Request) -> Response {
+        let Request {
+            id,
+            method,
+            params: value,
+        } = req;
+        let result = match method.as_str() {
+            DocumentSymbolRequest::METHOD => params(value).map(|p: DocumentSymbolParams| {
+                let symbols = self
+                    .text(&p.text_document.uri)
+                    .map(|text| handlers::document_symbols(&text))
+                    .unwrap_or_default();
+                to_value(DocumentSymbolResponse::Nested(symbols))
+            }),
+            GotoDefinition::METHOD => params(value).map(|p: GotoDefinitionParams| {
+                let p = p.text_document_position_params;
+                let location = self
+                    .text(&p.text_document.uri)
+                    .and_then(|text| handlers::definition(&text, p.position, &self.workspace));
+                to_value(location.map(GotoDefinitionResponse::Scalar))
+            }),
+            Completion::METHOD => params(value).map(|p: CompletionParams| {
+                let p = p.text_document_position;
+                let items = self
+                    .text(&p.text_document.uri)
+                    .map(|text| handlers::completion(&text, p.position, &self.workspace))
+                    .unwrap_or_default();
+                to_value(CompletionResponse::Array(items))
+            }),
+            SemanticTokensFullRequest::METHOD => params(value).map(|p: SemanticTokensParams| {
+                let data = self
+                    .text(&p.text_document.uri)
+                    .map(|text| handlers::semantic_tokens(&text))
+                    .unwrap_or_default();
+                to_value(SemanticTokensResult::Tokens(SemanticTokens {
+                    result_id: None,
+                    data,
+                }))
+            }),
+            _ => {
+                return Response::new_err(
+                    id,
+                    ErrorCode::MethodNotFound as i32,
+                    format!("unknown method: {}", method),
+                )
+            }
+        };
+        match result {
+            Ok(result) => Response::new_ok(id, result),
+            Err(e) => Response::new_err(id, ErrorCode::InvalidParams as i32, e.to_string()),
+        }
+    }
+
+    /// Returns the notifications to send
+    fn handle_notification(&mut self, not: Notification) -> Vec<Notification> {
+        let Notification {
+            method,
+            params: value,
+        } = not;
+        let url = match method.as_str() {
+            DidOpenTextDocument::METHOD => match params::<DidOpenTextDocumentParams>(value) {
+                Ok(p) => self.open(p.text_document.uri, p.text_document.text),
+                Err(_) => None,
+            },
+            DidChangeTextDocument::METHOD => match params::<DidChangeTextDocumentParams>(value) {
+                // the sync is full, so the last change is the whole text
+                Ok(mut p) => match p.content_changes.pop() {
+                    Some(change) => self.open(p.text_document.uri, change.text),
+                    None => None,
+                },
+                Err(_) => None,
+            },
+            DidSaveTextDocument::METHOD => match params::<DidSaveTextDocumentParams>(value) {
+                Ok(p) => match p.text {
+                    Some(text) => self.open(p.text_document.uri, text),
+                    None => None,
+                },
+                Err(_) => None,
+            },
+            DidCloseTextDocument::METHOD => {
+                if let Ok(p) = params::<DidCloseTextDocumentParams>(value) {
+                    let url = p.text_document.uri;
+                    self.documents.remove(&url);
+                    // the closed text may not be saved
+                    if handlers::is_scrapbox(&url) {
+                        self.workspace.reload(url.clone());
+                    }
+                    return vec![publish_diagnostics(url, vec![])];
+                }
+                None
+            }
+            _ => None,
+        };
+
+        match url.and_then(|url| Some((self.documents.get(&url)?, url))) {
+            Some((text, url)) => vec![publish_diagnostics(
+                url.clone(),
+                handlers::diagnostics(text),
+            )],
+            None => vec![],
+        }
+    }
+
+    /// Returns the url if it's a Scrapbox file
+    fn open(&mut self, url: Url, text: String) -> Option<Url> {
+        if !handlers::is_scrapbox(&url) {
+            return None;
+        }
+        self.workspace.update(url.clone(), &text);
+        self.documents.insert(url.clone(), text);
+        Some(url)
+    }
+}
+
+fn publish_diagnostics(uri: Url, diagnostics: Vec<lsp_types::Diagnostic>) -> Notification {
+    Notification::new(
+        PublishDiagnostics::METHOD.into(),
+        PublishDiagnosticsParams {
+            uri,
+            diagnostics,
+            version: None,
+        },
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use lsp_server::RequestId;
+    use lsp_types::{Location, Position, Range};
+    use serde_json::json;
+
+    struct Client {
+        connection: Connection,
+        next_id: i32,
+    }
+
+    impl Client {
+        fn request(&mut self, method: &str, params: serde_json::Value) -> serde_json::Value {
+            self.next_id += 1;
+            let id = RequestId::from(self.next_id);
+            let req = Request::new(id.clone(), method.into(), params);
+            self.connection.sender.send(req.into()).unwrap();
+            for message in &self.connection.receiver {
+                if let Message::Response(res) = message {
+                    assert_eq!(res.id, id);
+                    assert!(res.error.is_none(), "{:?}", res.error);
+                    return res.result.unwrap_or_default();
+                }
+            }
+            panic!("no response to {}", method);
+        }
+
+        fn notify(&self, method: &str, params: serde_json::Value) {
+            let not = Notification::new(method.into(), params);
+            self.connection.sender.send(not.into()).unwrap();
+        }
+
+        fn notification(&self) -> Notification {
+            for message in &self.connection.receiver {
+                if let Message::Notification(not) = message {
+                    return not;
+                }
+            }
+            panic!("no notification");
+        }
+    }
+
+    #[test]
+    fn run_test() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("page_b.sb"), "Page B\n#tag\n").unwrap();
+        let root = Url::from_directory_path(dir.path()).unwrap();
+        let a = Url::from_file_path(dir.path().join("a.sb")).unwrap();
+        let b = Url::from_file_path(dir.path().join("page_b.sb")).unwrap();
+
+        let (server, client) = Connection::memory();
+        let thread = std::thread::spawn(move || run(server).unwrap());
+        let mut client = Client {
+            connection: client,
+            next_id: 0,
+        };
+
+        let res = client.request("initialize", json!({ "capabilities": {}, "rootUri": root }));
+        assert_eq!(res["capabilities"]["definitionProvider"], json!(true));
+        client.notify("initialized", json!({}));
+
+        client.notify(
+            "textDocument/didOpen",
+            json!({ "textDocument": {
+                "uri": a, "languageId": "scrapbox", "version": 1,
+                "text": "Page A\n[* heading]\n[page b] [abc\n[",
+            }}),
+        );
+        let not = client.notification();
+        assert_eq!(not.method, "textDocument/publishDiagnostics");
+        let diagnostics: PublishDiagnosticsParams = serde_json::from_value(not.params).unwrap();
+        assert_eq!(diagnostics.uri, a);
+        assert_eq!(diagnostics.diagnostics.len(), 2);
+
+        let position =
+            json!({ "textDocument": { "uri": a }, "position": { "line": 2, "character": 2 } });
+        let res = client.request("textDocument/definition", position);
+        let location: Location = serde_json::from_value(res).unwrap();
+        assert_eq!(location, Location::new(b, Range::default()));
+
+        let res = client.request(
+            "textDocument/completion",
+            json!({ "textDocument": { "uri": a }, "position": { "line": 3, "character": 1 } }),
+        );
+        let labels: Vec<_> = res
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| item["label"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(labels, vec!["Page A", "Page B"]);
+
+        let res = client.request(
+            "textDocument/documentSymbol",
+            json!({ "textDocument": { "uri": a } }),
+        );
+        assert_eq!(res[0]["name"], json!("heading"));
+        assert_eq!(
+            serde_json::from_value::<Range>(res[0]["range"].clone()).unwrap(),
+            Range::new(Position::new(1, 0), Position::new(3, 1))
+        );
+
+        let res = client.request(
+            "textDocument/semanticTokens/full",
+            json!({ "textDocument": { "uri": a } }),
+        );
+        assert_eq!(res["data"], json!([1, 0, 11, 0, 0, 1, 0, 8, 1, 0]));
+
+        // "a.sb" isn't saved, so its title isn't completed after it is closed
+        client.notify(
+            "textDocument/didClose",
+            json!({ "textDocument": { "uri": a } }),
+        );
+        let not = client.notification();
+        let diagnostics: PublishDiagnosticsParams = serde_json::from_value(not.params).unwrap();
+        assert_eq!((diagnostics.uri, diagnostics.diagnostics), (a, vec![]));
+
+        let c = Url::from_file_path(dir.path().join("c.sb")).unwrap();
+        client.notify(
+            "textDocument/didOpen",
+            json!({ "textDocument": {
+                "uri": c, "languageId": "scrapbox", "version": 1, "text": "[",
+            }}),
+        );
+        client.notification();
+        let res = client.request(
+            "textDocument/completion",
+            json!({ "textDocument": { "uri": c }, "position": { "line": 0, "character": 1 } }),
+        );
+        let labels: Vec<_> = res
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| item["label"].as_str().unwrap().to_string())
+            .collect();
+        // "[" is the title of "c.sb"
+        assert_eq!(labels, vec!["Page B", "["]);
+
+        client.request("shutdown", json!(null));
+        client.notify("exit", json!(null));
+        thread.join().unwrap();
+    }
+}
@@ -0,0 +1,83 @@
+use std::ops::Range;
+
+use lsp_types::Position;
+use scrapbox_converter_core::line_index;
+
+/// Converts the byte offsets of a text to the positions of LSP, whose character is in UTF-16.
+pub struct LineIndex<'a> {
+    text: &'a str,
+    lines: line_index::LineIndex<'a>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(text: &'a str) -> Self {
+        Self {
+            text,
+            lines: line_index::LineIndex::new(text),
+        }
+    }
+
+    pub fn position(&self, offset: usize) -> Position {
+        let offset = offset.min(self.text.len());
+        let line = self.lines.line(offset);
+        let start = self.lines.line_start(line).unwrap_or_default();
+        let character = self.text[start..offset].encode_utf16().count();
+        Position::new(line as u32, character as u32)
+    }
+
+    pub fn range(&self, range: Range<usize>) -> lsp_types::Range {
+        lsp_types::Range::new(self.position(range.start), self.position(range.end))
+    }
+
+    /// A position after the end of the line is the end of the line.
+    pub fn offset(&self, position: Position) -> usize {
+        let start = match self.lines.line_start(position.line as usize) {
+            Some(start) => start,
+            None => return self.text.len(),
+        };
+        let line = self.text[start..].split('\n').next().unwrap_or_default();
+        let mut utf16 = 0;
+        for (i, c) in line.char_indices() {
+            if utf16 >= position.character as usize {
+                return start + i;
+            }
+            utf16 += c.len_utf16();
+        }
+        start + line.len()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest(offset, expected,
+        case(0, Position::new(0, 0)),
+        case(3, Position::new(0, 3)),
+        case(4, Position::new(1, 0)),
+        // "あ" is 3 bytes and 1 UTF-16 unit
+        case(7, Position::new(1, 1)),
+        // "😀" is 4 bytes and 2 UTF-16 units
+        case(11, Position::new(1, 3)),
+        case(100, Position::new(1, 4)),
+    )]
+    fn position_test(offset: usize, expected: Position) {
+        let index = LineIndex::new("abc\nあ😀x");
+        assert_eq!(index.position(offset), expected);
+    }
+
+    #[rstest(
+        position,
+        expected,
+        case(Position::new(0, 0), 0),
+        case(Position::new(0, 10), 3),
+        case(Position::new(1, 1), 7),
+        case(Position::new(1, 3), 11),
+        case(Position::new(5, 0), 12)
+    )]
+    fn offset_test(position: Position, expected: usize) {
+        let index = LineIndex::new("abc\nあ😀x");
+        assert_eq!(index.offset(position), expected);
+    }
+}
@@ -0,0 +1,187 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+use lsp_types::Url;
+use scrapbox_converter_core::link::normalize_title;
+
+use crate::analysis::analyze;
+
+/// The extension of the Scrapbox files in the workspace
+pub const EXTENSION: &str = "sb";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Page {
+    /// The first line of the file
+    title: Option<String>,
+    /// The file name without the extension
+    stem: String,
+    hashtags: BTreeSet<String>,
+}
+
+/// The pages in the workspace, to find the file of a link and to complete titles and tags.
+#[derive(Debug, Default)]
+pub struct Workspace {
+    pages: BTreeMap<Url, Page>,
+}
+
+impl Workspace {
+    /// Read the `.sb` files under `root`. The files which can't be read and symlinks are skipped.
+    pub fn scan(&mut self, root: &Path) {
+        let entries = match fs::read_dir(root) {
+            Ok(entries) => entries,
+            Err(e) => {
+                log::warn!("failed to read {}: {}", root.display(), e);
+                return;
+            }
+        };
+        for entry in entries.flatten() {
+            // `file_type` doesn't follow a symlink, which may point to a parent
+            let file_type = match entry.file_type() {
+                Ok(file_type) => file_type,
+                Err(_) => continue,
+            };
+            let path = entry.path();
+            if file_type.is_dir() {
+                self.scan(&path);
+            } else if file_type.is_file() && path.extension().is_some_and(|ext| ext == EXTENSION) {
+                if let (Ok(text), Ok(url)) = (fs::read_to_string(&path), Url::from_file_path(&path))
+                {
+                    self.update(url, &text);
+                }
+            }
+        }
+    }
+
+    /// Index the text of the file at `url`.
+    pub fn update(&mut self, url: Url, text: &str) {
+        let analysis = analyze(text);
+        let stem = url
+            .to_file_path()
+            .ok()
+            .and_then(|path| Some(path.file_stem()?.to_string_lossy().into_owned()))
+            .unwrap_or_default();
+        let page = Page {
+            title: analysis.title.clone(),
+            stem,
+            hashtags: analysis.hashtags().map(str::to_string).collect(),
+        };
+        self.pages.insert(url, page);
+    }
+
+    /// Index the file at `url` as it is saved, when the unsaved text is closed.
+    /// A file which can't be read, like a new file which isn't saved, is dropped.
+    pub fn reload(&mut self, url: Url) {
+        match url.to_file_path().map(fs::read_to_string) {
+            Ok(Ok(text)) => self.update(url, &text),
+            _ => {
+                self.pages.remove(&url);
+            }
+        }
+    }
+
+    /// The file of the page `title`, found by the first line or the file name
+    pub fn resolve(&self, title: &str) -> Option<&Url> {
+        let title = normalize_title(title);
+        let by_title = self.pages.iter().find(|(_, page)| {
+            page.title
+                .as_deref()
+                .is_some_and(|t| normalize_title(t) == title)
+        });
+        by_title
+            .or_else(|| {
+                self.pages
+                    .iter()
+                    .find(|(_, page)| normalize_title(&page.stem) == title)
+            })
+            .map(|(url, _)| url)
+    }
+
+    pub fn titles(&self) -> BTreeSet<&str> {
+        self.pages
+            .values()
+            .map(|page| page.title.as_deref().unwrap_or(&page.stem))
+            .collect()
+    }
+
+    pub fn hashtags(&self) -> BTreeSet<&str> {
+        self.pages
+            .values()
+            .flat_map(|page| page.hashtags.iter().map(String::as_str))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn workspace_test() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        fs::write(dir.path().join("a.sb"), "Page A\n#tag1 [b]\n").unwrap();
+        fs::write(dir.path().join("sub/ページ_b.sb"), "\n#tag2\n").unwrap();
+        fs::write(dir.path().join("c.txt"), "Page C\n#tag3\n").unwrap();
+
+        let mut workspace = Workspace::default();
+        workspace.scan(dir.path());
+
+        let a = Url::from_file_path(dir.path().join("a.sb")).unwrap();
+        let b = Url::from_file_path(dir.path().join("sub/ページ_b.sb")).unwrap();
+        assert_eq!(workspace.resolve("page_a"), Some(&a));
+        assert_eq!(workspace.resolve("ページ B"), Some(&b));
+        assert_eq!(workspace.resolve("Page C"), None);
+        assert_eq!(
+            workspace.titles().into_iter().collect::<Vec<_>>(),
+            vec!["Page A", "ページ_b"]
+        );
+        assert_eq!(
+            workspace.hashtags().into_iter().collect::<Vec<_>>(),
+            vec!["tag1", "tag2"]
+        );
+
+        // an edit of an open file
+        workspace.update(a.clone(), "Page A2\n#tag3\n");
+        assert_eq!(workspace.resolve("Page A2"), Some(&a));
+        assert_eq!(
+            workspace.hashtags().into_iter().collect::<Vec<_>>(),
+            vec!["tag2", "tag3"]
+        );
+
+        // the edit is closed without saving
+        workspace.reload(a.clone());
+        assert_eq!(workspace.resolve("Page A"), Some(&a));
+        assert_eq!(workspace.resolve("Page A2"), None);
+
+        // a new file is closed without saving
+        let d = Url::from_file_path(dir.path().join("d.sb")).unwrap();
+        workspace.update(d.clone(), "Page D\n#tag4\n");
+        workspace.reload(d);
+        assert_eq!(workspace.resolve("Page D"), None);
+        assert_eq!(
+            workspace.hashtags().into_iter().collect::<Vec<_>>(),
+            vec!["tag1", "tag2"]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn scan_symlink_test() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        fs::write(dir.path().join("sub/a.sb"), "Page A\n").unwrap();
+        // a loop, and a link to a page
+        std::os::unix::fs::symlink(dir.path(), dir.path().join("sub/loop")).unwrap();
+        std::os::unix::fs::symlink(dir.path().join("sub/a.sb"), dir.path().join("b.sb")).unwrap();
+
+        let mut workspace = Workspace::default();
+        workspace.scan(dir.path());
+
+        assert_eq!(workspace.pages.len(), 1);
+        assert_eq!(
+            workspace.resolve("Page A"),
+            Some(&Url::from_file_path(dir.path().join("sub/a.sb")).unwrap())
+        );
+    }
+}
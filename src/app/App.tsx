@@ -19,9 +19,8 @@ import React, { useEffect, useState } from "react";
 import {
   useWasm,
   scrapboxToMarkdown,
-  scrapboxToAST,
   markdownToScrapbox,
-  markdownToAST,
+  toAST,
 } from "../main";
 import { ConfigModal, defaultConfig } from "./ConfigModal";
 import { defaultScrapboxData, defaultMarkdownData } from "./data";
@@ -99,7 +98,7 @@ function App() {
           setDst(dst);
         },
         () => {
-          const dst = scrapboxToAST(scrapboxSrc, config);
+          const dst = toAST(scrapboxSrc, "Scrapbox", config);
           setDst(dst);
         },
       ],
@@ -115,7 +114,7 @@ function App() {
           setDst(markdownSrc);
         },
         () => {
-          const dst = markdownToAST(markdownSrc, config);
+          const dst = toAST(markdownSrc, "Markdown", config);
           setDst(dst);
         },
       ],
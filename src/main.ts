@@ -1,47 +1,97 @@
 import { useEffect, useState } from "react";
 
 import initCore, {
-  scrapboxToMarkdown as scrapboxToMarkdownCore,
-  scrapboxToAST as scrapboxToASTCore,
   markdownToScrapbox as markdownToScrapboxCore,
-  markdownToAST as markdownToASTCore,
+  scrapboxToOrg as scrapboxToOrgCore,
+  orgToScrapbox as orgToScrapboxCore,
+  toAST as toASTCore,
+  astToScrapbox as astToScrapboxCore,
+  astToMarkdown as astToMarkdownCore,
+  formatScrapbox as formatScrapboxCore,
+  ScrapboxDocument,
   Config,
+  FormatConfig,
+  SourceFormat,
 } from "@@/scrapbox_converter_demo";
 
 export const init = async () => {
   await initCore();
 };
 
+// parses again only the edited lines of the form
+let scrapboxDocument: ScrapboxDocument | undefined;
+
 export const scrapboxToMarkdown = (input: string, config: Config): string => {
   try {
-    return scrapboxToMarkdownCore(input, config);
+    scrapboxDocument ??= new ScrapboxDocument();
+    return scrapboxDocument.scrapboxToMarkdown(input, config);
   } catch (error) {
     console.error(error);
     return "";
   }
 };
 
-export const scrapboxToAST = (input: string, config: Config): string => {
+export const markdownToScrapbox = (input: string, config: Config): string => {
   try {
-    return scrapboxToASTCore(input, config);
+    return markdownToScrapboxCore(input, config);
   } catch (error) {
     console.error(error);
     return "";
   }
 };
 
-export const markdownToScrapbox = (input: string, config: Config): string => {
+export const scrapboxToOrg = (input: string, config: Config): string => {
   try {
-    return markdownToScrapboxCore(input, config);
+    return scrapboxToOrgCore(input, config);
+  } catch (error) {
+    console.error(error);
+    return "";
+  }
+};
+
+export const orgToScrapbox = (input: string, config: Config): string => {
+  try {
+    return orgToScrapboxCore(input, config);
+  } catch (error) {
+    console.error(error);
+    return "";
+  }
+};
+
+export const toAST = (
+  input: string,
+  sourceFormat: SourceFormat,
+  config: Config
+): string => {
+  try {
+    return toASTCore(input, sourceFormat, config);
+  } catch (error) {
+    console.error(error);
+    return "";
+  }
+};
+
+export const astToScrapbox = (input: string, config: Config): string => {
+  try {
+    return astToScrapboxCore(input, config);
+  } catch (error) {
+    console.error(error);
+    return "";
+  }
+};
+
+export const astToMarkdown = (input: string, config: Config): string => {
+  try {
+    return astToMarkdownCore(input, config);
   } catch (error) {
     console.error(error);
     return "";
   }
 };
 
-export const markdownToAST = (input: string, config: Config): string => {
+export const formatScrapbox = (input: string, config: FormatConfig): string => {
   try {
-    return markdownToASTCore(input, config);
+    return formatScrapboxCore(input, config);
   } catch (error) {
     console.error(error);
     return "";